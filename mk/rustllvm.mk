@@ -25,7 +25,7 @@ endif
 
 RUSTLLVM_OBJS_CS_$(1) := $$(addprefix rustllvm/, \
 	RustWrapper.cpp PassWrapper.cpp \
-	ArchiveWrapper.cpp)
+	ArchiveWrapper.cpp CoverageWrapper.cpp)
 
 RUSTLLVM_INCS_$(1) = $$(LLVM_EXTRA_INCDIRS_$(1)) \
                      $$(call CFG_CC_INCLUDE_$(1),$$(LLVM_INCDIR_$(1))) \
@@ -203,6 +203,10 @@ pub fn compiletest(build: &Build,
         cmd.arg("--quiet");
     }
 
+    if build.config.profiler {
+        cmd.arg("--profiler-support");
+    }
+
     // Only pass correct values for these flags for the `run-make` suite as it
     // requires that a C++ compiler was configured which isn't always the case.
     if suite == "run-make" {
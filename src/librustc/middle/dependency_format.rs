@@ -103,7 +103,7 @@ pub fn calculate(sess: &session::Session) {
 
 fn calculate_type(sess: &session::Session,
                   ty: config::CrateType) -> DependencyList {
-    if !sess.opts.output_types.should_trans() {
+    if sess.opts.metadata_only() {
         return Vec::new();
     }
 
@@ -12,6 +12,7 @@ use ty::AdtKind;
 use ty::layout::{Align, Size};
 
 use rustc_data_structures::fx::{FxHashSet};
+use serialize::json;
 
 use std::cmp::{self, Ordering};
 
@@ -63,13 +64,22 @@ pub struct TypeSizeInfo {
     pub variants: Vec<VariantInfo>,
 }
 
+#[derive(PartialEq, Eq, Hash, Debug, RustcEncodable)]
+pub struct FnSizeInfo {
+    pub name: String,
+    pub symbol_name: String,
+    pub stack_size: u64,
+    pub code_size: Option<u64>,
+}
+
 #[derive(PartialEq, Eq, Debug)]
 pub struct CodeStats {
     type_sizes: FxHashSet<TypeSizeInfo>,
+    fn_sizes: FxHashSet<FnSizeInfo>,
 }
 
 impl CodeStats {
-    pub fn new() -> Self { CodeStats { type_sizes: FxHashSet() } }
+    pub fn new() -> Self { CodeStats { type_sizes: FxHashSet(), fn_sizes: FxHashSet() } }
 
     pub fn record_type_size<S: ToString>(&mut self,
                                          kind: DataTypeKind,
@@ -89,6 +99,46 @@ impl CodeStats {
         self.type_sizes.insert(info);
     }
 
+    pub fn record_fn_size(&mut self,
+                          name: String,
+                          symbol_name: String,
+                          stack_size: u64,
+                          code_size: Option<u64>) {
+        self.fn_sizes.insert(FnSizeInfo {
+            name: name,
+            symbol_name: symbol_name,
+            stack_size: stack_size,
+            code_size: code_size,
+        });
+    }
+
+    pub fn print_fn_sizes(&self, json: bool) {
+        let mut sorted: Vec<_> = self.fn_sizes.iter().collect();
+
+        // Primary sort: stack size, large-to-small.
+        // Secondary sort: code size, large-to-small.
+        // Tertiary sort: symbol name (dictionary order)
+        sorted.sort_by(|info1, info2| {
+            // (reversing cmp order of the sizes to get large-to-small ordering)
+            (info2.stack_size, info2.code_size, &info1.symbol_name)
+                .cmp(&(info1.stack_size, info1.code_size, &info2.symbol_name))
+        });
+
+        if json {
+            println!("{}", json::as_json(&sorted));
+            return
+        }
+
+        for info in &sorted {
+            let code_size = match info.code_size {
+                Some(size) => format!("{} bytes", size),
+                None => "unknown".to_string(),
+            };
+            println!("print-fn-size fn: `{}`: stack: {} bytes, code: {}",
+                     info.name, info.stack_size, code_size);
+        }
+    }
+
     pub fn print_type_sizes(&self) {
         let mut sorted: Vec<_> = self.type_sizes.iter().collect();
 
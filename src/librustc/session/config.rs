@@ -25,6 +25,7 @@ use lint;
 use middle::cstore;
 
 use syntax::ast::{self, IntTy, UintTy};
+use syntax::codemap::FilePathMapping;
 use syntax::parse::token;
 use syntax::parse;
 use syntax::symbol::Symbol;
@@ -61,6 +62,20 @@ pub enum OptLevel {
     SizeMin, // -Oz
 }
 
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum Lto {
+    /// Don't do any LTO whatsoever
+    No,
+
+    /// Link all modules, including those of upstream rlibs, into a single
+    /// module and optimize it as a whole
+    Fat,
+
+    /// Do summary-based cross-module optimization of all modules, including
+    /// those of upstream rlibs, optimizing each one of them independently
+    Thin,
+}
+
 #[derive(Clone, Copy, PartialEq, Hash)]
 pub enum DebugInfoLevel {
     NoDebugInfo,
@@ -311,6 +326,10 @@ top_level_options!(
         // is currently just a hack and will be removed eventually, so please
         // try to not rely on this too much.
         actually_rustdoc: bool [TRACKED],
+
+        // Remap source path prefixes in all output (messages, object files,
+        // debuginfo, metadata, ...), in the order given on the command line.
+        remap_path_prefix: Vec<(String, String)> [TRACKED],
     }
 );
 
@@ -464,10 +483,16 @@ pub fn basic_options() -> Options {
         unstable_features: UnstableFeatures::Disallow,
         debug_assertions: true,
         actually_rustdoc: false,
+        remap_path_prefix: Vec::new(),
     }
 }
 
 impl Options {
+    /// The path remapping to apply to the names of all source files.
+    pub fn file_path_mapping(&self) -> FilePathMapping {
+        FilePathMapping::new(self.remap_path_prefix.clone())
+    }
+
     /// True if there is a reason to build the dep graph.
     pub fn build_dep_graph(&self) -> bool {
         self.incremental.is_some() ||
@@ -479,6 +504,15 @@ impl Options {
         self.incremental.is_none() ||
         self.cg.codegen_units == 1
     }
+
+    /// True if nothing needs to be translated, i.e. at most the crate's
+    /// metadata is written. No LLVM work is done at all in that case.
+    ///
+    /// This isn't the case for `-Z no-trans`, which still writes the usual
+    /// outputs, just without any code in them.
+    pub fn metadata_only(&self) -> bool {
+        !self.output_types.should_trans()
+    }
 }
 
 // The type of entry function, so
@@ -626,12 +660,16 @@ macro_rules! options {
             Some("a number");
         pub const parse_panic_strategy: Option<&'static str> =
             Some("either `panic` or `abort`");
+        pub const parse_opt_pathbuf: Option<&'static str> = Some("a path");
+        pub const parse_lto: Option<&'static str> =
+            Some("one of `thin`, `fat`, or omitted");
     }
 
     #[allow(dead_code)]
     mod $mod_set {
-        use super::{$struct_name, Passes, SomePasses, AllPasses};
+        use super::{$struct_name, Passes, SomePasses, AllPasses, Lto};
         use rustc_back::PanicStrategy;
+        use std::path::PathBuf;
 
         $(
             pub fn $opt(cg: &mut $struct_name, v: Option<&str>) -> bool {
@@ -672,6 +710,13 @@ macro_rules! options {
             }
         }
 
+        fn parse_opt_pathbuf(slot: &mut Option<PathBuf>, v: Option<&str>) -> bool {
+            match v {
+                Some(s) => { *slot = Some(PathBuf::from(s)); true },
+                None => false,
+            }
+        }
+
         fn parse_string(slot: &mut String, v: Option<&str>) -> bool {
             match v {
                 Some(s) => { *slot = s.to_string(); true },
@@ -743,6 +788,15 @@ macro_rules! options {
             }
         }
 
+        fn parse_lto(slot: &mut Lto, v: Option<&str>) -> bool {
+            *slot = match v {
+                None | Some("fat") => Lto::Fat,
+                Some("thin") => Lto::Thin,
+                _ => return false,
+            };
+            true
+        }
+
         fn parse_panic_strategy(slot: &mut Option<PanicStrategy>, v: Option<&str>) -> bool {
             match v {
                 Some("unwind") => *slot = Some(PanicStrategy::Unwind),
@@ -767,8 +821,9 @@ options! {CodegenOptions, CodegenSetter, basic_codegen_options,
         "extra arguments to pass to the linker (space separated)"),
     link_dead_code: bool = (false, parse_bool, [UNTRACKED],
         "don't let linker strip dead code (turning it on can be used for code coverage)"),
-    lto: bool = (false, parse_bool, [TRACKED],
-        "perform LLVM link-time optimizations"),
+    lto: Lto = (Lto::No, parse_lto, [TRACKED],
+        "perform LLVM link-time optimizations, either across all modules at once \
+         (`-C lto` or `-C lto=fat`) or summary-based and in parallel (`-C lto=thin`)"),
     target_cpu: Option<String> = (None, parse_opt_string, [TRACKED],
         "select target processor (rustc --print target-cpus for details)"),
     target_feature: String = ("".to_string(), parse_string, [TRACKED],
@@ -822,6 +877,11 @@ options! {CodegenOptions, CodegenSetter, basic_codegen_options,
         "set the inlining threshold for"),
     panic: Option<PanicStrategy> = (None, parse_panic_strategy,
         [TRACKED], "panic strategy to compile crate with"),
+    profile_generate: Option<PathBuf> = (None, parse_opt_pathbuf, [TRACKED],
+        "instrument the generated code to collect profiling data into the given \
+         directory, for use with `-C profile-use`"),
+    profile_use: Option<PathBuf> = (None, parse_opt_pathbuf, [TRACKED],
+        "use the given merged `.profdata` file to guide optimizations"),
 }
 
 options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
@@ -868,6 +928,10 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "print the pre-expansion AST as JSON and halt"),
     ls: bool = (false, parse_bool, [UNTRACKED],
         "list the symbols defined by a library crate"),
+    ls_json: bool = (false, parse_bool, [UNTRACKED],
+        "dump the decoded metadata of a library crate as JSON"),
+    emit_artifact_notifications: bool = (false, parse_bool, [UNTRACKED],
+        "print a line of JSON to stderr as soon as an output file has been written"),
     save_analysis: bool = (false, parse_bool, [UNTRACKED],
         "write syntax and type analysis (in JSON format) information, in \
          addition to normal output"),
@@ -876,6 +940,11 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
     save_analysis_api: bool = (false, parse_bool, [UNTRACKED],
         "write syntax and type analysis information for opaque libraries (in JSON format), \
          in addition to normal output"),
+    save_analysis_api_private: bool = (false, parse_bool, [UNTRACKED],
+        "include private items in the output of `-Z save-analysis-api`"),
+    save_analysis_stream: bool = (false, parse_bool, [UNTRACKED],
+        "write syntax and type analysis information as newline-delimited JSON records, \
+         in addition to normal output"),
     print_move_fragments: bool = (false, parse_bool, [UNTRACKED],
         "print out move-fragment data for every fn"),
     flowgraph_print_loans: bool = (false, parse_bool, [UNTRACKED],
@@ -903,8 +972,23 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "enable cross-crate incremental compilation (even more experimental)"),
     incremental_info: bool = (false, parse_bool, [UNTRACKED],
         "print high-level information about incremental reuse (or the lack thereof)"),
+    incremental_explain: bool = (false, parse_bool, [UNTRACKED],
+        "print which codegen units were re-used and, for the others, which changed inputs \
+         caused them to be rebuilt"),
     incremental_dump_hash: bool = (false, parse_bool, [UNTRACKED],
         "dump hash information in textual format to stdout"),
+    incremental_max_size: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "maximum size (in megabytes) of the incremental compilation directory; the caches \
+         of the least recently compiled crates are deleted when it is exceeded"),
+    incremental_max_age: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "delete the caches of crates that haven't been compiled for this many days from \
+         the incremental compilation directory"),
+    incremental_gc_min_age: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "never garbage collect the caches of crates compiled less than this many seconds \
+         ago (default: 10)"),
+    incremental_gc: Option<String> = (None, parse_opt_string, [UNTRACKED],
+        "garbage collect the given incremental compilation directory according to \
+         -Z incremental-max-size and -Z incremental-max-age, without compiling anything"),
     dump_dep_graph: bool = (false, parse_bool, [UNTRACKED],
           "dump the dependency graph to $RUST_DEP_GRAPH (default: /tmp/dep_graph.gv)"),
     query_dep_graph: bool = (false, parse_bool, [UNTRACKED],
@@ -931,6 +1015,8 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "show spans for compiler debugging (expr|pat|ty)"),
     print_type_sizes: bool = (false, parse_bool, [UNTRACKED],
           "print layout information for each type encountered"),
+    print_fn_sizes: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "print the stack frame and machine code size of each function (text|json)"),
     print_trans_items: Option<String> = (None, parse_opt_string, [UNTRACKED],
           "print the result of the translation item collection pass"),
     mir_opt_level: usize = (1, parse_uint, [TRACKED],
@@ -949,6 +1035,9 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "encode MIR of all functions into the crate metadata"),
     osx_rpath_install_name: bool = (false, parse_bool, [TRACKED],
           "pass `-install_name @rpath/...` to the OSX linker"),
+    instrument_coverage: bool = (false, parse_bool, [TRACKED],
+          "instrument the generated code with coverage counters and emit an LLVM \
+           coverage mapping, for use with `llvm-cov`"),
 }
 
 pub fn default_lib_output() -> CrateType {
@@ -1241,6 +1330,9 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
                            `hir` (the HIR), `hir,identified`, or
                            `hir,typed` (HIR with types for each node).",
                           "TYPE"),
+        opt::multi("", "remap-path-prefix", "Remap source names in all output \
+                                             (compiler messages and output files)",
+                   "FROM=TO"),
 
         // new options here should **not** use the `_ubnr` functions, all new
         // unstable options should use the short variants to indicate that they
@@ -1391,6 +1483,42 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
         early_error(error_format, "Value for codegen units must be a positive nonzero integer");
     }
 
+    if cg.profile_generate.is_some() && cg.profile_use.is_some() {
+        early_error(error_format, "options `-C profile-generate` and `-C profile-use` \
+                                   are exclusive");
+    }
+
+    if debugging_opts.instrument_coverage && cg.profile_generate.is_some() {
+        early_error(error_format, "options `-Z instrument-coverage` and `-C profile-generate` \
+                                   are exclusive");
+    }
+
+    if let Some(ref path) = cg.profile_use {
+        if !path.is_file() {
+            early_error(error_format, &format!("file `{}` passed to `-C profile-use` \
+                                                does not exist", path.display()));
+        }
+    }
+
+    if debugging_opts.incremental_gc.is_some() {
+        if debugging_opts.incremental_max_size.is_none() &&
+           debugging_opts.incremental_max_age.is_none() {
+            early_error(error_format, "`-Z incremental-gc` requires `-Z incremental-max-size` \
+                                       or `-Z incremental-max-age`");
+        }
+        if !matches.free.is_empty() {
+            early_error(error_format, "`-Z incremental-gc` does not take an input file");
+        }
+    }
+
+    match debugging_opts.print_fn_sizes.as_ref().map(|s| &s[..]) {
+        None | Some("text") | Some("json") => {}
+        Some(other) => {
+            early_error(error_format, &format!("unknown format `{}` for `-Z print-fn-sizes`, \
+                                                expected `text` or `json`", other))
+        }
+    }
+
     let mut prints = Vec::<PrintRequest>::new();
     if cg.target_cpu.as_ref().map_or(false, |s| s == "help") {
         prints.push(PrintRequest::TargetCPUs);
@@ -1534,6 +1662,19 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
 
     let crate_name = matches.opt_str("crate-name");
 
+    let remap_path_prefix = matches.opt_strs("remap-path-prefix").into_iter().map(|remap| {
+        // The last `=` separates the two paths, the prefix being remapped
+        // may contain one as well.
+        let mut parts = remap.rsplitn(2, '=');
+        let to = parts.next();
+        let from = parts.next();
+        match (from, to) {
+            (Some(from), Some(to)) => (from.to_string(), to.to_string()),
+            _ => early_error(error_format,
+                             "--remap-path-prefix must contain '=' between FROM and TO"),
+        }
+    }).collect();
+
     let incremental = debugging_opts.incremental.as_ref().map(|m| PathBuf::from(m));
 
     (Options {
@@ -1560,6 +1701,7 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
         unstable_features: UnstableFeatures::from_environment(),
         debug_assertions: debug_assertions,
         actually_rustdoc: false,
+        remap_path_prefix: remap_path_prefix,
     },
     cfg)
 }
@@ -1699,7 +1841,7 @@ mod dep_tracking {
     use std::hash::Hash;
     use std::path::PathBuf;
     use std::collections::hash_map::DefaultHasher;
-    use super::{Passes, CrateType, OptLevel, DebugInfoLevel,
+    use super::{Passes, CrateType, OptLevel, DebugInfoLevel, Lto,
                 OutputTypes, Externs, ErrorOutputType};
     use syntax::feature_gate::UnstableFeatures;
     use rustc_back::PanicStrategy;
@@ -1748,11 +1890,14 @@ mod dep_tracking {
     impl_dep_tracking_hash_via_hash!(PanicStrategy);
     impl_dep_tracking_hash_via_hash!(Passes);
     impl_dep_tracking_hash_via_hash!(OptLevel);
+    impl_dep_tracking_hash_via_hash!(Lto);
     impl_dep_tracking_hash_via_hash!(DebugInfoLevel);
     impl_dep_tracking_hash_via_hash!(UnstableFeatures);
     impl_dep_tracking_hash_via_hash!(Externs);
     impl_dep_tracking_hash_via_hash!(OutputTypes);
     impl_dep_tracking_hash_via_hash!(cstore::NativeLibraryKind);
+    // The order of the remappings matters, don't sort them.
+    impl_dep_tracking_hash_via_hash!(Vec<(String, String)>);
 
     impl_dep_tracking_hash_for_sortable_vec_of!(String);
     impl_dep_tracking_hash_for_sortable_vec_of!(CrateType);
@@ -1823,7 +1968,7 @@ mod tests {
     use std::iter::FromIterator;
     use std::path::PathBuf;
     use std::rc::Rc;
-    use super::{OutputType, OutputTypes, Externs};
+    use super::{OutputType, OutputTypes, Externs, Lto};
     use rustc_back::PanicStrategy;
     use syntax::symbol::Symbol;
 
@@ -2088,6 +2233,25 @@ mod tests {
         assert_eq!(v2.dep_tracking_hash(), v2.clone().dep_tracking_hash());
     }
 
+    #[test]
+    fn test_remap_path_prefix_tracking_hash_different_order() {
+        let mut v1 = super::basic_options();
+        let mut v2 = super::basic_options();
+
+        // Later remappings take precedence, so the order is significant.
+        v1.remap_path_prefix = vec![(String::from("/a"), String::from("/x")),
+                                    (String::from("/a/b"), String::from("/y"))];
+
+        v2.remap_path_prefix = vec![(String::from("/a/b"), String::from("/y")),
+                                    (String::from("/a"), String::from("/x"))];
+
+        assert!(v1.dep_tracking_hash() != v2.dep_tracking_hash());
+
+        // Check clone
+        assert_eq!(v1.dep_tracking_hash(), v1.clone().dep_tracking_hash());
+        assert_eq!(v2.dep_tracking_hash(), v2.clone().dep_tracking_hash());
+    }
+
     #[test]
     fn test_search_paths_tracking_hash_different_values() {
         let mut v1 = super::basic_options();
@@ -2298,7 +2462,11 @@ mod tests {
 
         // Make sure changing a [TRACKED] option changes the hash
         opts = reference.clone();
-        opts.cg.lto = true;
+        opts.cg.lto = Lto::Fat;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.cg.lto = Lto::Thin;
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
 
         opts = reference.clone();
@@ -2376,6 +2544,14 @@ mod tests {
         opts = reference.clone();
         opts.cg.panic = Some(PanicStrategy::Abort);
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.cg.profile_generate = Some(PathBuf::from("abc"));
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.cg.profile_use = Some(PathBuf::from("abc.profdata"));
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
     }
 
     #[test]
@@ -2412,12 +2588,18 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.ls = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.ls_json = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.emit_artifact_notifications = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.save_analysis = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.save_analysis_csv = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.save_analysis_api = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.save_analysis_stream = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.print_move_fragments = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.flowgraph_print_loans = true;
@@ -2450,6 +2632,8 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.print_trans_items = Some(String::from("abc"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.print_fn_sizes = Some(String::from("json"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir = Some(String::from("abc"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir_dir = Some(String::from("abc"));
@@ -2499,5 +2683,9 @@ mod tests {
         opts = reference.clone();
         opts.debugging_opts.mir_opt_level = 3;
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.instrument_coverage = true;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
     }
 }
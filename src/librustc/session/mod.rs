@@ -46,11 +46,12 @@ use std::cell::{self, Cell, RefCell};
 use std::collections::HashMap;
 use std::env;
 use std::ffi::CString;
-use std::io::Write;
+use std::io::{self, Write};
 use std::rc::Rc;
 use std::fmt;
 use std::time::Duration;
 use libc::c_int;
+use serialize::json;
 
 mod code_stats;
 pub mod config;
@@ -73,10 +74,12 @@ pub struct Session {
     pub derive_registrar_fn: Cell<Option<ast::NodeId>>,
     pub default_sysroot: Option<PathBuf>,
     // The name of the root source file of the crate, in the local file system.
-    // The path is always expected to be absolute. `None` means that there is no
-    // source file.
+    // The path is always expected to be absolute, unless it was made relative
+    // by `--remap-path-prefix`. `None` means that there is no source file.
     pub local_crate_source_file: Option<PathBuf>,
-    pub working_dir: PathBuf,
+    // The directory the compiler has been executed in, with the path
+    // remapping applied, and whether it was affected by the remapping.
+    pub working_dir: (String, bool),
     pub lint_store: RefCell<lint::LintStore>,
     pub lints: RefCell<NodeMap<Vec<lint::EarlyLint>>>,
     /// Set of (LintId, span, message) tuples tracking lint (sub)diagnostics
@@ -362,8 +365,14 @@ impl Session {
     pub fn print_llvm_passes(&self) -> bool {
         self.opts.debugging_opts.print_llvm_passes
     }
+    /// Whether any kind of LTO is performed, meaning that the Rust code of
+    /// upstream rlibs is pulled into the local crate instead of being linked
+    /// in directly.
     pub fn lto(&self) -> bool {
-        self.opts.cg.lto
+        self.opts.cg.lto != config::Lto::No
+    }
+    pub fn thin_lto(&self) -> bool {
+        self.opts.cg.lto == config::Lto::Thin
     }
     /// Returns the panic strategy for this compile session. If the user explicitly selected one
     /// using '-C panic', use that, otherwise use the panic strategy defined by the target.
@@ -506,6 +515,27 @@ impl Session {
         println!("Total time spent decoding DefPath tables:      {}",
                  duration_to_secs_str(self.perf_stats.decode_def_path_tables_time.get()));
     }
+
+    /// With `-Z emit-artifact-notifications`, tells whoever is driving the
+    /// build that `path` has been written by printing a line of JSON to stderr.
+    /// A build system can use this to start on dependent crates as soon as the
+    /// metadata is available, while this crate is still being translated.
+    pub fn emit_artifact_notification(&self, path: &Path, emit: &'static str) {
+        if !self.opts.debugging_opts.emit_artifact_notifications {
+            return;
+        }
+        let notification = ArtifactNotification {
+            artifact: path.display().to_string(),
+            emit: emit,
+        };
+        let _ = writeln!(io::stderr(), "{}", json::as_json(&notification));
+    }
+}
+
+#[derive(RustcEncodable)]
+struct ArtifactNotification {
+    artifact: String,
+    emit: &'static str,
 }
 
 pub fn build_session(sopts: config::Options,
@@ -514,12 +544,14 @@ pub fn build_session(sopts: config::Options,
                      registry: errors::registry::Registry,
                      cstore: Rc<for<'a> CrateStore<'a>>)
                      -> Session {
+    let file_path_mapping = sopts.file_path_mapping();
+
     build_session_with_codemap(sopts,
                                dep_graph,
                                local_crate_source_file,
                                registry,
                                cstore,
-                               Rc::new(codemap::CodeMap::new()),
+                               Rc::new(codemap::CodeMap::new(file_path_mapping)),
                                None)
 }
 
@@ -592,14 +624,20 @@ pub fn build_session_(sopts: config::Options,
         None => Some(filesearch::get_or_default_sysroot())
     };
 
+    let file_path_mapping = sopts.file_path_mapping();
+
     // Make the path absolute, if necessary
-    let local_crate_source_file = local_crate_source_file.map(|path|
-        if path.is_absolute() {
+    let local_crate_source_file = local_crate_source_file.map(|path| {
+        let path = if path.is_absolute() {
             path.clone()
         } else {
             env::current_dir().unwrap().join(&path)
-        }
-    );
+        };
+        PathBuf::from(file_path_mapping.map_prefix(path.to_string_lossy().into_owned()).0)
+    });
+
+    let working_dir = env::current_dir().unwrap().to_string_lossy().into_owned();
+    let working_dir = file_path_mapping.map_prefix(working_dir);
 
     let sess = Session {
         dep_graph: dep_graph.clone(),
@@ -615,7 +653,7 @@ pub fn build_session_(sopts: config::Options,
         derive_registrar_fn: Cell::new(None),
         default_sysroot: default_sysroot,
         local_crate_source_file: local_crate_source_file,
-        working_dir: env::current_dir().unwrap(),
+        working_dir: working_dir,
         lint_store: RefCell::new(lint::LintStore::new()),
         lints: RefCell::new(NodeMap()),
         one_time_diagnostics: RefCell::new(FxHashSet()),
@@ -709,6 +747,9 @@ unsafe fn configure_llvm(sess: &Session) {
         add("rustc"); // fake program name
         if sess.time_llvm_passes() { add("-time-passes"); }
         if sess.print_llvm_passes() { add("-debug-pass=Structure"); }
+        // LLVM only reports the stack frame size of functions exceeding this
+        // threshold, so lower it to hear about every function.
+        if sess.opts.debugging_opts.print_fn_sizes.is_some() { add("-warn-stack-size=0"); }
 
         for arg in &sess.opts.cg.llvm_args {
             add(&(*arg));
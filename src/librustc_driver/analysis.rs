@@ -0,0 +1,131 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A higher-level entry point for tools that want to inspect a crate rather
+//! than compile it.
+//!
+//! `run_analysis` takes ordinary command line arguments, runs the compiler up
+//! to and including type checking and then hands the type context to a
+//! closure, so linters and analyzers don't need to build their own
+//! `CompileController`. Sources which only exist in memory can be supplied
+//! through a `FileLoader`, for example an `InMemoryFileLoader`.
+
+use driver::CompileController;
+use {CompilerCalls, Compilation, run_compiler};
+
+use rustc::session::{CompileResult, Session};
+use rustc::ty::{self, TyCtxt};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use getopts;
+use syntax::codemap::{FileLoader, RealFileLoader};
+
+/// Runs the compiler with `args` (which, like `std::env::args()`, start with
+/// the program name) as far as type checking, then calls `analyze` with the
+/// type context of the crate.
+///
+/// Nothing is translated or written to disk. `analyze` isn't called if the
+/// crate has errors, or if `args` don't ask for a compilation at all (e.g.
+/// `--help`). The first element of the returned pair is `Ok` unless the
+/// compiler reported errors, in which case it holds their number; the second
+/// is what `analyze` returned, if it ran.
+pub fn run_analysis<F, R>(args: &[String],
+                          file_loader: Option<Box<FileLoader + 'static>>,
+                          analyze: F)
+                          -> (CompileResult, Option<R>)
+    where F: for<'a, 'tcx> FnOnce(TyCtxt<'a, 'tcx, 'tcx>, &ty::CrateAnalysis<'tcx>) -> R
+{
+    let analyze = RefCell::new(Some(analyze));
+    let output = RefCell::new(None);
+    let result = {
+        let mut calls = AnalysisCalls {
+            analyze: &analyze,
+            output: &output,
+        };
+        run_compiler(args, &mut calls, file_loader, None).0
+    };
+    // Stopping after analysis is reported as an error without any errors.
+    let result = match result {
+        Err(0) => Ok(()),
+        result => result,
+    };
+    (result, output.into_inner())
+}
+
+struct AnalysisCalls<'a, F: 'a, R: 'a> {
+    analyze: &'a RefCell<Option<F>>,
+    output: &'a RefCell<Option<R>>,
+}
+
+impl<'a, F, R> CompilerCalls<'a> for AnalysisCalls<'a, F, R>
+    where F: for<'b, 'tcx> FnOnce(TyCtxt<'b, 'tcx, 'tcx>, &ty::CrateAnalysis<'tcx>) -> R
+{
+    fn build_controller(&mut self, _: &Session, _: &getopts::Matches) -> CompileController<'a> {
+        let analyze = self.analyze;
+        let output = self.output;
+
+        let mut control = CompileController::basic();
+        control.after_analysis.stop = Compilation::Stop;
+        control.after_analysis.callback = box move |state| {
+            // This runs whether or not type checking succeeded.
+            if state.session.err_count() > 0 {
+                return;
+            }
+            let tcx = state.tcx.unwrap();
+            let analysis = state.analysis.unwrap();
+            if let Some(analyze) = analyze.borrow_mut().take() {
+                *output.borrow_mut() = Some(analyze(tcx, analysis));
+            }
+        };
+        control
+    }
+}
+
+/// A `FileLoader` serving some files from memory and everything else from
+/// the file system.
+pub struct InMemoryFileLoader {
+    files: HashMap<PathBuf, String>,
+}
+
+impl InMemoryFileLoader {
+    pub fn new() -> InMemoryFileLoader {
+        InMemoryFileLoader {
+            files: HashMap::new(),
+        }
+    }
+
+    /// Makes `path` read as `contents`, whether or not it exists on disk.
+    /// The compiler asks for paths as they were given to it, so the input
+    /// file should be added under the same name used in the arguments.
+    pub fn add_file<P: Into<PathBuf>>(&mut self, path: P, contents: String) {
+        self.files.insert(path.into(), contents);
+    }
+}
+
+impl FileLoader for InMemoryFileLoader {
+    fn file_exists(&self, path: &Path) -> bool {
+        self.files.contains_key(path) || RealFileLoader.file_exists(path)
+    }
+
+    fn abs_path(&self, path: &Path) -> Option<PathBuf> {
+        RealFileLoader.abs_path(path)
+    }
+
+    fn read_file(&self, path: &Path) -> io::Result<String> {
+        match self.files.get(path) {
+            Some(contents) => Ok(contents.clone()),
+            None => RealFileLoader.read_file(path),
+        }
+    }
+}
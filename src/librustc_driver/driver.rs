@@ -84,7 +84,7 @@ pub fn compile_input(sess: &Session,
     // We need nested scopes here, because the intermediate results can keep
     // large chunks of memory alive and we want to free them as soon as
     // possible to keep the peak memory usage low
-    let (outputs, trans) = {
+    let (outputs, trans, crate_hash) = {
         let krate = match phase_1_parse_input(sess, input) {
             Ok(krate) => krate,
             Err(mut parse_error) => {
@@ -194,18 +194,30 @@ pub fn compile_input(sess: &Session,
 
             result?;
 
+            // Nothing has to be translated if at most the metadata is wanted,
+            // so skip trans and LLVM entirely and just write the `.rmeta`.
+            if sess.opts.metadata_only() {
+                let metadata = phase_4_encode_metadata(tcx, analysis, &incremental_hashes_map);
+                link::link_metadata_only(sess, &metadata, &outputs, &crate_name);
+                return Ok((outputs, None, metadata.link.crate_hash));
+            }
+
             if log_enabled!(::log::INFO) {
                 println!("Pre-trans");
                 tcx.print_debug_stats();
             }
-            let trans = phase_4_translate_to_llvm(tcx, analysis, &incremental_hashes_map);
+            let trans = phase_4_translate_to_llvm(tcx,
+                                                  analysis,
+                                                  &incremental_hashes_map,
+                                                  &outputs);
 
             if log_enabled!(::log::INFO) {
                 println!("Post-trans");
                 tcx.print_debug_stats();
             }
 
-            Ok((outputs, trans))
+            let crate_hash = trans.link.crate_hash;
+            Ok((outputs, Some(trans), crate_hash))
         })??
     };
 
@@ -213,21 +225,27 @@ pub fn compile_input(sess: &Session,
         sess.code_stats.borrow().print_type_sizes();
     }
 
-    let phase5_result = phase_5_run_llvm_passes(sess, &trans, &outputs);
+    if let Some(ref trans) = trans {
+        let phase5_result = phase_5_run_llvm_passes(sess, trans, &outputs);
 
-    controller_entry_point!(after_llvm,
-                            sess,
-                            CompileState::state_after_llvm(input, sess, outdir, output, &trans),
-                            phase5_result);
-    phase5_result?;
+        controller_entry_point!(after_llvm,
+                                sess,
+                                CompileState::state_after_llvm(input, sess, outdir, output, trans),
+                                phase5_result);
+        phase5_result?;
 
-    write::cleanup_llvm(&trans);
+        if let Some(ref format) = sess.opts.debugging_opts.print_fn_sizes {
+            sess.code_stats.borrow().print_fn_sizes(format == "json");
+        }
+
+        write::cleanup_llvm(trans);
 
-    phase_6_link_output(sess, &trans, &outputs);
+        phase_6_link_output(sess, trans, &outputs);
+    }
 
     // Now that we won't touch anything in the incremental compilation directory
     // any more, we can finalize it (which involves renaming it)
-    rustc_incremental::finalize_session_directory(sess, trans.link.crate_hash);
+    rustc_incremental::finalize_session_directory(sess, crate_hash);
 
     if sess.opts.debugging_opts.perf_stats {
         sess.print_perf_stats();
@@ -249,7 +267,8 @@ fn keep_ast(sess: &Session) -> bool {
     sess.opts.debugging_opts.keep_ast ||
     sess.opts.debugging_opts.save_analysis ||
     sess.opts.debugging_opts.save_analysis_csv ||
-    sess.opts.debugging_opts.save_analysis_api
+    sess.opts.debugging_opts.save_analysis_api ||
+    sess.opts.debugging_opts.save_analysis_stream
 }
 
 /// The name used for source code that doesn't originate in a file
@@ -555,6 +574,34 @@ pub fn phase_2_configure_and_expand<F>(sess: &Session,
                                        after_expand: F)
                                        -> Result<ExpansionResult, usize>
     where F: FnOnce(&ast::Crate) -> CompileResult,
+{
+    phase_2_configure_and_expand_with_resolver(sess,
+                                               cstore,
+                                               krate,
+                                               registry,
+                                               crate_name,
+                                               addl_plugins,
+                                               make_glob_map,
+                                               after_expand,
+                                               |_, _| {})
+}
+
+/// Like `phase_2_configure_and_expand`, but also hands the expanded crate
+/// and the resolver to `after_resolve` once the crate has been lowered to
+/// HIR, so tools can resolve further paths, e.g. those in documentation, in
+/// the scope of the crate's modules.
+pub fn phase_2_configure_and_expand_with_resolver<F, R>(sess: &Session,
+                                                        cstore: &CStore,
+                                                        krate: ast::Crate,
+                                                        registry: Option<Registry>,
+                                                        crate_name: &str,
+                                                        addl_plugins: Option<Vec<String>>,
+                                                        make_glob_map: MakeGlobMap,
+                                                        after_expand: F,
+                                                        after_resolve: R)
+                                                        -> Result<ExpansionResult, usize>
+    where F: FnOnce(&ast::Crate) -> CompileResult,
+          R: for<'a> FnOnce(&ast::Crate, &mut Resolver<'a>),
 {
     let time_passes = sess.time_passes();
 
@@ -780,6 +827,8 @@ pub fn phase_2_configure_and_expand<F>(sess: &Session,
         hir_map::Forest::new(hir_crate, &sess.dep_graph)
     });
 
+    after_resolve(&krate, &mut resolver);
+
     // Discard hygiene data, which isn't required past lowering to HIR.
     if !keep_hygiene_data(sess) {
         syntax::ext::hygiene::reset_hygiene_data();
@@ -998,17 +1047,11 @@ pub fn phase_3_run_analysis_passes<'tcx, F, R>(sess: &'tcx Session,
     })
 }
 
-/// Run the translation phase to LLVM, after which the AST and analysis can
-pub fn phase_4_translate_to_llvm<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                                           analysis: ty::CrateAnalysis,
-                                           incremental_hashes_map: &IncrementalHashesMap)
-                                           -> trans::CrateTranslation {
+/// Run the passes that transform the MIR into the form which is translated
+/// to LLVM and also stored in the crate metadata.
+fn optimize_mir<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
     let time_passes = tcx.sess.time_passes();
 
-    time(time_passes,
-         "resolving dependency formats",
-         || dependency_format::calculate(&tcx.sess));
-
     if tcx.sess.opts.debugging_opts.mir_stats {
         mir_stats::print_mir_stats(tcx, "PRE OPTIMISATION MIR STATS");
     }
@@ -1044,11 +1087,55 @@ pub fn phase_4_translate_to_llvm<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     if tcx.sess.opts.debugging_opts.mir_stats {
         mir_stats::print_mir_stats(tcx, "POST OPTIMISATION MIR STATS");
     }
+}
+
+/// Encode the crate metadata without translating anything, for builds which
+/// only emit metadata (`--emit=metadata`). The MIR is still optimized first,
+/// so the metadata is the same as that of a full build.
+pub fn phase_4_encode_metadata<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                         analysis: ty::CrateAnalysis,
+                                         incremental_hashes_map: &IncrementalHashesMap)
+                                         -> trans::EncodedMetadata {
+    let time_passes = tcx.sess.time_passes();
+
+    time(time_passes,
+         "resolving dependency formats",
+         || dependency_format::calculate(&tcx.sess));
+
+    optimize_mir(tcx);
+
+    let metadata = trans::trans_metadata_only(tcx, analysis, incremental_hashes_map);
+
+    time(time_passes,
+         "assert dep graph",
+         || rustc_incremental::assert_dep_graph(tcx));
+
+    time(time_passes,
+         "serialize dep graph",
+         || rustc_incremental::save_dep_graph(tcx,
+                                              incremental_hashes_map,
+                                              metadata.link.crate_hash));
+    metadata
+}
+
+/// Run the translation phase to LLVM, after which the AST and analysis can
+pub fn phase_4_translate_to_llvm<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                           analysis: ty::CrateAnalysis,
+                                           incremental_hashes_map: &IncrementalHashesMap,
+                                           outputs: &OutputFilenames)
+                                           -> trans::CrateTranslation {
+    let time_passes = tcx.sess.time_passes();
+
+    time(time_passes,
+         "resolving dependency formats",
+         || dependency_format::calculate(&tcx.sess));
+
+    optimize_mir(tcx);
 
     let translation =
         time(time_passes,
              "translation",
-             move || trans::trans_crate(tcx, analysis, &incremental_hashes_map));
+             move || trans::trans_crate(tcx, analysis, &incremental_hashes_map, outputs));
 
     time(time_passes,
          "assert dep graph",
@@ -1160,7 +1247,12 @@ fn write_out_deps(sess: &Session, outputs: &OutputFilenames, crate_name: &str) {
                                          .iter()
                                          .filter(|fmap| fmap.is_real_file())
                                          .filter(|fmap| !fmap.is_imported())
-                                         .map(|fmap| escape_dep_filename(&fmap.name))
+                                         .map(|fmap| {
+                                             // Refer to the actual file, not
+                                             // its remapped name.
+                                             let path = fmap.unmapped_path.as_ref().unwrap();
+                                             escape_dep_filename(&path.to_string_lossy())
+                                         })
                                          .collect();
             let mut file = fs::File::create(&deps_filename)?;
             for path in &out_filenames {
@@ -87,7 +87,7 @@ use std::default::Default;
 use std::env;
 use std::io::{self, Read, Write};
 use std::iter::repeat;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::rc::Rc;
 use std::str;
@@ -103,6 +103,7 @@ use syntax_pos::{DUMMY_SP, MultiSpan};
 #[cfg(test)]
 pub mod test;
 
+pub mod analysis;
 pub mod driver;
 pub mod pretty;
 pub mod target_features;
@@ -203,7 +204,7 @@ pub fn run_compiler<'a>(args: &[String],
     let cstore = Rc::new(CStore::new(&dep_graph));
 
     let loader = file_loader.unwrap_or(box RealFileLoader);
-    let codemap = Rc::new(CodeMap::with_file_loader(loader));
+    let codemap = Rc::new(CodeMap::with_file_loader(loader, sopts.file_path_mapping()));
     let mut sess = session::build_session_with_codemap(
         sopts, &dep_graph, input_file_path, descriptions, cstore.clone(), codemap, emitter_dest,
     );
@@ -415,6 +416,20 @@ impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
                 let mut cfg = config::build_configuration(&sess, cfg.clone());
                 target_features::add_configuration(&mut cfg, &sess);
                 sess.parse_sess.config = cfg;
+
+                if let Some(ref incr_dir) = sess.opts.debugging_opts.incremental_gc {
+                    let incr_dir = Path::new(incr_dir);
+                    let result =
+                        rustc_incremental::garbage_collect_incr_comp_dir(&sess, incr_dir, None);
+                    if let Err(err) = result {
+                        sess.fatal(&format!("failed to garbage collect incremental \
+                                             compilation directory `{}`: {}",
+                                            incr_dir.display(),
+                                            err));
+                    }
+                    return None;
+                }
+
                 let should_stop =
                     RustcDefaultCalls::print_crate_info(&sess, None, odir, ofile);
 
@@ -520,7 +535,8 @@ impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
 fn save_analysis(sess: &Session) -> bool {
     sess.opts.debugging_opts.save_analysis ||
     sess.opts.debugging_opts.save_analysis_csv ||
-    sess.opts.debugging_opts.save_analysis_api
+    sess.opts.debugging_opts.save_analysis_api ||
+    sess.opts.debugging_opts.save_analysis_stream
 }
 
 fn save_analysis_format(sess: &Session) -> save::Format {
@@ -530,6 +546,8 @@ fn save_analysis_format(sess: &Session) -> save::Format {
         save::Format::Csv
     } else if sess.opts.debugging_opts.save_analysis_api {
         save::Format::JsonApi
+    } else if sess.opts.debugging_opts.save_analysis_stream {
+        save::Format::JsonStream
     } else {
         unreachable!();
     }
@@ -538,12 +556,18 @@ fn save_analysis_format(sess: &Session) -> save::Format {
 impl RustcDefaultCalls {
     pub fn list_metadata(sess: &Session, matches: &getopts::Matches, input: &Input) -> Compilation {
         let r = matches.opt_strs("Z");
-        if r.contains(&("ls".to_string())) {
+        let json = r.contains(&("ls-json".to_string()));
+        if json || r.contains(&("ls".to_string())) {
             match input {
                 &Input::File(ref ifile) => {
                     let path = &(*ifile);
                     let mut v = Vec::new();
-                    locator::list_file_metadata(&sess.target.target, path, &mut v).unwrap();
+                    if json {
+                        locator::list_file_metadata_json(&sess.target.target, path, &mut v)
+                            .unwrap();
+                    } else {
+                        locator::list_file_metadata(&sess.target.target, path, &mut v).unwrap();
+                    }
                     println!("{}", String::from_utf8(v).unwrap());
                 }
                 &Input::Str { .. } => {
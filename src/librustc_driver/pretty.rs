@@ -777,8 +777,9 @@ pub fn fold_crate(krate: ast::Crate, ppm: PpMode) -> ast::Crate {
 
 fn get_source(input: &Input, sess: &Session) -> (Vec<u8>, String) {
     let src_name = driver::source_name(input);
+    let (filemap_name, _) = sess.codemap().path_mapping().map_prefix(src_name.clone());
     let src = sess.codemap()
-        .get_filemap(&src_name)
+        .get_filemap(&filemap_name)
         .unwrap()
         .src
         .as_ref()
@@ -55,4 +55,5 @@ pub use persist::save_trans_partition;
 pub use persist::save_work_products;
 pub use persist::in_incr_comp_dir;
 pub use persist::finalize_session_directory;
+pub use persist::garbage_collect_incr_comp_dir;
 pub use persist::delete_workproduct_files;
@@ -43,6 +43,13 @@ pub struct SerializedDepGraph {
     /// but rather the `DefPathIndex`. This can then be retraced
     /// to find the current def-id.
     pub hashes: Vec<SerializedHash>,
+
+    /// For edges that end in a work-product, the chain of nodes in the
+    /// full dep-graph that led from the source of the edge to its target
+    /// (both included). These are only recorded with
+    /// `-Z incremental-explain` and are used to explain to the user why
+    /// a work-product could not be re-used in the next session.
+    pub paths: Vec<SerializedPath>,
 }
 
 /// Represents a "reduced" dependency edge. Unlike the full dep-graph,
@@ -54,6 +61,9 @@ pub struct SerializedDepGraph {
 /// increased.
 pub type SerializedEdge = (DepNode<DefPathIndex>, DepNode<DefPathIndex>);
 
+/// A chain of dep-nodes, each of which is read by the one following it.
+pub type SerializedPath = Vec<DepNode<DefPathIndex>>;
+
 #[derive(Debug, RustcEncodable, RustcDecodable)]
 pub struct SerializedHash {
     /// def-id of thing being hashed
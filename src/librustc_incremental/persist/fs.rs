@@ -61,6 +61,19 @@
 //! any more and will delete those. It will also delete any finalized session
 //! directories for a given crate except for the most recent one.
 //!
+//! The above only ever cleans up within the directory of the crate being
+//! compiled, so the caches of crates that are not compiled any more (or of
+//! old versions of dependencies) stay around forever. If a maximum age or size
+//! is configured for the incremental compilation directory (via
+//! `-Z incremental-max-age` and `-Z incremental-max-size`), the compiler will
+//! additionally delete whole crate directories when it finalizes a session:
+//! first those that have not been used for longer than the maximum age, then
+//! the least recently used ones until the directory is small enough again. A
+//! crate directory counts as used when a session directory was created in it,
+//! and directories that are in use by another compiler process (i.e. where
+//! any of the session directory locks can't be acquired) are left alone. The
+//! same can be done without compiling anything via `-Z incremental-gc=<dir>`.
+//!
 //! ## Synchronization
 //!
 //! There is some synchronization needed in order for the compiler to be able to
@@ -373,6 +386,15 @@ pub fn finalize_session_directory(sess: &Session, svh: Svh) {
     }
 
     let _ = garbage_collect_session_directories(sess);
+
+    let incr_dir = sess.opts.incremental.as_ref().unwrap();
+    let crate_dir = incr_comp_session_dir.parent().unwrap();
+    if let Err(err) = garbage_collect_incr_comp_dir(sess, incr_dir, Some(crate_dir)) {
+        sess.warn(&format!("Failed to garbage collect incremental compilation \
+                            directory `{}`: {}",
+                           incr_dir.display(),
+                           err));
+    }
 }
 
 pub fn delete_all_session_dir_contents(sess: &Session) -> io::Result<()> {
@@ -919,6 +941,198 @@ fn all_except_most_recent(deletion_candidates: Vec<(SystemTime, PathBuf, Option<
     }
 }
 
+/// Deletes crate directories from the incremental compilation directory
+/// `incr_dir` according to `-Z incremental-max-age` and
+/// `-Z incremental-max-size`: all that haven't been used for longer than the
+/// maximum age, then the least recently used ones until the total size of
+/// `incr_dir` doesn't exceed the maximum size anymore. `keep` is the crate
+/// directory of the current session, if any; it counts towards the size but
+/// is never deleted.
+pub fn garbage_collect_incr_comp_dir(sess: &Session,
+                                     incr_dir: &Path,
+                                     keep: Option<&Path>)
+                                     -> io::Result<()> {
+    let max_size = sess.opts.debugging_opts.incremental_max_size.map(|mb| {
+        mb as u64 * 1024 * 1024
+    });
+    let max_age = sess.opts.debugging_opts.incremental_max_age.map(|days| {
+        Duration::from_secs(days as u64 * 24 * 60 * 60)
+    });
+    // Crates compiled very recently might still be in the middle of
+    // finalizing their session directories.
+    let min_age = sess.opts.debugging_opts.incremental_gc_min_age.unwrap_or(10);
+    let min_age = Duration::from_secs(min_age as u64);
+
+    if max_size.is_none() && max_age.is_none() {
+        return Ok(())
+    }
+
+    debug!("garbage_collect_incr_comp_dir() - begin: {}", incr_dir.display());
+
+    let mut total_size = 0;
+    let mut candidates = vec![];
+
+    for dir_entry in try!(incr_dir.read_dir()) {
+        let path = match dir_entry {
+            Ok(dir_entry) => dir_entry.path(),
+            _ => {
+                // Ignore any errors
+                continue
+            }
+        };
+
+        if !path.is_dir() {
+            // This is something we don't know, leave it alone
+            continue
+        }
+
+        let size = dir_size(&path);
+        total_size += size;
+
+        if Some(&*path) == keep {
+            continue
+        }
+
+        match crate_dir_last_used(&path) {
+            Some(last_used) => candidates.push((last_used, size, path)),
+            None => {
+                debug!("garbage_collect_incr_comp_dir() - not a crate directory: {}",
+                       path.display());
+            }
+        }
+    }
+
+    // Least recently used first
+    candidates.sort_by_key(|&(last_used, ..)| last_used);
+
+    let now = SystemTime::now();
+    for (last_used, size, path) in candidates {
+        let too_old = max_age.map_or(false, |max_age| {
+            now.duration_since(last_used).map(|age| age > max_age).unwrap_or(false)
+        });
+        let too_big = max_size.map_or(false, |max_size| total_size > max_size);
+
+        // Everything after this is used more recently and we won't get any
+        // bigger by not deleting anything.
+        if !too_old && !too_big {
+            break
+        }
+
+        if now.duration_since(last_used).map(|age| age < min_age).unwrap_or(true) {
+            continue
+        }
+
+        debug!("garbage_collect_incr_comp_dir() - deleting `{}` ({} bytes)",
+               path.display(), size);
+
+        match delete_crate_dir(&path) {
+            Ok(true) => {
+                total_size -= size;
+
+                if sess.opts.debugging_opts.incremental_info {
+                    println!("incremental: garbage collected `{}` ({} bytes)",
+                             path.display(), size);
+                }
+            }
+            Ok(false) => {
+                debug!("garbage_collect_incr_comp_dir() - not collecting, still in use");
+            }
+            Err(err) => {
+                sess.warn(&format!("Failed to garbage collect incremental compilation \
+                                    crate directory `{}`: {}",
+                                   path.display(),
+                                   err));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns when a session directory was last created in the given crate
+/// directory, or `None` if it doesn't contain any session directories (and
+/// thus probably isn't a crate directory).
+fn crate_dir_last_used(crate_dir: &Path) -> Option<SystemTime> {
+    let entries = match crate_dir.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return None,
+    };
+
+    entries.filter_map(|dir_entry| dir_entry.ok())
+           .filter_map(|dir_entry| {
+               let entry_name = dir_entry.file_name();
+               let entry_name = entry_name.to_string_lossy();
+               extract_timestamp_from_session_dir(&entry_name).ok()
+           })
+           .max()
+}
+
+/// Deletes a crate directory if none of its session directories is in use.
+/// Returns `false` if it was in use and thus not deleted.
+fn delete_crate_dir(crate_dir: &Path) -> io::Result<bool> {
+    let mut locks = vec![];
+    let mut session_dirs = vec![];
+
+    for dir_entry in try!(crate_dir.read_dir()) {
+        let dir_entry = try!(dir_entry);
+        let entry_name = dir_entry.file_name();
+        let entry_name = entry_name.to_string_lossy();
+
+        if is_session_directory_lock_file(&entry_name) {
+            match flock::Lock::new(&dir_entry.path(),
+                                   false,  // don't wait
+                                   false,  // don't create the lock-file
+                                   true) { // get an exclusive lock
+                Ok(lock) => locks.push((dir_entry.path(), lock)),
+                Err(_) => return Ok(false),
+            }
+        } else {
+            session_dirs.push(dir_entry.path());
+        }
+    }
+
+    // Delete everything but the lock files while we are holding the locks
+    for path in session_dirs {
+        if path.is_dir() {
+            try!(safe_remove_dir_all(&path));
+        } else {
+            try!(safe_remove_file(&path));
+        }
+    }
+
+    // Then the lock files we hold. Lock files which turned up since we
+    // looked belong to sessions started in the meantime and stay put.
+    for (path, lock) in locks {
+        let _ = safe_remove_file(&path);
+        mem::drop(lock);
+    }
+
+    // If another process has started a new session in this directory in the
+    // meantime, the directory isn't empty and we leave it alone.
+    let _ = std_fs::remove_dir(crate_dir);
+
+    Ok(true)
+}
+
+/// The total size of all files in `path` and its subdirectories. Files that
+/// can't be inspected are ignored.
+fn dir_size(path: &Path) -> u64 {
+    let entries = match path.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    entries.filter_map(|dir_entry| dir_entry.ok())
+           .map(|dir_entry| {
+               match dir_entry.metadata() {
+                   Ok(ref metadata) if metadata.is_dir() => dir_size(&dir_entry.path()),
+                   Ok(metadata) => metadata.len(),
+                   Err(_) => 0,
+               }
+           })
+           .sum()
+}
+
 /// Since paths of artifacts within session directories can get quite long, we
 /// need to support deleting files with very long paths. The regular
 /// WinApi functions only support paths up to 260 characters, however. In order
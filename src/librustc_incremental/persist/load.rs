@@ -185,21 +185,46 @@ pub fn decode_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                   })
                                   .collect();
 
+    // With `-Z incremental-explain`, the previous session recorded how each
+    // input reached the work-products depending on it.
+    let explain = tcx.sess.opts.debugging_opts.incremental_explain;
+    let mut paths = FxHashMap();
+    if explain {
+        if serialized_dep_graph.paths.is_empty() {
+            println!("incremental: the previous session was not compiled with \
+                      `-Z incremental-explain`, dependency chains are not available");
+        }
+        for path in &serialized_dep_graph.paths {
+            if let Some(target_node) = retraced.map(&path[path.len() - 1]) {
+                paths.insert((&path[0], target_node), path);
+            }
+        }
+    }
+
     // Compute which work-products have an input that has changed or
     // been removed. Put the dirty ones into a set.
     let mut dirty_target_nodes = FxHashSet();
     for &(raw_source_node, ref target_node) in &retraced_edges {
         if dirty_raw_source_nodes.contains(raw_source_node) {
-            if !dirty_target_nodes.contains(target_node) {
-                dirty_target_nodes.insert(target_node.clone());
-
-                if tcx.sess.opts.debugging_opts.incremental_info {
-                    // It'd be nice to pretty-print these paths better than just
-                    // using the `Debug` impls, but wev.
-                    println!("incremental: module {:?} is dirty because {:?} \
-                              changed or was removed",
-                             target_node,
-                             raw_source_node.map_def(|&index| {
+            let newly_dirty = dirty_target_nodes.insert(target_node.clone());
+
+            // Without `-Z incremental-explain`, only report the first
+            // input that made a work-product dirty.
+            if (newly_dirty && tcx.sess.opts.debugging_opts.incremental_info) || explain {
+                // It'd be nice to pretty-print these paths better than just
+                // using the `Debug` impls, but wev.
+                println!("incremental: module {:?} is dirty because {:?} \
+                          changed or was removed",
+                         target_node,
+                         raw_source_node.map_def(|&index| {
+                             Some(directory.def_path_string(tcx, index))
+                         }).unwrap());
+            }
+
+            if let Some(path) = paths.get(&(raw_source_node, target_node.clone())) {
+                for node in &path[1 .. path.len() - 1] {
+                    println!("incremental:     via {:?}",
+                             node.map_def(|&index| {
                                  Some(directory.def_path_string(tcx, index))
                              }).unwrap());
                 }
@@ -24,6 +24,7 @@ mod work_product;
 mod file_format;
 
 pub use self::fs::finalize_session_directory;
+pub use self::fs::garbage_collect_incr_comp_dir;
 pub use self::fs::in_incr_comp_dir;
 pub use self::load::load_dep_graph;
 pub use self::save::save_dep_graph;
@@ -13,6 +13,7 @@ use rustc::hir::def_id::DefId;
 use rustc_data_structures::fx::FxHashMap;
 use rustc_data_structures::bitvec::BitVector;
 use rustc_data_structures::graph::{NodeIndex, Graph};
+use std::collections::VecDeque;
 
 use super::hash::*;
 use ich::Fingerprint;
@@ -29,6 +30,13 @@ pub struct Predecessors<'query> {
     // - Keys: some hashable node
     // - Values: the hash thereof
     pub hashes: FxHashMap<&'query DepNode<DefId>, Fingerprint>,
+
+    // - Keys: (input, work-product) pairs from `inputs`
+    // - Values: the shortest chain of nodes leading from the input to the
+    //   work-product, both included. Only computed with
+    //   `-Z incremental-explain`.
+    pub paths: FxHashMap<(&'query DepNode<DefId>, &'query DepNode<DefId>),
+                         Vec<&'query DepNode<DefId>>>,
 }
 
 impl<'q> Predecessors<'q> {
@@ -88,9 +96,27 @@ impl<'q> Predecessors<'q> {
                   .or_insert_with(|| hcx.hash(input).unwrap());
         }
 
+        let mut paths = FxHashMap();
+        if tcx.sess.opts.debugging_opts.incremental_explain {
+            for (node_index, node) in query.graph.all_nodes().iter().enumerate() {
+                if let DepNode::WorkProduct(_) = node.data {
+                    let root_paths = find_root_paths(&query.graph,
+                                                     node_index as u32,
+                                                     HashContext::is_hashable);
+                    for path in root_paths {
+                        let path: Vec<_> = path.iter().map(|&i| {
+                            query.graph.node_data(NodeIndex(i as usize))
+                        }).collect();
+                        paths.insert((path[0], &node.data), path);
+                    }
+                }
+            }
+        }
+
         Predecessors {
             inputs: inputs,
             hashes: hashes,
+            paths: paths,
         }
     }
 }
@@ -191,6 +217,54 @@ fn find_roots<T, F>(graph: &Graph<T, ()>,
     }
 }
 
+// Starting at `start_node`, this function does a breadth-first search
+// towards the roots of the dep-graph, like `find_roots` does. Instead of just
+// the set of roots, it returns, for every root it finds, the shortest chain of
+// nodes from that root to `start_node`. This is much more expensive than
+// `find_roots`, so it is only used for explaining to the user why something
+// had to be recompiled.
+fn find_root_paths<T, F>(graph: &Graph<T, ()>,
+                         start_node: u32,
+                         is_root: F)
+                         -> Vec<Vec<u32>>
+    where F: Fn(&T) -> bool,
+{
+    // For each visited node, the node we reached it from (i.e. the next node
+    // on the way back to `start_node`).
+    let mut successors = FxHashMap();
+    let mut visited = BitVector::new(graph.len_nodes());
+    let mut queue = VecDeque::new();
+    let mut roots = Vec::new();
+
+    visited.insert(start_node as usize);
+    queue.push_back(start_node);
+
+    while let Some(node) = queue.pop_front() {
+        if is_root(graph.node_data(NodeIndex(node as usize))) {
+            roots.push(node);
+            continue
+        }
+
+        for pred in graph.predecessor_nodes(NodeIndex(node as usize)) {
+            let pred = pred.node_id() as u32;
+            if visited.insert(pred as usize) {
+                successors.insert(pred, node);
+                queue.push_back(pred);
+            }
+        }
+    }
+
+    roots.into_iter().map(|root| {
+        let mut path = vec![root];
+        let mut node = root;
+        while let Some(&next) = successors.get(&node) {
+            path.push(next);
+            node = next;
+        }
+        path
+    }).collect()
+}
+
 struct DfsWorkspace {
     stack: Vec<u32>,
     visited: BitVector,
@@ -364,3 +438,57 @@ fn test_cached_dfs_cyclic() {
         assert_eq!(ws1.output.nodes, vec![8, 9, 10, 11]);
     }
 }
+
+#[test]
+fn test_root_paths() {
+
+    //    0       1 <---- 2       3
+    //    ^       |       ^       ^
+    //    |       v       |       |
+    //    4 ----> 5 ----> 6 ----> 7
+    //    ^       ^       ^       ^
+    //    |       |       |       |
+    //    8       9      10      11
+
+    let mut g: Graph<bool, ()> = Graph::new();
+    g.add_node(false);
+    g.add_node(false);
+    g.add_node(false);
+    g.add_node(false);
+    g.add_node(false);
+    g.add_node(false);
+    g.add_node(false);
+    g.add_node(false);
+    g.add_node(true);
+    g.add_node(true);
+    g.add_node(true);
+    g.add_node(true);
+
+    g.add_edge(NodeIndex( 4), NodeIndex(0), ());
+    g.add_edge(NodeIndex( 8), NodeIndex(4), ());
+    g.add_edge(NodeIndex( 4), NodeIndex(5), ());
+    g.add_edge(NodeIndex( 1), NodeIndex(5), ());
+    g.add_edge(NodeIndex( 9), NodeIndex(5), ());
+    g.add_edge(NodeIndex( 5), NodeIndex(6), ());
+    g.add_edge(NodeIndex( 6), NodeIndex(2), ());
+    g.add_edge(NodeIndex( 2), NodeIndex(1), ());
+    g.add_edge(NodeIndex(10), NodeIndex(6), ());
+    g.add_edge(NodeIndex( 6), NodeIndex(7), ());
+    g.add_edge(NodeIndex(11), NodeIndex(7), ());
+    g.add_edge(NodeIndex( 7), NodeIndex(3), ());
+
+    fn is_root(x: &bool) -> bool { *x }
+
+    let mut paths = find_root_paths(&g, 3, is_root);
+    paths.sort();
+    assert_eq!(paths, vec![vec![8, 4, 5, 6, 7, 3],
+                           vec![9, 5, 6, 7, 3],
+                           vec![10, 6, 7, 3],
+                           vec![11, 7, 3]]);
+
+    let mut paths = find_root_paths(&g, 2, is_root);
+    paths.sort();
+    assert_eq!(paths, vec![vec![8, 4, 5, 6, 2],
+                           vec![9, 5, 6, 2],
+                           vec![10, 6, 2]]);
+}
@@ -178,6 +178,7 @@ pub fn encode_dep_graph(preds: &Predecessors,
     // Create a flat list of (Input, WorkProduct) edges for
     // serialization.
     let mut edges = vec![];
+    let mut paths: Vec<SerializedPath> = vec![];
     for (&target, sources) in &preds.inputs {
         match *target {
             DepNode::MetaData(ref def_id) => {
@@ -188,10 +189,13 @@ pub fn encode_dep_graph(preds: &Predecessors,
             }
             _ => (),
         }
-        let target = builder.map(target);
+        let mapped_target = builder.map(target);
         for &source in sources {
+            if let Some(path) = preds.paths.get(&(source, target)) {
+                paths.push(path.iter().map(|&node| builder.map(node)).collect());
+            }
             let source = builder.map(source);
-            edges.push((source, target.clone()));
+            edges.push((source, mapped_target.clone()));
         }
     }
 
@@ -213,6 +217,7 @@ pub fn encode_dep_graph(preds: &Predecessors,
                 }
             })
             .collect(),
+        paths: paths,
     };
 
     if tcx.sess.opts.debugging_opts.incremental_info {
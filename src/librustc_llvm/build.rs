@@ -96,7 +96,7 @@ fn main() {
 
     let optional_components =
         ["x86", "arm", "aarch64", "mips", "powerpc", "pnacl", "systemz", "jsbackend", "msp430",
-         "sparc", "nvptx"];
+         "sparc", "nvptx", "coverage", "lto"];
 
     // FIXME: surely we don't need all these components, right? Stuff like mcjit
     //        or interpreter the compiler itself never uses.
@@ -149,9 +149,11 @@ fn main() {
     println!("cargo:rerun-if-changed=../rustllvm/PassWrapper.cpp");
     println!("cargo:rerun-if-changed=../rustllvm/RustWrapper.cpp");
     println!("cargo:rerun-if-changed=../rustllvm/ArchiveWrapper.cpp");
+    println!("cargo:rerun-if-changed=../rustllvm/CoverageWrapper.cpp");
     cfg.file("../rustllvm/PassWrapper.cpp")
        .file("../rustllvm/RustWrapper.cpp")
        .file("../rustllvm/ArchiveWrapper.cpp")
+       .file("../rustllvm/CoverageWrapper.cpp")
        .cpp(true)
        .cpp_link_stdlib(None) // we handle this below
        .compile("librustllvm.a");
@@ -104,9 +104,31 @@ impl InlineAsmDiagnostic {
     }
 }
 
+#[derive(Copy, Clone)]
+pub struct StackSizeDiagnostic {
+    pub function: ValueRef,
+    pub stack_size: u64,
+}
+
+impl StackSizeDiagnostic {
+    unsafe fn unpack(di: DiagnosticInfoRef) -> StackSizeDiagnostic {
+        let mut opt = StackSizeDiagnostic {
+            function: ptr::null_mut(),
+            stack_size: 0,
+        };
+
+        super::LLVMRustUnpackStackSizeDiagnostic(di,
+                                                 &mut opt.function,
+                                                 &mut opt.stack_size);
+
+        opt
+    }
+}
+
 pub enum Diagnostic {
     Optimization(OptimizationDiagnostic),
     InlineAsm(InlineAsmDiagnostic),
+    StackSize(StackSizeDiagnostic),
 
     /// LLVM has other types that we do not wrap here.
     UnknownDiagnostic(DiagnosticInfoRef),
@@ -120,6 +142,8 @@ impl Diagnostic {
         match kind {
             Dk::InlineAsm => InlineAsm(InlineAsmDiagnostic::unpack(di)),
 
+            Dk::StackSize => StackSize(StackSizeDiagnostic::unpack(di)),
+
             Dk::OptimizationRemark => {
                 Optimization(OptimizationDiagnostic::unpack(OptimizationRemark, di))
             }
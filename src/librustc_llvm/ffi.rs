@@ -331,6 +331,27 @@ pub enum PassKind {
     Module,
 }
 
+/// LLVMRustThinLTOModule
+#[repr(C)]
+pub struct ThinLTOModule {
+    pub identifier: *const c_char,
+    pub data: *const u8,
+    pub len: usize,
+}
+
+/// LLVMRustCounterMappingRegion, a source region covered by a single
+/// profile counter.
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub struct CounterMappingRegion {
+    pub counter_id: c_uint,
+    pub file_id: c_uint,
+    pub line_start: c_uint,
+    pub column_start: c_uint,
+    pub line_end: c_uint,
+    pub column_end: c_uint,
+}
+
 // Opaque pointer types
 #[allow(missing_copy_implementations)]
 pub enum Module_opaque {}
@@ -407,6 +428,12 @@ pub type RustArchiveMemberRef = *mut RustArchiveMember_opaque;
 #[allow(missing_copy_implementations)]
 pub enum OperandBundleDef_opaque {}
 pub type OperandBundleDefRef = *mut OperandBundleDef_opaque;
+#[allow(missing_copy_implementations)]
+pub enum ThinLTOData_opaque {}
+pub type ThinLTODataRef = *mut ThinLTOData_opaque;
+#[allow(missing_copy_implementations)]
+pub enum ThinLTOBuffer_opaque {}
+pub type ThinLTOBufferRef = *mut ThinLTOBuffer_opaque;
 
 pub type DiagnosticHandler = unsafe extern "C" fn(DiagnosticInfoRef, *mut c_void);
 pub type InlineAsmDiagHandler = unsafe extern "C" fn(SMDiagnosticRef, *const c_void, c_uint);
@@ -1567,7 +1594,9 @@ extern "C" {
                                                OptLevel: CodeGenOptLevel,
                                                MergeFunctions: bool,
                                                SLPVectorize: bool,
-                                               LoopVectorize: bool);
+                                               LoopVectorize: bool,
+                                               PGOGenPath: *const c_char,
+                                               PGOUsePath: *const c_char);
     pub fn LLVMRustAddLibraryInfo(PM: PassManagerRef,
                                   M: ModuleRef,
                                   DisableSimplifyLibCalls: bool);
@@ -1579,6 +1608,10 @@ extern "C" {
                                    FileType: FileType)
                                    -> LLVMRustResult;
     pub fn LLVMRustPrintModule(PM: PassManagerRef, M: ModuleRef, Output: *const c_char);
+    pub fn LLVMRustGetObjectFunctionSizes(T: TargetMachineRef,
+                                          Path: *const c_char,
+                                          s: RustStringRef)
+                                          -> bool;
     pub fn LLVMRustSetLLVMOptions(Argc: c_int, Argv: *const *const c_char);
     pub fn LLVMRustPrintPasses();
     pub fn LLVMRustSetNormalizedTarget(M: ModuleRef, triple: *const c_char);
@@ -1613,6 +1646,9 @@ extern "C" {
                                              cookie_out: *mut c_uint,
                                              message_out: *mut TwineRef,
                                              instruction_out: *mut ValueRef);
+    pub fn LLVMRustUnpackStackSizeDiagnostic(DI: DiagnosticInfoRef,
+                                             function_out: *mut ValueRef,
+                                             stack_size_out: *mut u64);
 
     pub fn LLVMRustWriteDiagnosticInfoToString(DI: DiagnosticInfoRef, s: RustStringRef);
     pub fn LLVMRustGetDiagInfoKind(DI: DiagnosticInfoRef) -> DiagnosticKind;
@@ -1651,6 +1687,41 @@ extern "C" {
     pub fn LLVMRustSetComdat(M: ModuleRef, V: ValueRef, Name: *const c_char);
     pub fn LLVMRustUnsetComdat(V: ValueRef);
     pub fn LLVMRustSetModulePIELevel(M: ModuleRef);
+
+    pub fn LLVMRustThinLTOAvailable() -> bool;
+    pub fn LLVMRustThinLTOBufferCreate(M: ModuleRef) -> ThinLTOBufferRef;
+    pub fn LLVMRustThinLTOBufferFree(M: ThinLTOBufferRef);
+    pub fn LLVMRustThinLTOBufferPtr(M: ThinLTOBufferRef) -> *const c_char;
+    pub fn LLVMRustThinLTOBufferLen(M: ThinLTOBufferRef) -> size_t;
+    pub fn LLVMRustCreateThinLTOData(Modules: *const ThinLTOModule,
+                                     NumModules: c_uint,
+                                     PreservedSymbols: *const *const c_char,
+                                     NumPreservedSymbols: c_uint)
+                                     -> ThinLTODataRef;
+    pub fn LLVMRustFreeThinLTOData(Data: ThinLTODataRef);
+    pub fn LLVMRustParseBitcodeForThinLTO(Context: ContextRef,
+                                          Data: *const u8,
+                                          len: usize,
+                                          Identifier: *const c_char)
+                                          -> ModuleRef;
+    pub fn LLVMRustPrepareThinLTORename(Data: ThinLTODataRef, Module: ModuleRef) -> bool;
+    pub fn LLVMRustPrepareThinLTOResolveWeak(Data: ThinLTODataRef, Module: ModuleRef) -> bool;
+    pub fn LLVMRustPrepareThinLTOInternalize(Data: ThinLTODataRef, Module: ModuleRef) -> bool;
+    pub fn LLVMRustPrepareThinLTOImport(Data: ThinLTODataRef, Module: ModuleRef) -> bool;
+    pub fn LLVMRustPassManagerBuilderPopulateThinLTOPassManager(PMB: PassManagerBuilderRef,
+                                                                PM: PassManagerRef);
+
+    pub fn LLVMRustCoverageWriteFilenamesSectionToBuffer(Filenames: *const *const c_char,
+                                                         FilenamesLen: size_t,
+                                                         BufferOut: RustStringRef);
+    pub fn LLVMRustCoverageWriteMappingToBuffer(VirtualFileMappingIDs: *const c_uint,
+                                                NumVirtualFileMappingIDs: c_uint,
+                                                Regions: *const CounterMappingRegion,
+                                                NumRegions: c_uint,
+                                                BufferOut: RustStringRef);
+    pub fn LLVMRustCoverageCreatePGOFuncNameVar(F: ValueRef, FuncName: *const c_char) -> ValueRef;
+    pub fn LLVMRustCoverageComputeHash(Name: *const c_char) -> u64;
+    pub fn LLVMRustCoverageMappingVersion() -> u32;
 }
 
 
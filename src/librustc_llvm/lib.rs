@@ -295,6 +295,14 @@ pub fn build_string<F>(f: F) -> Option<String>
     String::from_utf8(buf.into_inner()).ok()
 }
 
+pub fn build_byte_buffer<F>(f: F) -> Vec<u8>
+    where F: FnOnce(RustStringRef)
+{
+    let mut buf = RefCell::new(Vec::new());
+    f(&mut buf as RustStringRepr as RustStringRef);
+    buf.into_inner()
+}
+
 pub unsafe fn twine_to_string(tr: TwineRef) -> String {
     build_string(|s| LLVMRustWriteTwineToString(tr, s)).expect("got a non-UTF8 Twine from LLVM")
 }
@@ -367,6 +367,7 @@ impl<'a> CrateLoader<'a> {
                 rejected_via_triple: vec![],
                 rejected_via_kind: vec![],
                 rejected_via_version: vec![],
+                rejected_via_checksum: vec![],
                 rejected_via_filename: vec![],
                 should_match_name: true,
                 is_proc_macro: Some(false),
@@ -383,6 +384,7 @@ impl<'a> CrateLoader<'a> {
                     rejected_via_triple: vec![],
                     rejected_via_kind: vec![],
                     rejected_via_version: vec![],
+                    rejected_via_checksum: vec![],
                     rejected_via_filename: vec![],
                     is_proc_macro: Some(true),
                     ..locate_ctxt
@@ -522,6 +524,7 @@ impl<'a> CrateLoader<'a> {
             rejected_via_triple: vec![],
             rejected_via_kind: vec![],
             rejected_via_version: vec![],
+            rejected_via_checksum: vec![],
             rejected_via_filename: vec![],
             should_match_name: true,
             is_proc_macro: None,
@@ -776,6 +779,30 @@ impl<'a> CrateLoader<'a> {
                                   &|data| data.needs_panic_runtime());
     }
 
+    fn inject_profiler_runtime(&mut self) {
+        if self.sess.opts.cg.profile_generate.is_none() &&
+           !self.sess.opts.debugging_opts.instrument_coverage {
+            return
+        }
+
+        // Instrumented code calls into the LLVM profiling runtime, which we
+        // ship as the `profiler_builtins` crate. Only final artifacts need
+        // the runtime, but it's harmless to load it for rlibs as well and
+        // that way a missing runtime is reported as early as possible.
+        info!("loading profiler");
+
+        let name = Symbol::intern("profiler_builtins");
+        let dep_kind = DepKind::Implicit;
+        let (_, data) =
+            self.resolve_crate(&None, name, name, None, DUMMY_SP, PathKind::Crate, dep_kind);
+
+        // Sanity check the loaded crate to ensure it is indeed a profiler runtime
+        if !data.is_profiler_runtime() {
+            self.sess.err(&format!("the crate `{}` is not a profiler runtime",
+                                   name));
+        }
+    }
+
     fn inject_allocator_crate(&mut self) {
         // Make sure that we actually need an allocator, if none of our
         // dependencies need one then we definitely don't!
@@ -973,6 +1000,7 @@ impl<'a> middle::cstore::CrateLoader for CrateLoader<'a> {
     fn postprocess(&mut self, krate: &ast::Crate) {
         self.inject_allocator_crate();
         self.inject_panic_runtime(krate);
+        self.inject_profiler_runtime();
 
         if log_enabled!(log::INFO) {
             dump_crates(&self.cstore);
@@ -36,7 +36,7 @@ use std::mem;
 use std::str;
 use std::u32;
 
-use rustc_serialize::{Decodable, Decoder, SpecializedDecoder, opaque};
+use rustc_serialize::{Decodable, Decoder, SpecializedDecoder, json, opaque};
 use syntax::attr;
 use syntax::ast;
 use syntax::codemap;
@@ -229,7 +229,11 @@ impl<'a, 'tcx, T> SpecializedDecoder<LazySeq<T>> for DecodeContext<'a, 'tcx> {
 impl<'a, 'tcx> SpecializedDecoder<CrateNum> for DecodeContext<'a, 'tcx> {
     fn specialized_decode(&mut self) -> Result<CrateNum, Self::Error> {
         let cnum = CrateNum::from_u32(u32::decode(self)?);
-        if cnum == LOCAL_CRATE {
+        // Without a loaded crate there is no `cnum_map` to translate
+        // through, e.g. when dumping a metadata blob, so leave it as-is.
+        if self.cdata.is_none() {
+            Ok(cnum)
+        } else if cnum == LOCAL_CRATE {
             Ok(self.cdata().cnum)
         } else {
             Ok(self.cdata().cnum_map.borrow()[cnum])
@@ -392,6 +396,16 @@ impl<'a, 'tcx> MetadataBlob {
         self.raw_bytes().starts_with(METADATA_HEADER)
     }
 
+    /// Whether the checksum at the end of the metadata matches its contents.
+    pub fn verify_checksum(&self) -> bool {
+        let bytes = self.raw_bytes();
+        if bytes.len() < METADATA_HEADER.len() + METADATA_CHECKSUM_LEN {
+            return false;
+        }
+        let (data, checksum) = bytes.split_at(bytes.len() - METADATA_CHECKSUM_LEN);
+        metadata_checksum(data) == checksum
+    }
+
     pub fn get_rustc_version(&self) -> String {
         Lazy::with_position(METADATA_HEADER.len() + 4).decode(self)
     }
@@ -414,9 +428,181 @@ impl<'a, 'tcx> MetadataBlob {
         write!(out, "\n")?;
         Ok(())
     }
+
+    /// Dumps the decoded `CrateRoot` as a single JSON object: the crate's
+    /// SVH, its dependencies, the public items with their def-paths and
+    /// stability, lang items, native libraries and macro definitions.
+    pub fn list_crate_metadata_json(&self, out: &mut io::Write) -> io::Result<()> {
+        let root = self.get_root();
+        let raw_bytes = self.raw_bytes();
+        let def_path_table = root.def_path_table.decode(self);
+
+        let def_path = |index: DefIndex| -> String {
+            let path = DefPath::make(LOCAL_CRATE, index, |p| def_path_table.def_key(p));
+            let mut s = root.name.to_string();
+            for component in &path.data {
+                s.push_str(&format!("::{}[{}]",
+                                    component.data.as_interned_str(),
+                                    component.disambiguator));
+            }
+            s
+        };
+
+        let mut items = vec![];
+        let mut macros = vec![];
+        for (index, entry) in root.index.iter_enumerated(raw_bytes) {
+            let entry = entry.decode(self);
+            if let EntryKind::MacroDef(macro_def) = entry.kind {
+                let name = def_path_table.def_key(index).disambiguated_data.data.get_opt_name();
+                macros.push(MacroJson {
+                    name: name.map(|name| name.to_string()).unwrap_or_default(),
+                    def_path: def_path(index),
+                    body: macro_def.decode(self).body,
+                });
+            }
+            if entry.visibility.decode(self) != ty::Visibility::Public {
+                continue;
+            }
+            let stability = entry.stability.map(|stab| {
+                let stab = stab.decode(self);
+                let (level, since) = match stab.level {
+                    attr::Unstable { .. } => ("unstable", None),
+                    attr::Stable { since } => ("stable", Some(since.to_string())),
+                };
+                StabilityJson {
+                    level: level,
+                    feature: stab.feature.to_string(),
+                    since: since,
+                }
+            });
+            items.push(ItemJson {
+                def_path: def_path(index),
+                kind: entry.kind.description(),
+                stability: stability,
+            });
+        }
+
+        let crate_json = CrateRootJson {
+            name: root.name.to_string(),
+            rustc_version: self.get_rustc_version(),
+            triple: root.triple.clone(),
+            hash: root.hash.to_string(),
+            disambiguator: root.disambiguator.to_string(),
+            panic_strategy: root.panic_strategy.desc().to_string(),
+            dependencies: root.crate_deps.decode(self).map(|dep| {
+                DependencyJson {
+                    name: dep.name.to_string(),
+                    hash: dep.hash.to_string(),
+                    kind: format!("{:?}", dep.kind),
+                }
+            }).collect(),
+            lang_items: root.lang_items.decode(self).map(|(index, item)| {
+                LangItemJson {
+                    name: lang_items::LanguageItems::item_name(item),
+                    def_path: def_path(index),
+                }
+            }).collect(),
+            lang_items_missing: root.lang_items_missing.decode(self).map(|item| {
+                lang_items::LanguageItems::item_name(item as usize)
+            }).collect(),
+            native_libraries: root.native_libraries.decode(self).map(|lib| {
+                NativeLibraryJson {
+                    name: lib.name.to_string(),
+                    kind: format!("{:?}", lib.kind),
+                }
+            }).collect(),
+            items: items,
+            macros: macros,
+        };
+        writeln!(out, "{}", json::as_json(&crate_json))
+    }
+}
+
+#[derive(RustcEncodable)]
+struct CrateRootJson {
+    name: String,
+    rustc_version: String,
+    triple: String,
+    hash: String,
+    disambiguator: String,
+    panic_strategy: String,
+    dependencies: Vec<DependencyJson>,
+    lang_items: Vec<LangItemJson>,
+    lang_items_missing: Vec<&'static str>,
+    native_libraries: Vec<NativeLibraryJson>,
+    items: Vec<ItemJson>,
+    macros: Vec<MacroJson>,
+}
+
+#[derive(RustcEncodable)]
+struct DependencyJson {
+    name: String,
+    hash: String,
+    kind: String,
+}
+
+#[derive(RustcEncodable)]
+struct LangItemJson {
+    name: &'static str,
+    def_path: String,
+}
+
+#[derive(RustcEncodable)]
+struct NativeLibraryJson {
+    name: String,
+    kind: String,
+}
+
+#[derive(RustcEncodable)]
+struct ItemJson {
+    def_path: String,
+    kind: &'static str,
+    stability: Option<StabilityJson>,
+}
+
+#[derive(RustcEncodable)]
+struct StabilityJson {
+    level: &'static str,
+    feature: String,
+    since: Option<String>,
+}
+
+#[derive(RustcEncodable)]
+struct MacroJson {
+    name: String,
+    def_path: String,
+    body: String,
 }
 
 impl<'tcx> EntryKind<'tcx> {
+    fn description(&self) -> &'static str {
+        match *self {
+            EntryKind::Const => "const",
+            EntryKind::ImmStatic |
+            EntryKind::MutStatic |
+            EntryKind::ForeignImmStatic |
+            EntryKind::ForeignMutStatic => "static",
+            EntryKind::ForeignMod => "foreign_mod",
+            EntryKind::Type => "type",
+            EntryKind::Enum => "enum",
+            EntryKind::Field => "field",
+            EntryKind::Variant(_) => "variant",
+            EntryKind::Struct(_) => "struct",
+            EntryKind::Union(_) => "union",
+            EntryKind::Fn(_) |
+            EntryKind::ForeignFn(_) => "fn",
+            EntryKind::Mod(_) => "mod",
+            EntryKind::MacroDef(_) => "macro",
+            EntryKind::Closure(_) => "closure",
+            EntryKind::Trait(_) => "trait",
+            EntryKind::Impl(_) |
+            EntryKind::DefaultImpl(_) => "impl",
+            EntryKind::Method(_) => "method",
+            EntryKind::AssociatedType(_) => "associated_type",
+            EntryKind::AssociatedConst(_) => "associated_const",
+        }
+    }
+
     fn to_def(&self, did: DefId) -> Option<Def> {
         Some(match *self {
             EntryKind::Const => Def::Const(did),
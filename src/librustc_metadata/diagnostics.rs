@@ -106,4 +106,5 @@ register_diagnostics! {
     E0465, // multiple .. candidates for `..` found
     E0519, // local crate and dependency have same (crate-name, disambiguator)
     E0523, // two dependencies have same (crate-name, disambiguator) but different SVH
+    E0581, // found crate with corrupted metadata
 }
@@ -1396,5 +1396,9 @@ pub fn encode_metadata<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     result[header + 2] = (pos >> 8) as u8;
     result[header + 3] = (pos >> 0) as u8;
 
+    // Seal everything with a checksum, which the locator verifies.
+    let checksum = metadata_checksum(&result);
+    result.extend_from_slice(&checksum);
+
     result
 }
@@ -269,6 +269,7 @@ pub struct Context<'a> {
     pub rejected_via_triple: Vec<CrateMismatch>,
     pub rejected_via_kind: Vec<CrateMismatch>,
     pub rejected_via_version: Vec<CrateMismatch>,
+    pub rejected_via_checksum: Vec<CrateMismatch>,
     pub rejected_via_filename: Vec<CrateMismatch>,
     pub should_match_name: bool,
     pub is_proc_macro: Option<bool>,
@@ -355,6 +356,13 @@ impl<'a> Context<'a> {
                              "found crate `{}` compiled by an incompatible version of rustc{}",
                              self.ident,
                              add)
+        } else if !self.rejected_via_checksum.is_empty() {
+            struct_span_err!(self.sess,
+                             self.span,
+                             E0581,
+                             "found crate `{}` with corrupted metadata{}",
+                             self.ident,
+                             add)
         } else {
             let mut err = struct_span_err!(self.sess,
                                            self.span,
@@ -418,6 +426,18 @@ impl<'a> Context<'a> {
                                   got));
             }
         }
+        if !self.rejected_via_checksum.is_empty() {
+            err.help("the file may be truncated or have been overwritten while being read; \
+                      please recompile that crate");
+            let mismatches = self.rejected_via_checksum.iter();
+            for (i, &CrateMismatch { ref path, ref got }) in mismatches.enumerate() {
+                err.note(&format!("crate `{}` path #{}: {} compiled by {:?}",
+                                  self.ident,
+                                  i + 1,
+                                  path.display(),
+                                  got));
+            }
+        }
         if !self.rejected_via_filename.is_empty() {
             let dylibname = self.dylibname();
             let mismatches = self.rejected_via_filename.iter();
@@ -665,6 +685,15 @@ impl<'a> Context<'a> {
             return None;
         }
 
+        if !metadata.verify_checksum() {
+            info!("Rejecting via checksum");
+            self.rejected_via_checksum.push(CrateMismatch {
+                path: libpath.to_path_buf(),
+                got: found_version,
+            });
+            return None;
+        }
+
         let root = metadata.get_root();
         if let Some(is_proc_macro) = self.is_proc_macro {
             if root.macro_derive_registrar.is_some() != is_proc_macro {
@@ -969,6 +998,22 @@ pub fn read_meta_section_name(_target: &Target) -> &'static str {
 
 // A diagnostic function for dumping crate metadata to an output stream
 pub fn list_file_metadata(target: &Target, path: &Path, out: &mut io::Write) -> io::Result<()> {
+    list_file_metadata_as(target, path, out, false)
+}
+
+// Like `list_file_metadata`, but dumps the whole crate root as JSON
+pub fn list_file_metadata_json(target: &Target,
+                               path: &Path,
+                               out: &mut io::Write)
+                               -> io::Result<()> {
+    list_file_metadata_as(target, path, out, true)
+}
+
+fn list_file_metadata_as(target: &Target,
+                         path: &Path,
+                         out: &mut io::Write,
+                         json: bool)
+                         -> io::Result<()> {
     let filename = path.file_name().unwrap().to_str().unwrap();
     let flavor = if filename.ends_with(".rlib") {
         CrateFlavor::Rlib
@@ -978,6 +1023,7 @@ pub fn list_file_metadata(target: &Target, path: &Path, out: &mut io::Write) ->
         CrateFlavor::Dylib
     };
     match get_metadata_section(target, flavor, path) {
+        Ok(ref metadata) if json => metadata.list_crate_metadata_json(out),
         Ok(metadata) => metadata.list_crate_metadata(out),
         Err(msg) => write!(out, "{}\n", msg),
     }
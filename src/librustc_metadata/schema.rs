@@ -19,6 +19,7 @@ use rustc::middle::lang_items;
 use rustc::mir;
 use rustc::ty::{self, Ty};
 use rustc_back::PanicStrategy;
+use rustc_data_structures::blake2b::blake2b;
 
 use rustc_serialize as serialize;
 use syntax::{ast, attr};
@@ -29,15 +30,22 @@ use std::marker::PhantomData;
 
 use rustc_i128::u128;
 
+/// The version string recorded in, and compared against, crate metadata.
+/// It includes the full commit hash when one is known, so that two builds
+/// of the same release from different sources don't load each other's
+/// crates.
 pub fn rustc_version() -> String {
-    format!("rustc {}",
-            option_env!("CFG_VERSION").unwrap_or("unknown version"))
+    let version = option_env!("CFG_VERSION").unwrap_or("unknown version");
+    match option_env!("CFG_VER_HASH") {
+        Some(hash) => format!("rustc {} (commit {})", version, hash),
+        None => format!("rustc {}", version),
+    }
 }
 
 /// Metadata encoding version.
 /// NB: increment this if you change the format of metadata such that
 /// the rustc version can't be found to compare with `rustc_version()`.
-pub const METADATA_VERSION: u8 = 4;
+pub const METADATA_VERSION: u8 = 5;
 
 /// Metadata header which includes `METADATA_VERSION`.
 /// To get older versions of rustc to ignore this metadata,
@@ -46,10 +54,23 @@ pub const METADATA_VERSION: u8 = 4;
 ///
 /// This header is followed by the position of the `CrateRoot`,
 /// which is encoded as a 32-bit big-endian unsigned integer,
-/// and further followed by the rustc version string.
+/// and further followed by the rustc version string. The metadata
+/// ends with a checksum, see `metadata_checksum`.
 pub const METADATA_HEADER: &'static [u8; 12] =
     &[0, 0, 0, 0, b'r', b'u', b's', b't', 0, 0, 0, METADATA_VERSION];
 
+/// Length of the checksum which ends the metadata.
+pub const METADATA_CHECKSUM_LEN: usize = 8;
+
+/// Computes the checksum appended to the metadata, covering every byte
+/// before it. This is what lets the locator tell a truncated or otherwise
+/// corrupted crate apart from one that just needs decoding.
+pub fn metadata_checksum(data: &[u8]) -> [u8; METADATA_CHECKSUM_LEN] {
+    let mut checksum = [0; METADATA_CHECKSUM_LEN];
+    blake2b(&mut checksum, &[], data);
+    checksum
+}
+
 /// The shorthand encoding uses an enum's variant index `usize`
 /// and is offset by this value so it never matches a real variant.
 /// This offset is also chosen so that the first byte is never < 0x80.
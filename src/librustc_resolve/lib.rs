@@ -1348,6 +1348,46 @@ impl<'a> Resolver<'a> {
         self.crate_loader.postprocess(krate);
     }
 
+    /// Resolves `path`, `::`-separated segments written as in the module
+    /// `module_id`, after the whole crate has been resolved. This is used by
+    /// tools, e.g. for the paths in documentation. Gives the definition of
+    /// the longest prefix of the path which names an item, along with the
+    /// number of segments which follow that prefix (e.g., the name of an
+    /// associated item).
+    pub fn resolve_str_path(&mut self, module_id: NodeId, path: &str, is_value: bool)
+                            -> Option<(Def, usize)> {
+        let module_def_id = self.definitions.local_def_id(module_id);
+        let module = match self.module_map.get(&module_def_id) {
+            Some(&module) => module,
+            None => return None,
+        };
+        let path = path.split("::").enumerate().map(|(i, segment)| {
+            if i == 0 && segment.is_empty() {
+                keywords::CrateRoot.ident()
+            } else {
+                Ident::from_str(segment)
+            }
+        }).collect::<Vec<_>>();
+        let ns = if is_value { ValueNS } else { TypeNS };
+
+        let orig_module = replace(&mut self.current_module, module);
+        self.ribs[ValueNS].push(Rib::new(ModuleRibKind(module)));
+        self.ribs[TypeNS].push(Rib::new(ModuleRibKind(module)));
+
+        let result = match self.resolve_path(&path, Some(ns), None) {
+            PathResult::Module(module) => module.def().map(|def| (def, 0)),
+            PathResult::NonModule(path_res) if path_res.base_def != Def::Err => {
+                Some((path_res.base_def, path_res.depth))
+            }
+            _ => None,
+        };
+
+        self.current_module = orig_module;
+        self.ribs[ValueNS].pop();
+        self.ribs[TypeNS].pop();
+        result
+    }
+
     fn new_module(&self, parent: Module<'a>, kind: ModuleKind, normal_ancestor_id: DefId)
                   -> Module<'a> {
         self.arenas.alloc_module(ModuleData::new(Some(parent), kind, normal_ancestor_id))
@@ -138,6 +138,16 @@ pub struct EnumData {
     pub sig: Signature,
 }
 
+/// Data for the inferred type of an expression.
+#[derive(Debug, RustcEncodable)]
+pub struct ExprTypeData {
+    pub span: Span,
+    pub scope: NodeId,
+    pub value: String,
+    // The definition of the type's outermost ADT, if there is one.
+    pub type_id: Option<DefId>,
+}
+
 /// Data for extern crates.
 #[derive(Debug, RustcEncodable)]
 pub struct ExternCrateData {
@@ -229,6 +239,9 @@ pub struct MacroUseData {
     // Because macro expansion happens before ref-ids are determined,
     // we use the callee span to reference the associated macro definition.
     pub callee_span: Span,
+    // The whole invocation at the source callsite, `span` may only cover the
+    // macro's name.
+    pub callsite_span: Span,
     pub scope: NodeId,
     pub imported: bool,
 }
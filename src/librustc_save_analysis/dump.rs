@@ -13,6 +13,7 @@ use super::external_data::*;
 pub trait Dump {
     fn crate_prelude(&mut self, CratePreludeData) {}
     fn enum_data(&mut self, EnumData) {}
+    fn expr_type(&mut self, ExprTypeData) {}
     fn extern_crate(&mut self, ExternCrateData) {}
     fn impl_data(&mut self, ImplData) {}
     fn inheritance(&mut self, InheritanceData) {}
@@ -1118,6 +1118,7 @@ impl<'l, 'tcx: 'l, 'll, D: Dump + 'll> DumpVisitor<'l, 'tcx, 'll, D> {
                     qualname: qualname,
                     scope: data.scope,
                     callee_span: data.callee_span,
+                    callsite_span: data.callsite_span,
                     imported: data.imported,
                 }.lower(self.tcx));
             }
@@ -1372,6 +1373,9 @@ impl<'l, 'tcx: 'l, 'll, D: Dump +'ll> Visitor<'l> for DumpVisitor<'l, 'tcx, 'll,
     fn visit_expr(&mut self, ex: &'l ast::Expr) {
         debug!("visit_expr {:?}", ex.node);
         self.process_macro_use(ex.span, ex.id);
+        if let Some(type_data) = self.save_ctxt.get_expr_type_data(ex) {
+            self.dumper.expr_type(type_data.lower(self.tcx));
+        }
         match ex.node {
             ast::ExprKind::Call(ref _f, ref _args) => {
                 // Don't need to do anything for function calls,
@@ -119,6 +119,28 @@ impl Lower for data::EnumData {
     }
 }
 
+/// Data for the inferred type of an expression.
+#[derive(Debug, RustcEncodable)]
+pub struct ExprTypeData {
+    pub span: SpanData,
+    pub scope: DefId,
+    pub value: String,
+    pub type_id: Option<DefId>,
+}
+
+impl Lower for data::ExprTypeData {
+    type Target = ExprTypeData;
+
+    fn lower(self, tcx: TyCtxt) -> ExprTypeData {
+        ExprTypeData {
+            span: SpanData::from_span(self.span, tcx.sess.codemap()),
+            scope: make_def_id(self.scope, &tcx.hir),
+            value: self.value,
+            type_id: self.type_id,
+        }
+    }
+}
+
 /// Data for extern crates.
 #[derive(Debug, RustcEncodable)]
 pub struct ExternCrateData {
@@ -293,6 +315,7 @@ pub struct MacroUseData {
     // Because macro expansion happens before ref-ids are determined,
     // we use the callee span to reference the associated macro definition.
     pub callee_span: SpanData,
+    pub callsite_span: SpanData,
     pub scope: DefId,
 }
 
@@ -305,6 +328,7 @@ impl Lower for data::MacroUseData {
             name: self.name,
             qualname: self.qualname,
             callee_span: SpanData::from_span(self.callee_span, tcx.sess.codemap()),
+            callsite_span: SpanData::from_span(self.callsite_span, tcx.sess.codemap()),
             scope: make_def_id(self.scope, &tcx.hir),
         }
     }
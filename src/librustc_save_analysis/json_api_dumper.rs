@@ -24,16 +24,28 @@ use super::Format;
 // information here, and (for example) generate Rustdoc URLs, but don't need
 // information for navigating the source of the crate.
 // Relative to the regular JSON save-analysis info, this form is filtered to
-// remove non-visible items, but includes some extra info for items (e.g., the
-// parent field for finding the struct to which a field belongs).
+// remove non-visible items (unless created with `with_private_items`), but
+// includes some extra info for items (e.g., the parent field for finding the
+// struct to which a field belongs).
 pub struct JsonApiDumper<'b, W: Write + 'b> {
     output: &'b mut W,
     result: Analysis,
+    include_private: bool,
 }
 
 impl<'b, W: Write> JsonApiDumper<'b, W> {
     pub fn new(writer: &'b mut W) -> JsonApiDumper<'b, W> {
-        JsonApiDumper { output: writer, result: Analysis::new() }
+        JsonApiDumper { output: writer, result: Analysis::new(), include_private: false }
+    }
+
+    /// Like `new`, but keeps the items which aren't public too, e.g., for
+    /// indexing a crate whose source isn't distributed.
+    pub fn with_private_items(writer: &'b mut W) -> JsonApiDumper<'b, W> {
+        JsonApiDumper { output: writer, result: Analysis::new(), include_private: true }
+    }
+
+    fn is_visible(&self, vis: Visibility) -> bool {
+        self.include_private || vis == Visibility::Public
     }
 }
 
@@ -46,13 +58,24 @@ impl<'b, W: Write> Drop for JsonApiDumper<'b, W> {
 }
 
 macro_rules! impl_fn {
+    // For the kinds of data which have a visibility.
+    (visible $fn_name: ident, $data_type: ident, $bucket: ident) => {
+        fn $fn_name(&mut self, data: $data_type) {
+            if !self.is_visible(data.visibility) {
+                return;
+            }
+            if let Some(datum) = From::from(data) {
+                self.result.$bucket.push(datum);
+            }
+        }
+    };
     ($fn_name: ident, $data_type: ident, $bucket: ident) => {
         fn $fn_name(&mut self, data: $data_type) {
             if let Some(datum) = From::from(data) {
                 self.result.$bucket.push(datum);
             }
         }
-    }
+    };
 }
 
 impl<'b, W: Write + 'b> Dump for JsonApiDumper<'b, W> {
@@ -60,20 +83,20 @@ impl<'b, W: Write + 'b> Dump for JsonApiDumper<'b, W> {
         self.result.prelude = Some(data)
     }
 
-    impl_fn!(use_data, UseData, imports);
-    impl_fn!(use_glob, UseGlobData, imports);
+    impl_fn!(visible use_data, UseData, imports);
+    impl_fn!(visible use_glob, UseGlobData, imports);
 
-    impl_fn!(enum_data, EnumData, defs);
+    impl_fn!(visible enum_data, EnumData, defs);
     impl_fn!(tuple_variant, TupleVariantData, defs);
     impl_fn!(struct_variant, StructVariantData, defs);
-    impl_fn!(struct_data, StructData, defs);
-    impl_fn!(trait_data, TraitData, defs);
-    impl_fn!(function, FunctionData, defs);
-    impl_fn!(method, MethodData, defs);
+    impl_fn!(visible struct_data, StructData, defs);
+    impl_fn!(visible trait_data, TraitData, defs);
+    impl_fn!(visible function, FunctionData, defs);
+    impl_fn!(visible method, MethodData, defs);
     impl_fn!(macro_data, MacroData, defs);
-    impl_fn!(mod_data, ModData, defs);
-    impl_fn!(typedef, TypeDefData, defs);
-    impl_fn!(variable, VariableData, defs);
+    impl_fn!(visible mod_data, ModData, defs);
+    impl_fn!(visible typedef, TypeDefData, defs);
+    impl_fn!(visible variable, VariableData, defs);
 }
 
 // FIXME methods. The defs have information about possible overriding and the
@@ -87,10 +110,12 @@ struct Analysis {
     prelude: Option<CratePreludeData>,
     imports: Vec<Import>,
     defs: Vec<Def>,
-    // These two fields are dummies so that clients can parse the two kinds of
+    // These fields are dummies so that clients can parse the two kinds of
     // JSON data in the same way.
     refs: Vec<()>,
     macro_refs: Vec<()>,
+    relations: Vec<()>,
+    expr_types: Vec<()>,
 }
 
 impl Analysis {
@@ -102,6 +127,8 @@ impl Analysis {
             defs: vec![],
             refs: vec![],
             macro_refs: vec![],
+            relations: vec![],
+            expr_types: vec![],
         }
     }
 }
@@ -140,30 +167,24 @@ enum ImportKind {
 
 impl From<UseData> for Option<Import> {
     fn from(data: UseData) -> Option<Import> {
-        match data.visibility {
-            Visibility::Public => Some(Import {
-                kind: ImportKind::Use,
-                id: From::from(data.id),
-                span: data.span,
-                name: data.name,
-                value: String::new(),
-            }),
-            _ => None,
-        }
+        Some(Import {
+            kind: ImportKind::Use,
+            id: From::from(data.id),
+            span: data.span,
+            name: data.name,
+            value: String::new(),
+        })
     }
 }
 impl From<UseGlobData> for Option<Import> {
     fn from(data: UseGlobData) -> Option<Import> {
-        match data.visibility {
-            Visibility::Public => Some(Import {
-                kind: ImportKind::GlobUse,
-                id: From::from(data.id),
-                span: data.span,
-                name: "*".to_owned(),
-                value: data.names.join(", "),
-            }),
-            _ => None,
-        }
+        Some(Import {
+            kind: ImportKind::GlobUse,
+            id: From::from(data.id),
+            span: data.span,
+            name: "*".to_owned(),
+            value: data.names.join(", "),
+        })
     }
 }
 
@@ -210,22 +231,19 @@ enum DefKind {
 
 impl From<EnumData> for Option<Def> {
     fn from(data: EnumData) -> Option<Def> {
-        match data.visibility {
-            Visibility::Public => Some(Def {
-                kind: DefKind::Enum,
-                id: From::from(data.id),
-                span: data.span,
-                name: data.name,
-                qualname: data.qualname,
-                value: data.value,
-                parent: None,
-                children: data.variants.into_iter().map(|id| From::from(id)).collect(),
-                decl_id: None,
-                docs: data.docs,
-                sig: Some(From::from(data.sig)),
-            }),
-            _ => None,
-        }
+        Some(Def {
+            kind: DefKind::Enum,
+            id: From::from(data.id),
+            span: data.span,
+            name: data.name,
+            qualname: data.qualname,
+            value: data.value,
+            parent: None,
+            children: data.variants.into_iter().map(|id| From::from(id)).collect(),
+            decl_id: None,
+            docs: data.docs,
+            sig: Some(From::from(data.sig)),
+        })
     }
 }
 
@@ -265,8 +283,7 @@ impl From<StructVariantData> for Option<Def> {
 }
 impl From<StructData> for Option<Def> {
     fn from(data: StructData) -> Option<Def> {
-        match data.visibility {
-            Visibility::Public => Some(Def {
+        Some(Def {
             kind: DefKind::Struct,
             id: From::from(data.id),
             span: data.span,
@@ -278,69 +295,58 @@ impl From<StructData> for Option<Def> {
             decl_id: None,
             docs: data.docs,
             sig: Some(From::from(data.sig)),
-        }),
-            _ => None,
-        }
+        })
     }
 }
 impl From<TraitData> for Option<Def> {
     fn from(data: TraitData) -> Option<Def> {
-        match data.visibility {
-            Visibility::Public => Some(Def {
-                kind: DefKind::Trait,
-                id: From::from(data.id),
-                span: data.span,
-                name: data.name,
-                qualname: data.qualname,
-                value: data.value,
-                children: data.items.into_iter().map(|id| From::from(id)).collect(),
-                parent: None,
-                decl_id: None,
-                docs: data.docs,
-                sig: Some(From::from(data.sig)),
-            }),
-            _ => None,
-        }
+        Some(Def {
+            kind: DefKind::Trait,
+            id: From::from(data.id),
+            span: data.span,
+            name: data.name,
+            qualname: data.qualname,
+            value: data.value,
+            children: data.items.into_iter().map(|id| From::from(id)).collect(),
+            parent: None,
+            decl_id: None,
+            docs: data.docs,
+            sig: Some(From::from(data.sig)),
+        })
     }
 }
 impl From<FunctionData> for Option<Def> {
     fn from(data: FunctionData) -> Option<Def> {
-        match data.visibility {
-            Visibility::Public => Some(Def {
-                kind: DefKind::Function,
-                id: From::from(data.id),
-                span: data.span,
-                name: data.name,
-                qualname: data.qualname,
-                value: data.value,
-                children: vec![],
-                parent: data.parent.map(|id| From::from(id)),
-                decl_id: None,
-                docs: data.docs,
-                sig: Some(From::from(data.sig)),
-            }),
-            _ => None,
-        }
+        Some(Def {
+            kind: DefKind::Function,
+            id: From::from(data.id),
+            span: data.span,
+            name: data.name,
+            qualname: data.qualname,
+            value: data.value,
+            children: vec![],
+            parent: data.parent.map(|id| From::from(id)),
+            decl_id: None,
+            docs: data.docs,
+            sig: Some(From::from(data.sig)),
+        })
     }
 }
 impl From<MethodData> for Option<Def> {
     fn from(data: MethodData) -> Option<Def> {
-        match data.visibility {
-            Visibility::Public => Some(Def {
-                kind: DefKind::Method,
-                id: From::from(data.id),
-                span: data.span,
-                name: data.name,
-                qualname: data.qualname,
-                value: data.value,
-                children: vec![],
-                parent: data.parent.map(|id| From::from(id)),
-                decl_id: data.decl_id.map(|id| From::from(id)),
-                docs: data.docs,
-                sig: Some(From::from(data.sig)),
-            }),
-            _ => None,
-        }
+        Some(Def {
+            kind: DefKind::Method,
+            id: From::from(data.id),
+            span: data.span,
+            name: data.name,
+            qualname: data.qualname,
+            value: data.value,
+            children: vec![],
+            parent: data.parent.map(|id| From::from(id)),
+            decl_id: data.decl_id.map(|id| From::from(id)),
+            docs: data.docs,
+            sig: Some(From::from(data.sig)),
+        })
     }
 }
 impl From<MacroData> for Option<Def> {
@@ -362,68 +368,59 @@ impl From<MacroData> for Option<Def> {
 }
 impl From<ModData> for Option<Def> {
     fn from(data:ModData) -> Option<Def> {
-        match data.visibility {
-            Visibility::Public => Some(Def {
-                kind: DefKind::Mod,
-                id: From::from(data.id),
-                span: data.span,
-                name: data.name,
-                qualname: data.qualname,
-                value: data.filename,
-                children: data.items.into_iter().map(|id| From::from(id)).collect(),
-                parent: None,
-                decl_id: None,
-                docs: data.docs,
-                sig: Some(From::from(data.sig)),
-            }),
-            _ => None,
-        }
+        Some(Def {
+            kind: DefKind::Mod,
+            id: From::from(data.id),
+            span: data.span,
+            name: data.name,
+            qualname: data.qualname,
+            value: data.filename,
+            children: data.items.into_iter().map(|id| From::from(id)).collect(),
+            parent: None,
+            decl_id: None,
+            docs: data.docs,
+            sig: Some(From::from(data.sig)),
+        })
     }
 }
 impl From<TypeDefData> for Option<Def> {
     fn from(data: TypeDefData) -> Option<Def> {
-        match data.visibility {
-            Visibility::Public => Some(Def {
-                kind: DefKind::Type,
-                id: From::from(data.id),
-                span: data.span,
-                name: data.name,
-                qualname: data.qualname,
-                value: data.value,
-                children: vec![],
-                parent: data.parent.map(|id| From::from(id)),
-                decl_id: None,
-                docs: String::new(),
-                sig: data.sig.map(|s| From::from(s)),
-            }),
-            _ => None,
-        }
+        Some(Def {
+            kind: DefKind::Type,
+            id: From::from(data.id),
+            span: data.span,
+            name: data.name,
+            qualname: data.qualname,
+            value: data.value,
+            children: vec![],
+            parent: data.parent.map(|id| From::from(id)),
+            decl_id: None,
+            docs: String::new(),
+            sig: data.sig.map(|s| From::from(s)),
+        })
     }
 }
 
 impl From<VariableData> for Option<Def> {
     fn from(data: VariableData) -> Option<Def> {
-        match data.visibility {
-            Visibility::Public => Some(Def {
-                kind: match data.kind {
-                    VariableKind::Static => DefKind::Static,
-                    VariableKind::Const => DefKind::Const,
-                    VariableKind::Local => { return None }
-                    VariableKind::Field => DefKind::Field,
-                },
-                id: From::from(data.id),
-                span: data.span,
-                name: data.name,
-                qualname: data.qualname,
-                value: data.value,
-                children: vec![],
-                parent: data.parent.map(|id| From::from(id)),
-                decl_id: None,
-                docs: data.docs,
-                sig: data.sig.map(|s| From::from(s)),
-            }),
-            _ => None,
-        }
+        Some(Def {
+            kind: match data.kind {
+                VariableKind::Static => DefKind::Static,
+                VariableKind::Const => DefKind::Const,
+                VariableKind::Local => { return None }
+                VariableKind::Field => DefKind::Field,
+            },
+            id: From::from(data.id),
+            span: data.span,
+            name: data.name,
+            qualname: data.qualname,
+            value: data.value,
+            children: vec![],
+            parent: data.parent.map(|id| From::from(id)),
+            decl_id: None,
+            docs: data.docs,
+            sig: data.sig.map(|s| From::from(s)),
+        })
     }
 }
 
@@ -18,18 +18,33 @@ use data::{VariableKind, SigElement};
 use dump::Dump;
 use super::Format;
 
-pub struct JsonDumper<'b, W: Write + 'b> {
+/// Receives the records produced by a `JsonDumper`.
+pub trait DumpOutput {
+    fn record(&mut self, record: Record);
+}
+
+/// Buffers the records of the whole crate and writes them as a single JSON
+/// object once the dumper is dropped.
+pub struct WriteOutput<'b, W: Write + 'b> {
     output: &'b mut W,
     result: Analysis,
 }
 
-impl<'b, W: Write> JsonDumper<'b, W> {
-    pub fn new(writer: &'b mut W) -> JsonDumper<'b, W> {
-        JsonDumper { output: writer, result: Analysis::new() }
+impl<'b, W: Write> DumpOutput for WriteOutput<'b, W> {
+    fn record(&mut self, record: Record) {
+        match record {
+            Record::Prelude(data) => self.result.prelude = Some(data),
+            Record::Import(data) => self.result.imports.push(data),
+            Record::Def(data) => self.result.defs.push(data),
+            Record::Ref(data) => self.result.refs.push(data),
+            Record::MacroRef(data) => self.result.macro_refs.push(data),
+            Record::Relation(data) => self.result.relations.push(data),
+            Record::ExprType(data) => self.result.expr_types.push(data),
+        }
     }
 }
 
-impl<'b, W: Write> Drop for JsonDumper<'b, W> {
+impl<'b, W: Write> Drop for WriteOutput<'b, W> {
     fn drop(&mut self) {
         if let Err(_) = write!(self.output, "{}", as_json(&self.result)) {
             error!("Error writing output");
@@ -37,42 +52,92 @@ impl<'b, W: Write> Drop for JsonDumper<'b, W> {
     }
 }
 
+/// Writes every record as soon as it is produced, as one JSON object per
+/// line. Unlike `WriteOutput`, memory use does not grow with the crate.
+pub struct StreamOutput<'b, W: Write + 'b> {
+    output: &'b mut W,
+}
+
+impl<'b, W: Write> DumpOutput for StreamOutput<'b, W> {
+    fn record(&mut self, record: Record) {
+        if let Err(_) = writeln!(self.output, "{}", as_json(&record)) {
+            error!("Error writing output");
+        }
+    }
+}
+
+/// Hands every record to a callback, for tools running save-analysis
+/// in-process.
+pub struct CallbackOutput<'b> {
+    callback: &'b mut FnMut(Record),
+}
+
+impl<'b> DumpOutput for CallbackOutput<'b> {
+    fn record(&mut self, record: Record) {
+        (self.callback)(record)
+    }
+}
+
+pub struct JsonDumper<O: DumpOutput> {
+    output: O,
+}
+
+impl<'b, W: Write> JsonDumper<WriteOutput<'b, W>> {
+    pub fn new(writer: &'b mut W) -> JsonDumper<WriteOutput<'b, W>> {
+        JsonDumper { output: WriteOutput { output: writer, result: Analysis::new() } }
+    }
+}
+
+impl<'b, W: Write> JsonDumper<StreamOutput<'b, W>> {
+    pub fn streaming(writer: &'b mut W) -> JsonDumper<StreamOutput<'b, W>> {
+        JsonDumper { output: StreamOutput { output: writer } }
+    }
+}
+
+impl<'b> JsonDumper<CallbackOutput<'b>> {
+    pub fn with_callback(callback: &'b mut FnMut(Record)) -> JsonDumper<CallbackOutput<'b>> {
+        JsonDumper { output: CallbackOutput { callback: callback } }
+    }
+}
+
 macro_rules! impl_fn {
-    ($fn_name: ident, $data_type: ident, $bucket: ident) => {
+    ($fn_name: ident, $data_type: ident, $record: ident) => {
         fn $fn_name(&mut self, data: $data_type) {
-            self.result.$bucket.push(From::from(data));
+            self.output.record(Record::$record(From::from(data)));
         }
     }
 }
 
-impl<'b, W: Write + 'b> Dump for JsonDumper<'b, W> {
+impl<O: DumpOutput> Dump for JsonDumper<O> {
     fn crate_prelude(&mut self, data: CratePreludeData) {
-        self.result.prelude = Some(data)
+        self.output.record(Record::Prelude(data))
     }
 
-    impl_fn!(extern_crate, ExternCrateData, imports);
-    impl_fn!(use_data, UseData, imports);
-    impl_fn!(use_glob, UseGlobData, imports);
+    impl_fn!(extern_crate, ExternCrateData, Import);
+    impl_fn!(use_data, UseData, Import);
+    impl_fn!(use_glob, UseGlobData, Import);
+
+    impl_fn!(enum_data, EnumData, Def);
+    impl_fn!(tuple_variant, TupleVariantData, Def);
+    impl_fn!(struct_variant, StructVariantData, Def);
+    impl_fn!(struct_data, StructData, Def);
+    impl_fn!(trait_data, TraitData, Def);
+    impl_fn!(function, FunctionData, Def);
+    impl_fn!(method, MethodData, Def);
+    impl_fn!(macro_data, MacroData, Def);
+    impl_fn!(typedef, TypeDefData, Def);
+    impl_fn!(variable, VariableData, Def);
 
-    impl_fn!(enum_data, EnumData, defs);
-    impl_fn!(tuple_variant, TupleVariantData, defs);
-    impl_fn!(struct_variant, StructVariantData, defs);
-    impl_fn!(struct_data, StructData, defs);
-    impl_fn!(trait_data, TraitData, defs);
-    impl_fn!(function, FunctionData, defs);
-    impl_fn!(method, MethodData, defs);
-    impl_fn!(macro_data, MacroData, defs);
-    impl_fn!(typedef, TypeDefData, defs);
-    impl_fn!(variable, VariableData, defs);
+    impl_fn!(function_ref, FunctionRefData, Ref);
+    impl_fn!(function_call, FunctionCallData, Ref);
+    impl_fn!(method_call, MethodCallData, Ref);
+    impl_fn!(mod_ref, ModRefData, Ref);
+    impl_fn!(type_ref, TypeRefData, Ref);
+    impl_fn!(variable_ref, VariableRefData, Ref);
 
-    impl_fn!(function_ref, FunctionRefData, refs);
-    impl_fn!(function_call, FunctionCallData, refs);
-    impl_fn!(method_call, MethodCallData, refs);
-    impl_fn!(mod_ref, ModRefData, refs);
-    impl_fn!(type_ref, TypeRefData, refs);
-    impl_fn!(variable_ref, VariableRefData, refs);
+    impl_fn!(macro_use, MacroUseData, MacroRef);
 
-    impl_fn!(macro_use, MacroUseData, macro_refs);
+    impl_fn!(expr_type, ExprTypeData, ExprType);
 
     fn mod_data(&mut self, data: ModData) {
         let id: Id = From::from(data.id);
@@ -85,6 +150,7 @@ impl<'b, W: Write + 'b> Dump for JsonDumper<'b, W> {
             value: data.filename,
             children: data.items.into_iter().map(|id| From::from(id)).collect(),
             decl_id: None,
+            parent: None,
             docs: data.docs,
             sig: Some(From::from(data.sig)),
         };
@@ -97,7 +163,7 @@ impl<'b, W: Write + 'b> Dump for JsonDumper<'b, W> {
                 span: def.span,
                 ref_id: id,
             };
-            self.result.refs.push(rf);
+            self.output.record(Record::Ref(rf));
             def.span = SpanData {
                 file_name: def.value.clone(),
                 byte_start: 0,
@@ -109,19 +175,36 @@ impl<'b, W: Write + 'b> Dump for JsonDumper<'b, W> {
             }
         }
 
-        self.result.defs.push(def);
+        self.output.record(Record::Def(def));
     }
 
-    // FIXME store this instead of throwing it away.
-    fn impl_data(&mut self, _data: ImplData) {}
-    fn inheritance(&mut self, _data: InheritanceData) {}
+    fn impl_data(&mut self, data: ImplData) {
+        let id: Id = From::from(data.id);
+        if let Some(self_id) = data.self_ref {
+            self.output.record(Record::Relation(Relation {
+                span: data.span.clone(),
+                kind: RelationKind::ImplType,
+                from: id,
+                to: From::from(self_id),
+            }));
+        }
+        if let Some(trait_id) = data.trait_ref {
+            self.output.record(Record::Relation(Relation {
+                span: data.span,
+                kind: RelationKind::ImplTrait,
+                from: id,
+                to: From::from(trait_id),
+            }));
+        }
+    }
+
+    impl_fn!(inheritance, InheritanceData, Relation);
 }
 
 // FIXME do we want to change ExternalData to this mode? It will break DXR.
-// FIXME methods. The defs have information about possible overriding and the
-// refs have decl information (e.g., a trait method where we know the required
-// method, but not the supplied method). In both cases, we are currently
-// ignoring it.
+// FIXME methods. The refs for method calls point at the impl method where we
+// can resolve it and at the trait method otherwise (e.g., a call through a
+// trait object or a generic receiver); the decl information is dropped.
 
 #[derive(Debug, RustcEncodable)]
 struct Analysis {
@@ -131,6 +214,20 @@ struct Analysis {
     defs: Vec<Def>,
     refs: Vec<Ref>,
     macro_refs: Vec<MacroRef>,
+    relations: Vec<Relation>,
+    expr_types: Vec<ExprType>,
+}
+
+/// A single piece of save-analysis data, as handed to a `DumpOutput`.
+#[derive(Debug, RustcEncodable)]
+pub enum Record {
+    Prelude(CratePreludeData),
+    Import(Import),
+    Def(Def),
+    Ref(Ref),
+    MacroRef(MacroRef),
+    Relation(Relation),
+    ExprType(ExprType),
 }
 
 impl Analysis {
@@ -142,6 +239,8 @@ impl Analysis {
             defs: vec![],
             refs: vec![],
             macro_refs: vec![],
+            relations: vec![],
+            expr_types: vec![],
         }
     }
 }
@@ -149,9 +248,9 @@ impl Analysis {
 // DefId::index is a newtype and so the JSON serialisation is ugly. Therefore
 // we use our own Id which is the same, but without the newtype.
 #[derive(Clone, Copy, Debug, RustcEncodable)]
-struct Id {
-    krate: u32,
-    index: u32,
+pub struct Id {
+    pub krate: u32,
+    pub index: u32,
 }
 
 impl From<DefId> for Id {
@@ -164,16 +263,16 @@ impl From<DefId> for Id {
 }
 
 #[derive(Debug, RustcEncodable)]
-struct Import {
-    kind: ImportKind,
-    ref_id: Option<Id>,
-    span: SpanData,
-    name: String,
-    value: String,
+pub struct Import {
+    pub kind: ImportKind,
+    pub ref_id: Option<Id>,
+    pub span: SpanData,
+    pub name: String,
+    pub value: String,
 }
 
 #[derive(Debug, RustcEncodable)]
-enum ImportKind {
+pub enum ImportKind {
     ExternCrate,
     Use,
     GlobUse,
@@ -214,21 +313,23 @@ impl From<UseGlobData> for Import {
 }
 
 #[derive(Debug, RustcEncodable)]
-struct Def {
-    kind: DefKind,
-    id: Id,
-    span: SpanData,
-    name: String,
-    qualname: String,
-    value: String,
-    children: Vec<Id>,
-    decl_id: Option<Id>,
-    docs: String,
-    sig: Option<JsonSignature>,
+pub struct Def {
+    pub kind: DefKind,
+    pub id: Id,
+    pub span: SpanData,
+    pub name: String,
+    pub qualname: String,
+    pub value: String,
+    pub children: Vec<Id>,
+    pub decl_id: Option<Id>,
+    // The impl, trait or ADT which this item belongs to, if any.
+    pub parent: Option<Id>,
+    pub docs: String,
+    pub sig: Option<JsonSignature>,
 }
 
 #[derive(Debug, RustcEncodable)]
-enum DefKind {
+pub enum DefKind {
     // value = variant names
     Enum,
     // value = enum name + variant name + types
@@ -265,6 +366,7 @@ impl From<EnumData> for Def {
             value: data.value,
             children: data.variants.into_iter().map(|id| From::from(id)).collect(),
             decl_id: None,
+            parent: None,
             docs: data.docs,
             sig: Some(From::from(data.sig)),
         }
@@ -282,6 +384,7 @@ impl From<TupleVariantData> for Def {
             value: data.value,
             children: vec![],
             decl_id: None,
+            parent: data.parent.map(|id| From::from(id)),
             docs: data.docs,
             sig: Some(From::from(data.sig)),
         }
@@ -298,6 +401,7 @@ impl From<StructVariantData> for Def {
             value: data.value,
             children: vec![],
             decl_id: None,
+            parent: data.parent.map(|id| From::from(id)),
             docs: data.docs,
             sig: Some(From::from(data.sig)),
         }
@@ -314,6 +418,7 @@ impl From<StructData> for Def {
             value: data.value,
             children: data.fields.into_iter().map(|id| From::from(id)).collect(),
             decl_id: None,
+            parent: None,
             docs: data.docs,
             sig: Some(From::from(data.sig)),
         }
@@ -330,6 +435,7 @@ impl From<TraitData> for Def {
             value: data.value,
             children: data.items.into_iter().map(|id| From::from(id)).collect(),
             decl_id: None,
+            parent: None,
             docs: data.docs,
             sig: Some(From::from(data.sig)),
         }
@@ -346,6 +452,7 @@ impl From<FunctionData> for Def {
             value: data.value,
             children: vec![],
             decl_id: None,
+            parent: data.parent.map(|id| From::from(id)),
             docs: data.docs,
             sig: Some(From::from(data.sig)),
         }
@@ -362,6 +469,7 @@ impl From<MethodData> for Def {
             value: data.value,
             children: vec![],
             decl_id: data.decl_id.map(|id| From::from(id)),
+            parent: data.parent.map(|id| From::from(id)),
             docs: data.docs,
             sig: Some(From::from(data.sig)),
         }
@@ -378,6 +486,7 @@ impl From<MacroData> for Def {
             value: String::new(),
             children: vec![],
             decl_id: None,
+            parent: None,
             docs: data.docs,
             sig: None,
         }
@@ -394,6 +503,7 @@ impl From<TypeDefData> for Def {
             value: data.value,
             children: vec![],
             decl_id: None,
+            parent: data.parent.map(|id| From::from(id)),
             docs: String::new(),
             sig: data.sig.map(|s| From::from(s)),
         }
@@ -415,6 +525,7 @@ impl From<VariableData> for Def {
             value: data.type_value,
             children: vec![],
             decl_id: None,
+            parent: data.parent.map(|id| From::from(id)),
             docs: data.docs,
             sig: None,
         }
@@ -422,7 +533,7 @@ impl From<VariableData> for Def {
 }
 
 #[derive(Debug, RustcEncodable)]
-enum RefKind {
+pub enum RefKind {
     Function,
     Mod,
     Type,
@@ -430,10 +541,10 @@ enum RefKind {
 }
 
 #[derive(Debug, RustcEncodable)]
-struct Ref {
-    kind: RefKind,
-    span: SpanData,
-    ref_id: Id,
+pub struct Ref {
+    pub kind: RefKind,
+    pub span: SpanData,
+    pub ref_id: Id,
 }
 
 impl From<FunctionRefData> for Ref {
@@ -492,10 +603,12 @@ impl From<VariableRefData> for Ref {
 }
 
 #[derive(Debug, RustcEncodable)]
-struct MacroRef {
-    span: SpanData,
-    qualname: String,
-    callee_span: SpanData,
+pub struct MacroRef {
+    pub span: SpanData,
+    pub qualname: String,
+    pub callee_span: SpanData,
+    // The whole invocation which was expanded, `span` only covers the name.
+    pub callsite_span: SpanData,
 }
 
 impl From<MacroUseData> for MacroRef {
@@ -504,18 +617,65 @@ impl From<MacroUseData> for MacroRef {
             span: data.span,
             qualname: data.qualname,
             callee_span: data.callee_span,
+            callsite_span: data.callsite_span,
+        }
+    }
+}
+
+#[derive(Debug, RustcEncodable)]
+pub enum RelationKind {
+    // from = impl, to = the implemented trait
+    ImplTrait,
+    // from = impl, to = the self type
+    ImplType,
+    // from = trait, to = a super-trait
+    SuperTrait,
+}
+
+#[derive(Debug, RustcEncodable)]
+pub struct Relation {
+    pub span: SpanData,
+    pub kind: RelationKind,
+    pub from: Id,
+    pub to: Id,
+}
+
+impl From<InheritanceData> for Relation {
+    fn from(data: InheritanceData) -> Relation {
+        Relation {
+            span: data.span,
+            kind: RelationKind::SuperTrait,
+            from: From::from(data.deriv_id),
+            to: From::from(data.base_id),
+        }
+    }
+}
+
+#[derive(Debug, RustcEncodable)]
+pub struct ExprType {
+    pub span: SpanData,
+    pub value: String,
+    pub ref_id: Option<Id>,
+}
+
+impl From<ExprTypeData> for ExprType {
+    fn from(data: ExprTypeData) -> ExprType {
+        ExprType {
+            span: data.span,
+            value: data.value,
+            ref_id: data.type_id.map(|id| From::from(id)),
         }
     }
 }
 
 #[derive(Debug, RustcEncodable)]
 pub struct JsonSignature {
-    span: SpanData,
-    text: String,
-    ident_start: usize,
-    ident_end: usize,
-    defs: Vec<JsonSigElement>,
-    refs: Vec<JsonSigElement>,
+    pub span: SpanData,
+    pub text: String,
+    pub ident_start: usize,
+    pub ident_end: usize,
+    pub defs: Vec<JsonSigElement>,
+    pub refs: Vec<JsonSigElement>,
 }
 
 impl From<Signature> for JsonSignature {
@@ -533,9 +693,9 @@ impl From<Signature> for JsonSignature {
 
 #[derive(Debug, RustcEncodable)]
 pub struct JsonSigElement {
-    id: Id,
-    start: usize,
-    end: usize,
+    pub id: Id,
+    pub start: usize,
+    pub end: usize,
 }
 
 impl From<SigElement> for JsonSigElement {
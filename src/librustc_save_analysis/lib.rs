@@ -45,7 +45,9 @@ use rustc::hir::def::Def;
 use rustc::hir::map::Node;
 use rustc::hir::def_id::DefId;
 use rustc::session::config::CrateType::CrateTypeExecutable;
-use rustc::ty::{self, TyCtxt};
+use rustc::traits::{self, Reveal, SelectionContext};
+use rustc::ty::{self, TyCtxt, TypeFoldable};
+use rustc::ty::subst::Substs;
 
 use std::env;
 use std::fs::File;
@@ -62,13 +64,23 @@ use syntax_pos::*;
 
 pub use self::csv_dumper::CsvDumper;
 pub use self::json_api_dumper::JsonApiDumper;
-pub use self::json_dumper::JsonDumper;
+pub use self::json_dumper::{JsonDumper, DumpOutput, Record};
+pub use self::json_dumper::{WriteOutput, StreamOutput, CallbackOutput};
 pub use self::data::*;
 pub use self::external_data::make_def_id;
 pub use self::dump::Dump;
 pub use self::dump_visitor::DumpVisitor;
 use self::span_utils::SpanUtils;
 
+/// The data handed to the callback of `process_crate_with_callback`. These
+/// are the same records the JSON formats are made of.
+pub mod records {
+    pub use json_dumper::{Record, Id, Import, ImportKind, Def, DefKind, Ref, RefKind};
+    pub use json_dumper::{MacroRef, Relation, RelationKind, ExprType};
+    pub use json_dumper::{JsonSignature, JsonSigElement};
+    pub use external_data::{CratePreludeData, SpanData};
+}
+
 // FIXME this is legacy code and should be removed
 pub mod recorder {
     pub use self::Row::*;
@@ -497,10 +509,14 @@ impl<'l, 'tcx: 'l> SaveContext<'l, 'tcx> {
             }
             ast::ExprKind::MethodCall(..) => {
                 let method_call = ty::MethodCall::expr(expr.id);
-                let method_id = self.tables.method_map[&method_call].def_id;
+                let method = self.tables.method_map[&method_call];
+                let method_id = method.def_id;
                 let (def_id, decl_id) = match self.tcx.associated_item(method_id).container {
                     ty::ImplContainer(_) => (Some(method_id), None),
-                    ty::TraitContainer(_) => (None, Some(method_id)),
+                    ty::TraitContainer(_) => {
+                        (self.resolve_trait_method(expr.span, method_id, method.substs),
+                         Some(method_id))
+                    }
                 };
                 let sub_span = self.span_utils.sub_span_for_meth_name(expr.span);
                 filter!(self.span_utils, sub_span, expr.span, None);
@@ -522,6 +538,65 @@ impl<'l, 'tcx: 'l> SaveContext<'l, 'tcx> {
         }
     }
 
+    /// Returns the inferred type of an expression, before any adjustments
+    /// (autoderef, autoref or coercions) are applied.
+    pub fn get_expr_type_data(&self, expr: &ast::Expr) -> Option<ExprTypeData> {
+        if generated_code(expr.span) {
+            return None;
+        }
+        // Not every AST expression survives lowering, e.g. parentheses.
+        let hir_node = match self.tcx.hir.find(expr.id) {
+            Some(Node::NodeExpr(expr)) => expr,
+            _ => return None,
+        };
+        let ty = option_try!(self.tables.expr_ty_opt(hir_node));
+        if ty.references_error() {
+            return None;
+        }
+        Some(ExprTypeData {
+            span: expr.span,
+            scope: self.enclosing_scope(expr.id),
+            value: ty.to_string(),
+            type_id: ty.ty_adt_def().map(|def| def.did),
+        })
+    }
+
+    /// Finds the method which a call to the trait method `method_id` dispatches
+    /// to. Returns `None` if that is only known after monomorphization or at
+    /// runtime, i.e., the receiver is generic or a trait object.
+    fn resolve_trait_method(&self,
+                            span: Span,
+                            method_id: DefId,
+                            substs: &'tcx Substs<'tcx>)
+                            -> Option<DefId> {
+        if substs.needs_infer() || substs.needs_subst() {
+            return None;
+        }
+        let trait_id = option_try!(self.tcx.trait_of_item(method_id));
+        let trait_ref = ty::Binder(ty::TraitRef::from_method(self.tcx, trait_id, substs));
+        let trait_ref = self.tcx.erase_regions(&trait_ref);
+
+        let impl_id = self.tcx.infer_ctxt((), Reveal::All).enter(|infcx| {
+            let mut selcx = SelectionContext::new(&infcx);
+            let cause = traits::ObligationCause::misc(span, ast::DUMMY_NODE_ID);
+            let obligation = traits::Obligation::new(cause, trait_ref.to_poly_trait_predicate());
+            match selcx.select(&obligation) {
+                Ok(Some(traits::VtableImpl(data))) => Some(data.impl_def_id),
+                _ => None,
+            }
+        });
+        let impl_id = option_try!(impl_id);
+
+        // The impl may not override the method, in which case a default from
+        // a less specialized impl or from the trait itself is called.
+        let name = self.tcx.associated_item(method_id).name;
+        let trait_def = self.tcx.lookup_trait_def(trait_id);
+        trait_def.ancestors(impl_id)
+                 .defs(self.tcx, name, ty::AssociatedKind::Method)
+                 .next()
+                 .map(|node_item| node_item.item.def_id)
+    }
+
     pub fn get_path_def(&self, id: NodeId) -> Def {
         match self.tcx.hir.get(id) {
             Node::NodeTraitRef(tr) => tr.path.def,
@@ -602,7 +677,17 @@ impl<'l, 'tcx: 'l> SaveContext<'l, 'tcx> {
             Def::Method(decl_id) => {
                 let sub_span = self.span_utils.sub_span_for_meth_name(path.span);
                 filter!(self.span_utils, sub_span, path.span, None);
-                let def_id = if decl_id.is_local() {
+                let resolved = self.tables.node_id_item_substs(id).and_then(|substs| {
+                    match self.tcx.associated_item(decl_id).container {
+                        ty::TraitContainer(_) => {
+                            self.resolve_trait_method(path.span, decl_id, substs)
+                        }
+                        ty::ImplContainer(_) => None,
+                    }
+                });
+                let def_id = if resolved.is_some() {
+                    resolved
+                } else if decl_id.is_local() {
                     let ti = self.tcx.associated_item(decl_id);
                     self.tcx.associated_items(ti.container.id())
                         .find(|item| item.name == ti.name && item.defaultness.has_value())
@@ -688,6 +773,7 @@ impl<'l, 'tcx: 'l> SaveContext<'l, 'tcx> {
                                         span: callsite,
                                         name: mac_name.clone(),
                                         callee_span: mac_span,
+                                        callsite_span: callsite,
                                         scope: self.enclosing_scope(id),
                                         imported: true,
                                         qualname: String::new()// FIXME: generate the real qualname
@@ -698,6 +784,7 @@ impl<'l, 'tcx: 'l> SaveContext<'l, 'tcx> {
             span: callsite,
             name: callee.name().to_string(),
             callee_span: callee_span,
+            callsite_span: callsite,
             scope: self.enclosing_scope(id),
             imported: false,
             qualname: String::new() // FIXME: generate the real qualname
@@ -836,6 +923,8 @@ pub enum Format {
     Csv,
     Json,
     JsonApi,
+    /// Newline-delimited JSON, one record per line.
+    JsonStream,
 }
 
 impl Format {
@@ -843,6 +932,7 @@ impl Format {
         match *self {
             Format::Csv => ".csv",
             Format::Json | Format::JsonApi => ".json",
+            Format::JsonStream => ".jsonl",
         }
     }
 }
@@ -897,6 +987,44 @@ pub fn process_crate<'l, 'tcx>(tcx: TyCtxt<'l, 'tcx, 'tcx>,
     root_path.pop();
     let output = &mut output_file;
 
+    match format {
+        Format::Csv => dump_crate(tcx, krate, analysis, cratename, &mut CsvDumper::new(output)),
+        Format::Json => dump_crate(tcx, krate, analysis, cratename, &mut JsonDumper::new(output)),
+        Format::JsonApi => {
+            let mut dumper = if tcx.sess.opts.debugging_opts.save_analysis_api_private {
+                JsonApiDumper::with_private_items(output)
+            } else {
+                JsonApiDumper::new(output)
+            };
+            dump_crate(tcx, krate, analysis, cratename, &mut dumper)
+        }
+        Format::JsonStream => {
+            dump_crate(tcx, krate, analysis, cratename, &mut JsonDumper::streaming(output))
+        }
+    }
+}
+
+/// Like `process_crate`, but rather than writing a file, hands each record to
+/// `callback` as soon as it has been produced.
+pub fn process_crate_with_callback<'l, 'tcx>(tcx: TyCtxt<'l, 'tcx, 'tcx>,
+                                             krate: &ast::Crate,
+                                             analysis: &'l ty::CrateAnalysis<'tcx>,
+                                             cratename: &str,
+                                             callback: &mut FnMut(Record)) {
+    let _ignore = tcx.dep_graph.in_ignore();
+
+    assert!(analysis.glob_map.is_some());
+
+    info!("Dumping crate {}", cratename);
+
+    dump_crate(tcx, krate, analysis, cratename, &mut JsonDumper::with_callback(callback));
+}
+
+fn dump_crate<'l, 'tcx, D: Dump>(tcx: TyCtxt<'l, 'tcx, 'tcx>,
+                                 krate: &'l ast::Crate,
+                                 analysis: &'l ty::CrateAnalysis<'tcx>,
+                                 cratename: &str,
+                                 dumper: &mut D) {
     let save_ctxt = SaveContext {
         tcx: tcx,
         tables: &ty::TypeckTables::empty(),
@@ -904,21 +1032,10 @@ pub fn process_crate<'l, 'tcx>(tcx: TyCtxt<'l, 'tcx, 'tcx>,
         span_utils: SpanUtils::new(&tcx.sess),
     };
 
-    macro_rules! dump {
-        ($new_dumper: expr) => {{
-            let mut dumper = $new_dumper;
-            let mut visitor = DumpVisitor::new(save_ctxt, &mut dumper);
+    let mut visitor = DumpVisitor::new(save_ctxt, dumper);
 
-            visitor.dump_crate_info(cratename, krate);
-            visit::walk_crate(&mut visitor, krate);
-        }}
-    }
-
-    match format {
-        Format::Csv => dump!(CsvDumper::new(output)),
-        Format::Json => dump!(JsonDumper::new(output)),
-        Format::JsonApi => dump!(JsonApiDumper::new(output)),
-    }
+    visitor.dump_crate_info(cratename, krate);
+    visit::walk_crate(&mut visitor, krate);
 }
 
 // Utility functions for the module.
@@ -10,6 +10,7 @@
 
 use super::archive::{ArchiveBuilder, ArchiveConfig};
 use super::linker::Linker;
+use super::lto;
 use super::rpath::RPathConfig;
 use super::rpath;
 use super::msvc;
@@ -22,7 +23,7 @@ use session::Session;
 use middle::cstore::{self, LinkMeta, NativeLibrary, LibSource};
 use middle::cstore::{LinkagePreference, NativeLibraryKind};
 use middle::dependency_format::Linkage;
-use CrateTranslation;
+use {CrateTranslation, EncodedMetadata};
 use util::common::time;
 use util::fs::fix_windows_verbatim_for_gcc;
 use rustc::dep_graph::DepNode;
@@ -208,7 +209,7 @@ pub fn link_binary(sess: &Session,
     // Remove the temporary object file and metadata if we aren't saving temps
     if !sess.opts.cg.save_temps {
         if sess.opts.output_types.should_trans() {
-            for obj in object_filenames(trans, outputs) {
+            for obj in object_filenames(sess, trans, outputs) {
                 remove(sess, &obj);
             }
         }
@@ -218,6 +219,32 @@ pub fn link_binary(sess: &Session,
     out_filenames
 }
 
+/// Writes the outputs of a metadata-only build, which is just the `.rmeta`
+/// file, if one was requested. Nothing is linked in that case.
+pub fn link_metadata_only(sess: &Session,
+                          metadata: &EncodedMetadata,
+                          outputs: &OutputFilenames,
+                          crate_name: &str) -> Vec<PathBuf> {
+    let _task = sess.dep_graph.in_task(DepNode::LinkBinary);
+
+    let mut out_filenames = Vec::new();
+    if outputs.outputs.contains_key(&OutputType::Metadata) {
+        out_filenames.push(write_rmeta(sess, &metadata.metadata, outputs, crate_name));
+    }
+    out_filenames
+}
+
+/// Writes the crate's `.rmeta` file and lets the build system know about it,
+/// see `Session::emit_artifact_notification`.
+pub fn write_rmeta(sess: &Session,
+                   metadata: &[u8],
+                   outputs: &OutputFilenames,
+                   crate_name: &str) -> PathBuf {
+    let out_filename = filename_for_metadata(sess, crate_name, outputs);
+    emit_metadata(sess, metadata, &out_filename);
+    sess.emit_artifact_notification(&out_filename, "metadata");
+    out_filename
+}
 
 /// Returns default crate type for target
 ///
@@ -363,7 +390,7 @@ fn link_binary_output(sess: &Session,
                       crate_type: config::CrateType,
                       outputs: &OutputFilenames,
                       crate_name: &str) -> Vec<PathBuf> {
-    let objects = object_filenames(trans, outputs);
+    let objects = object_filenames(sess, trans, outputs);
 
     for file in &objects {
         check_file_is_writeable(file, sess);
@@ -376,12 +403,6 @@ fn link_binary_output(sess: &Session,
 
     let mut out_filenames = vec![];
 
-    if outputs.outputs.contains_key(&OutputType::Metadata) {
-        let out_filename = filename_for_metadata(sess, crate_name, outputs);
-        emit_metadata(sess, trans, &out_filename);
-        out_filenames.push(out_filename);
-    }
-
     if outputs.outputs.should_trans() {
         let out_filename = out_filename(sess, crate_type, outputs, crate_name);
         match crate_type {
@@ -403,12 +424,23 @@ fn link_binary_output(sess: &Session,
     out_filenames
 }
 
-fn object_filenames(trans: &CrateTranslation,
+fn object_filenames(sess: &Session,
+                    trans: &CrateTranslation,
                     outputs: &OutputFilenames)
                     -> Vec<PathBuf> {
-    trans.modules.iter().map(|module| {
+    let mut objects: Vec<PathBuf> = trans.modules.iter().map(|module| {
         outputs.temp_path(OutputType::Object, Some(&module.name[..]))
-    }).collect()
+    }).collect();
+
+    // With ThinLTO the modules of upstream crates are translated along with
+    // our own, and their objects replace the ones in the rlibs.
+    if sess.thin_lto() {
+        objects.extend(lto::upstream_module_names(sess).iter().map(|name| {
+            outputs.temp_path(OutputType::Object, Some(&name[..]))
+        }));
+    }
+
+    objects
 }
 
 fn archive_search_paths(sess: &Session) -> Vec<PathBuf> {
@@ -432,8 +464,8 @@ fn archive_config<'a>(sess: &'a Session,
     }
 }
 
-fn emit_metadata<'a>(sess: &'a Session, trans: &CrateTranslation, out_filename: &Path) {
-    let result = fs::File::create(out_filename).and_then(|mut f| f.write_all(&trans.metadata));
+fn emit_metadata<'a>(sess: &'a Session, metadata: &[u8], out_filename: &Path) {
+    let result = fs::File::create(out_filename).and_then(|mut f| f.write_all(metadata));
     if let Err(e) = result {
         sess.fatal(&format!("failed to write {}: {}", out_filename.display(), e));
     }
@@ -857,6 +889,12 @@ fn link_args(cmd: &mut Linker,
     // Pass debuginfo flags down to the linker.
     cmd.debuginfo();
 
+    // Make sure the profiling runtime is retained when instrumenting.
+    if sess.opts.cg.profile_generate.is_some() ||
+       sess.opts.debugging_opts.instrument_coverage {
+        cmd.pgo_gen();
+    }
+
     // We want to prevent the compiler from accidentally leaking in any system
     // libraries, so we explicitly ask gcc to not link to any libraries by
     // default. Note that this does not happen for windows because windows pulls
@@ -93,6 +93,7 @@ pub trait Linker {
     fn no_whole_archives(&mut self);
     fn export_symbols(&mut self, tmpdir: &Path, crate_type: CrateType);
     fn subsystem(&mut self, subsystem: &str);
+    fn pgo_gen(&mut self);
 }
 
 pub struct GnuLinker<'a> {
@@ -305,6 +306,16 @@ impl<'a> Linker for GnuLinker<'a> {
     fn subsystem(&mut self, subsystem: &str) {
         self.cmd.arg(&format!("-Wl,--subsystem,{}", subsystem));
     }
+
+    fn pgo_gen(&mut self) {
+        if !self.sess.target.target.options.linker_is_gnu { return }
+
+        // Instrumented objects only reference the profiling runtime through
+        // the registration hooks LLVM emits, which GNU-style linkers happily
+        // drop when the runtime archive is seen. Force the runtime in so that
+        // profiles actually get written out at exit.
+        self.cmd.arg("-Wl,-u,__llvm_profile_runtime");
+    }
 }
 
 pub struct MsvcLinker<'a> {
@@ -485,6 +496,11 @@ impl<'a> Linker for MsvcLinker<'a> {
             self.cmd.arg("/ENTRY:mainCRTStartup");
         }
     }
+
+    fn pgo_gen(&mut self) {
+        // Nothing needed here, the runtime is pulled in through the
+        // `__llvm_profile_runtime_user` reference LLVM emits on COFF targets.
+    }
 }
 
 fn exported_symbols(scx: &SharedCrateContext,
@@ -14,17 +14,24 @@ use back::symbol_export::{self, ExportedSymbols};
 use rustc::session::{self, config};
 use llvm;
 use llvm::archive_ro::ArchiveRO;
-use llvm::{ModuleRef, TargetMachineRef, True, False};
+use llvm::{ContextRef, ModuleRef, TargetMachineRef, True, False};
 use rustc::util::common::time;
 use rustc::util::common::path2cstr;
 use rustc::hir::def_id::LOCAL_CRATE;
 use back::write::{ModuleConfig, with_llvm_pmb};
+use errors::Handler;
 
-use libc;
+use libc::{self, c_uint};
 use flate;
 
 use std::ffi::CString;
-use std::path::Path;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::sync::Arc;
+
+const BYTECODE_SUFFIX: &'static str = ".bytecode.deflate";
 
 pub fn crate_type_allows_lto(crate_type: config::CrateType) -> bool {
     match crate_type {
@@ -38,12 +45,8 @@ pub fn crate_type_allows_lto(crate_type: config::CrateType) -> bool {
     }
 }
 
-pub fn run(sess: &session::Session,
-           llmod: ModuleRef,
-           tm: TargetMachineRef,
-           exported_symbols: &ExportedSymbols,
-           config: &ModuleConfig,
-           temp_no_opt_bc_filename: &Path) {
+/// Aborts compilation if LTO can't be performed for the requested outputs.
+pub fn check_lto_allowed(sess: &session::Session) {
     if sess.opts.cg.prefer_dynamic {
         sess.struct_err("cannot prefer dynamic linking when performing LTO")
             .note("only 'staticlib', 'bin', and 'cdylib' outputs are \
@@ -59,7 +62,14 @@ pub fn run(sess: &session::Session,
                             static library outputs");
         }
     }
+}
 
+/// The symbols which have to stay visible after LTO: those exported by the
+/// local crate and those of upstream crates taking part in LTO which are
+/// exported from the final artifact. Everything else can be internalized.
+fn symbol_white_list(sess: &session::Session,
+                     exported_symbols: &ExportedSymbols)
+                     -> Vec<CString> {
     let export_threshold =
         symbol_export::crates_export_threshold(&sess.crate_types.borrow()[..]);
 
@@ -79,10 +89,7 @@ pub fn run(sess: &session::Session,
         .filter_map(symbol_filter)
         .collect();
 
-    // For each of our upstream dependencies, find the corresponding rlib and
-    // load the bitcode from the archive. Then merge it into the current LLVM
-    // module that we've got.
-    link::each_linked_rlib(sess, &mut |cnum, path| {
+    link::each_linked_rlib(sess, &mut |cnum, _| {
         // `#![no_builtins]` crates don't participate in LTO.
         if sess.cstore.is_no_builtins(cnum) {
             return;
@@ -92,65 +99,107 @@ pub fn run(sess: &session::Session,
             exported_symbols.exported_symbols(cnum)
                             .iter()
                             .filter_map(symbol_filter));
+    });
+
+    symbol_white_list
+}
+
+/// Calls `f` with the name and the encoded bytecode of every module of the
+/// upstream crates taking part in LTO, as found in their rlibs.
+fn each_upstream_module(sess: &session::Session,
+                        f: &mut FnMut(&str, &[u8])) {
+    link::each_linked_rlib(sess, &mut |cnum, path| {
+        // `#![no_builtins]` crates don't participate in LTO.
+        if sess.cstore.is_no_builtins(cnum) {
+            return;
+        }
 
         let archive = ArchiveRO::open(&path).expect("wanted an rlib");
         let bytecodes = archive.iter().filter_map(|child| {
             child.ok().and_then(|c| c.name().map(|name| (name, c)))
-        }).filter(|&(name, _)| name.ends_with("bytecode.deflate"));
+        }).filter(|&(name, _)| name.ends_with(BYTECODE_SUFFIX));
         for (name, data) in bytecodes {
-            let bc_encoded = data.data();
-
-            let bc_decoded = if is_versioned_bytecode_format(bc_encoded) {
-                time(sess.time_passes(), &format!("decode {}", name), || {
-                    // Read the version
-                    let version = extract_bytecode_format_version(bc_encoded);
-
-                    if version == 1 {
-                        // The only version existing so far
-                        let data_size = extract_compressed_bytecode_size_v1(bc_encoded);
-                        let compressed_data = &bc_encoded[
-                            link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET..
-                            (link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET + data_size as usize)];
-
-                        match flate::inflate_bytes(compressed_data) {
-                            Ok(inflated) => inflated,
-                            Err(_) => {
-                                sess.fatal(&format!("failed to decompress bc of `{}`",
-                                                   name))
-                            }
-                        }
-                    } else {
-                        sess.fatal(&format!("Unsupported bytecode format version {}",
-                                           version))
+            f(&name[..name.len() - BYTECODE_SUFFIX.len()], data.data());
+        }
+    });
+}
+
+/// The names of the modules of upstream crates which are translated locally
+/// when performing ThinLTO, in place of the object files of their rlibs.
+pub fn upstream_module_names(sess: &session::Session) -> Vec<String> {
+    let mut names = Vec::new();
+    each_upstream_module(sess, &mut |name, _| names.push(name.to_string()));
+    names
+}
+
+/// Decodes the LLVM bitcode stored in an rlib under `name`.
+fn decode_bytecode(sess: &session::Session, name: &str, bc_encoded: &[u8]) -> Vec<u8> {
+    if is_versioned_bytecode_format(bc_encoded) {
+        time(sess.time_passes(), &format!("decode {}", name), || {
+            // Read the version
+            let version = extract_bytecode_format_version(bc_encoded);
+
+            if version == 1 {
+                // The only version existing so far
+                let data_size = extract_compressed_bytecode_size_v1(bc_encoded);
+                let compressed_data = &bc_encoded[
+                    link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET..
+                    (link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET + data_size as usize)];
+
+                match flate::inflate_bytes(compressed_data) {
+                    Ok(inflated) => inflated.to_vec(),
+                    Err(_) => {
+                        sess.fatal(&format!("failed to decompress bc of `{}`",
+                                           name))
                     }
-                })
+                }
             } else {
-                time(sess.time_passes(), &format!("decode {}", name), || {
-                    // the object must be in the old, pre-versioning format, so
-                    // simply inflate everything and let LLVM decide if it can
-                    // make sense of it
-                    match flate::inflate_bytes(bc_encoded) {
-                        Ok(bc) => bc,
-                        Err(_) => {
-                            sess.fatal(&format!("failed to decompress bc of `{}`",
-                                               name))
-                        }
-                    }
-                })
-            };
-
-            let ptr = bc_decoded.as_ptr();
-            debug!("linking {}", name);
-            time(sess.time_passes(), &format!("ll link {}", name), || unsafe {
-                if !llvm::LLVMRustLinkInExternalBitcode(llmod,
-                                                        ptr as *const libc::c_char,
-                                                        bc_decoded.len() as libc::size_t) {
-                    write::llvm_err(sess.diagnostic(),
-                                    format!("failed to load bc of `{}`",
-                                            &name[..]));
+                sess.fatal(&format!("Unsupported bytecode format version {}",
+                                   version))
+            }
+        })
+    } else {
+        time(sess.time_passes(), &format!("decode {}", name), || {
+            // the object must be in the old, pre-versioning format, so
+            // simply inflate everything and let LLVM decide if it can
+            // make sense of it
+            match flate::inflate_bytes(bc_encoded) {
+                Ok(bc) => bc.to_vec(),
+                Err(_) => {
+                    sess.fatal(&format!("failed to decompress bc of `{}`",
+                                       name))
                 }
-            });
-        }
+            }
+        })
+    }
+}
+
+pub fn run(sess: &session::Session,
+           llmod: ModuleRef,
+           tm: TargetMachineRef,
+           exported_symbols: &ExportedSymbols,
+           config: &ModuleConfig,
+           temp_no_opt_bc_filename: &Path) {
+    check_lto_allowed(sess);
+
+    let symbol_white_list = symbol_white_list(sess, exported_symbols);
+
+    // For each of our upstream dependencies, find the corresponding rlib and
+    // load the bitcode from the archive. Then merge it into the current LLVM
+    // module that we've got.
+    each_upstream_module(sess, &mut |name, bc_encoded| {
+        let bc_decoded = decode_bytecode(sess, name, bc_encoded);
+
+        let ptr = bc_decoded.as_ptr();
+        debug!("linking {}", name);
+        time(sess.time_passes(), &format!("ll link {}", name), || unsafe {
+            if !llvm::LLVMRustLinkInExternalBitcode(llmod,
+                                                    ptr as *const libc::c_char,
+                                                    bc_decoded.len() as libc::size_t) {
+                write::llvm_err(sess.diagnostic(),
+                                format!("failed to load bc of `{}`", name));
+            }
+        });
     });
 
     // Internalize everything but the exported symbols of the current module
@@ -208,6 +257,215 @@ pub fn run(sess: &session::Session,
     debug!("lto done");
 }
 
+/// A module serialized along with its ThinLTO summary.
+pub struct ThinBuffer(llvm::ThinLTOBufferRef);
+
+unsafe impl Send for ThinBuffer {}
+unsafe impl Sync for ThinBuffer {}
+
+impl ThinBuffer {
+    pub fn new(llmod: ModuleRef) -> ThinBuffer {
+        unsafe {
+            ThinBuffer(llvm::LLVMRustThinLTOBufferCreate(llmod))
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        unsafe {
+            let ptr = llvm::LLVMRustThinLTOBufferPtr(self.0) as *const u8;
+            let len = llvm::LLVMRustThinLTOBufferLen(self.0);
+            slice::from_raw_parts(ptr, len)
+        }
+    }
+}
+
+impl Drop for ThinBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            llvm::LLVMRustThinLTOBufferFree(self.0);
+        }
+    }
+}
+
+/// The result of the ThinLTO analysis over all modules.
+struct ThinData(llvm::ThinLTODataRef);
+
+unsafe impl Send for ThinData {}
+unsafe impl Sync for ThinData {}
+
+impl Drop for ThinData {
+    fn drop(&mut self) {
+        unsafe {
+            llvm::LLVMRustFreeThinLTOData(self.0);
+        }
+    }
+}
+
+struct ThinShared {
+    data: ThinData,
+    // The serialized modules, which `data` refers to.
+    buffers: Vec<Vec<u8>>,
+    module_names: Vec<CString>,
+}
+
+/// One of the modules taking part in ThinLTO. After the analysis over all
+/// modules is done, each of them can be optimized and translated on its
+/// own, and in parallel with the others.
+pub struct ThinModule {
+    shared: Arc<ThinShared>,
+    idx: usize,
+}
+
+impl ThinModule {
+    pub fn name(&self) -> &str {
+        self.shared.module_names[self.idx].to_str().unwrap()
+    }
+
+    /// Loads this module into `llcx`, applies the results of the analysis
+    /// to it (promotion of symbols used by other modules, resolution of weak
+    /// symbols and internalization) and imports the functions it uses from
+    /// other modules, then runs the ThinLTO optimization pipeline over it.
+    pub unsafe fn optimize(&self,
+                           handler: &Handler,
+                           llcx: ContextRef,
+                           tm: TargetMachineRef,
+                           config: &ModuleConfig)
+                           -> ModuleRef {
+        let data = self.shared.data.0;
+        let buffer = &self.shared.buffers[self.idx];
+        let llmod = llvm::LLVMRustParseBitcodeForThinLTO(llcx,
+                                                         buffer.as_ptr(),
+                                                         buffer.len(),
+                                                         self.shared.module_names[self.idx]
+                                                             .as_ptr());
+        if llmod.is_null() {
+            write::llvm_err(handler, format!("failed to parse bitcode for thin LTO module `{}`",
+                                             self.name()));
+        }
+
+        if !llvm::LLVMRustPrepareThinLTORename(data, llmod) {
+            write::llvm_err(handler, "failed to prepare thin LTO module".to_string());
+        }
+        if !llvm::LLVMRustPrepareThinLTOResolveWeak(data, llmod) {
+            write::llvm_err(handler, "failed to prepare thin LTO module".to_string());
+        }
+        if !llvm::LLVMRustPrepareThinLTOInternalize(data, llmod) {
+            write::llvm_err(handler, "failed to prepare thin LTO module".to_string());
+        }
+        if !llvm::LLVMRustPrepareThinLTOImport(data, llmod) {
+            write::llvm_err(handler, "failed to prepare thin LTO module".to_string());
+        }
+
+        let pm = llvm::LLVMCreatePassManager();
+        llvm::LLVMRustAddAnalysisPasses(tm, pm, llmod);
+        let pass = llvm::LLVMRustFindAndCreatePass("verify\0".as_ptr() as *const _);
+        assert!(!pass.is_null());
+        llvm::LLVMRustAddPass(pm, pass);
+
+        with_llvm_pmb(llmod, config, &mut |b| {
+            llvm::LLVMRustPassManagerBuilderPopulateThinLTOPassManager(b, pm);
+        });
+
+        let pass = llvm::LLVMRustFindAndCreatePass("verify\0".as_ptr() as *const _);
+        assert!(!pass.is_null());
+        llvm::LLVMRustAddPass(pm, pass);
+
+        llvm::LLVMRunPassManager(pm, llmod);
+        llvm::LLVMDisposePassManager(pm);
+
+        llmod
+    }
+}
+
+/// Prepares ThinLTO over the local modules in `modules`, given as their
+/// names along with the files their ThinLTO bitcode was written to, and the
+/// modules of all upstream crates taking part in LTO.
+///
+/// This loads the summaries of all modules and runs the whole-program
+/// analysis over them, which decides what gets imported where and what can
+/// be internalized. The returned modules are ready to be optimized.
+pub fn run_thin(sess: &session::Session,
+                exported_symbols: &ExportedSymbols,
+                modules: Vec<(String, PathBuf)>)
+                -> Vec<ThinModule> {
+    check_lto_allowed(sess);
+
+    let symbol_white_list = symbol_white_list(sess, exported_symbols);
+
+    let mut module_names = Vec::new();
+    let mut buffers = Vec::new();
+
+    for (name, path) in modules {
+        let mut buffer = Vec::new();
+        if let Err(e) = File::open(&path).and_then(|mut f| f.read_to_end(&mut buffer)) {
+            sess.fatal(&format!("failed to read bitcode of `{}` from {}: {}",
+                                name, path.display(), e));
+        }
+        module_names.push(CString::new(name).unwrap());
+        buffers.push(buffer);
+    }
+
+    // Upstream crates only ship plain bitcode without a summary, so each of
+    // their modules is loaded and written back out along with one.
+    each_upstream_module(sess, &mut |name, bc_encoded| {
+        let bc_decoded = decode_bytecode(sess, name, bc_encoded);
+        let cname = CString::new(name).unwrap();
+
+        debug!("summarizing {}", name);
+        let buffer = time(sess.time_passes(), &format!("summarize {}", name), || unsafe {
+            let llcx = llvm::LLVMContextCreate();
+            let llmod = llvm::LLVMRustParseBitcodeForThinLTO(llcx,
+                                                             bc_decoded.as_ptr(),
+                                                             bc_decoded.len(),
+                                                             cname.as_ptr());
+            if llmod.is_null() {
+                write::llvm_err(sess.diagnostic(),
+                                format!("failed to load bc of `{}`", name));
+            }
+            let buffer = ThinBuffer::new(llmod).data().to_vec();
+            llvm::LLVMDisposeModule(llmod);
+            llvm::LLVMContextDispose(llcx);
+            buffer
+        });
+
+        module_names.push(cname);
+        buffers.push(buffer);
+    });
+
+    let thin_modules = module_names.iter().zip(&buffers).map(|(name, buffer)| {
+        llvm::ThinLTOModule {
+            identifier: name.as_ptr(),
+            data: buffer.as_ptr(),
+            len: buffer.len(),
+        }
+    }).collect::<Vec<_>>();
+    let arr: Vec<*const libc::c_char> = symbol_white_list.iter()
+                                                         .map(|c| c.as_ptr())
+                                                         .collect();
+
+    let data = time(sess.time_passes(), "thin lto index", || unsafe {
+        llvm::LLVMRustCreateThinLTOData(thin_modules.as_ptr(),
+                                        thin_modules.len() as c_uint,
+                                        arr.as_ptr(),
+                                        arr.len() as c_uint)
+    });
+    if data.is_null() {
+        write::llvm_err(sess.diagnostic(), "failed to prepare thin LTO context".to_string());
+    }
+
+    let shared = Arc::new(ThinShared {
+        data: ThinData(data),
+        buffers: buffers,
+        module_names: module_names,
+    });
+    (0..shared.module_names.len()).map(|idx| {
+        ThinModule {
+            shared: shared.clone(),
+            idx: idx,
+        }
+    }).collect()
+}
+
 fn is_versioned_bytecode_format(bc: &[u8]) -> bool {
     let magic_id_byte_count = link::RLIB_BYTECODE_OBJECT_MAGIC.len();
     return bc.len() > magic_id_byte_count &&
@@ -379,3 +379,87 @@ fn mangle<PI: Iterator<Item=InternedString>>(path: PI, hash: &str) -> String {
     n.push('E'); // End name-sequence.
     n
 }
+
+/// Reverses `mangle` and `sanitize` for display purposes, e.g. turning
+/// `_ZN3foo3bar17h05af221e174051e9E` into `foo::bar`. Symbols which don't look
+/// like they were produced by `mangle` are returned unchanged.
+pub fn demangle(symbol: &str) -> String {
+    if !(symbol.len() > 4 && symbol.starts_with("_ZN") && symbol.ends_with("E")) {
+        return symbol.to_string();
+    }
+
+    // Split the name-sequence into its <len, name> components.
+    let mut components = Vec::new();
+    let mut inner = &symbol[3 .. symbol.len() - 1];
+    while !inner.is_empty() {
+        let digits = inner.chars().take_while(|c| c.is_digit(10)).count();
+        let end = match inner[..digits].parse::<usize>() {
+            Ok(len) if len > 0 && digits + len <= inner.len() => digits + len,
+            _ => return symbol.to_string(),
+        };
+        if !inner.is_char_boundary(end) {
+            return symbol.to_string();
+        }
+        components.push(&inner[digits..end]);
+        inner = &inner[end..];
+    }
+
+    // The last component is the symbol hash, which is just noise for humans.
+    if let Some(hash) = components.last().cloned() {
+        if components.len() > 1 && hash.len() == 17 && hash.starts_with("h") &&
+           hash[1..].chars().all(|c| c.is_digit(16)) {
+            components.pop();
+        }
+    }
+
+    let mut result = String::new();
+    for (i, component) in components.into_iter().enumerate() {
+        if i > 0 {
+            result.push_str("::");
+        }
+
+        // Undo the underscore-qualification done by `sanitize`.
+        let mut rest = if component.starts_with("_$") { &component[1..] } else { component };
+        while !rest.is_empty() {
+            if rest.starts_with("..") {
+                result.push_str("::");
+                rest = &rest[2..];
+            } else if rest.starts_with("$") {
+                let end = match rest[1..].find('$') {
+                    Some(end) => end + 2,
+                    None => {
+                        result.push_str(rest);
+                        break
+                    }
+                };
+                let escaped = match &rest[..end] {
+                    "$SP$" => Some('@'),
+                    "$BP$" => Some('*'),
+                    "$RF$" => Some('&'),
+                    "$LT$" => Some('<'),
+                    "$GT$" => Some('>'),
+                    "$LP$" => Some('('),
+                    "$RP$" => Some(')'),
+                    "$C$" => Some(','),
+                    code if code.starts_with("$u") => {
+                        u32::from_str_radix(&code[2 .. end - 1], 16).ok()
+                            .and_then(::std::char::from_u32)
+                    }
+                    _ => None,
+                };
+                match escaped {
+                    Some(c) => result.push(c),
+                    None => result.push_str(&rest[..end]),
+                }
+                rest = &rest[end..];
+            } else {
+                let end = rest.char_indices().skip(1)
+                              .find(|&(_, c)| c == '$' || c == '.')
+                              .map_or(rest.len(), |(i, _)| i);
+                result.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+        }
+    }
+    result
+}
@@ -11,6 +11,7 @@
 use back::lto;
 use back::link::{get_linker, remove};
 use back::symbol_export::ExportedSymbols;
+use back::symbol_names;
 use rustc_incremental::{save_trans_partition, in_incr_comp_dir};
 use session::config::{OutputFilenames, OutputTypes, Passes, SomePasses, AllPasses};
 use session::Session;
@@ -22,14 +23,18 @@ use {CrateTranslation, ModuleLlvm, ModuleSource, ModuleTranslation};
 use util::common::{time, time_depth, set_time_depth};
 use util::common::path2cstr;
 use util::fs::link_or_copy;
+use util::nodemap::FxHashMap;
 use errors::{self, Handler, Level, DiagnosticBuilder};
 use errors::emitter::Emitter;
 use syntax_pos::MultiSpan;
 use context::{is_pie_binary, get_reloc_model};
 
-use std::ffi::CString;
-use std::fs;
+use std::cmp;
+use std::ffi::{CStr, CString};
+use std::fs::{self, File};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::ptr;
 use std::str;
 use std::sync::{Arc, Mutex};
 use std::sync::mpsc::channel;
@@ -135,6 +140,68 @@ impl Emitter for SharedEmitter {
     }
 }
 
+// The stack frame and machine code sizes of the functions we generate code
+// for, collected from all worker threads for `-Z print-fn-sizes`.
+#[derive(Clone)]
+struct SharedFnSizes {
+    stack_sizes: Arc<Mutex<Vec<(String, u64)>>>,
+    code_sizes: Arc<Mutex<Vec<(String, u64)>>>,
+}
+
+impl SharedFnSizes {
+    fn new() -> SharedFnSizes {
+        SharedFnSizes {
+            stack_sizes: Arc::new(Mutex::new(Vec::new())),
+            code_sizes: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    unsafe fn record_code_sizes(&self, handler: &Handler, tm: TargetMachineRef, obj: &Path) {
+        let obj_c = path2cstr(obj);
+        let mut ok = true;
+        let sizes = llvm::build_string(|s| {
+            ok = llvm::LLVMRustGetObjectFunctionSizes(tm, obj_c.as_ptr(), s);
+        });
+        if !ok {
+            let msg = format!("failed to read function sizes from {}", obj.display());
+            match llvm::last_error() {
+                Some(err) => handler.warn(&format!("{}: {}", msg, err)),
+                None => handler.warn(&msg),
+            }
+            return
+        }
+
+        let mut code_sizes = self.code_sizes.lock().unwrap();
+        for line in sizes.as_ref().map_or("", |s| &s[..]).lines() {
+            let mut parts = line.rsplitn(2, '\t');
+            let size = parts.next().and_then(|size| size.parse().ok());
+            if let (Some(size), Some(name)) = (size, parts.next()) {
+                code_sizes.push((name.to_string(), size));
+            }
+        }
+    }
+
+    fn record(&self, sess: &Session) {
+        // A function which LLVM did not report a stack size for doesn't have
+        // a stack frame.
+        let mut sizes = FxHashMap();
+        for &(ref name, size) in self.stack_sizes.lock().unwrap().iter() {
+            sizes.entry(name.clone()).or_insert((0, None)).0 = size;
+        }
+        for &(ref name, size) in self.code_sizes.lock().unwrap().iter() {
+            sizes.entry(name.clone()).or_insert((0, None)).1 = Some(size);
+        }
+
+        let mut code_stats = sess.code_stats.borrow_mut();
+        for (symbol_name, (stack_size, code_size)) in sizes {
+            code_stats.record_fn_size(symbol_names::demangle(&symbol_name),
+                                      symbol_name,
+                                      stack_size,
+                                      code_size);
+        }
+    }
+}
+
 // On android, we by default compile for armv7 processors. This enables
 // things like double word CAS instructions (rather than emulating them)
 // which are *far* more efficient. This is obviously undesirable in some
@@ -251,8 +318,17 @@ pub struct ModuleConfig {
     /// Some(level) to optimize binary size, or None to not affect program size.
     opt_size: Option<llvm::CodeGenOptSize>,
 
+    /// Some(path) to instrument the module for profiling, writing the raw
+    /// profile to the given file pattern at runtime.
+    pgo_gen: Option<String>,
+    /// Some(path) to a merged profile used to guide optimizations.
+    pgo_use: Option<String>,
+
     // Flags indicating which outputs to produce.
     emit_no_opt_bc: bool,
+    // Write the optimized module along with its ThinLTO summary in place of
+    // the regular outputs, which are produced after ThinLTO.
+    emit_pre_thin_lto_bc: bool,
     emit_bc: bool,
     emit_lto_bc: bool,
     emit_ir: bool,
@@ -284,7 +360,11 @@ impl ModuleConfig {
             opt_level: None,
             opt_size: None,
 
+            pgo_gen: None,
+            pgo_use: None,
+
             emit_no_opt_bc: false,
+            emit_pre_thin_lto_bc: false,
             emit_bc: false,
             emit_lto_bc: false,
             emit_ir: false,
@@ -340,12 +420,16 @@ struct CodegenContext<'a> {
     worker: usize,
     // The incremental compilation session directory, or None if we are not
     // compiling incrementally
-    incr_comp_session_dir: Option<PathBuf>
+    incr_comp_session_dir: Option<PathBuf>,
+    // Where to collect function sizes, or None if `-Z print-fn-sizes` wasn't
+    // passed
+    fn_sizes: Option<SharedFnSizes>,
 }
 
 impl<'a> CodegenContext<'a> {
     fn new_with_session(sess: &'a Session,
-                        exported_symbols: &'a ExportedSymbols)
+                        exported_symbols: &'a ExportedSymbols,
+                        fn_sizes: Option<SharedFnSizes>)
                         -> CodegenContext<'a> {
         CodegenContext {
             lto_ctxt: Some((sess, exported_symbols)),
@@ -353,7 +437,8 @@ impl<'a> CodegenContext<'a> {
             plugin_passes: sess.plugin_llvm_passes.borrow().clone(),
             remark: sess.opts.cg.remark.clone(),
             worker: 0,
-            incr_comp_session_dir: sess.incr_comp_session_dir_opt().map(|r| r.clone())
+            incr_comp_session_dir: sess.incr_comp_session_dir_opt().map(|r| r.clone()),
+            fn_sizes: fn_sizes,
         }
     }
 }
@@ -421,6 +506,14 @@ unsafe extern "C" fn diagnostic_handler(info: DiagnosticInfoRef, user: *mut c_vo
             }
         }
 
+        llvm::diagnostic::StackSize(stack) => {
+            if let Some(ref fn_sizes) = cgcx.fn_sizes {
+                let name = CStr::from_ptr(llvm::LLVMGetValueName(stack.function));
+                fn_sizes.stack_sizes.lock().unwrap()
+                        .push((name.to_string_lossy().into_owned(), stack.stack_size));
+            }
+        }
+
         _ => (),
     }
 }
@@ -519,7 +612,7 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
         llvm::LLVMDisposePassManager(mpm);
 
         match cgcx.lto_ctxt {
-            Some((sess, exported_symbols)) if sess.lto() =>  {
+            Some((sess, exported_symbols)) if sess.opts.cg.lto == config::Lto::Fat =>  {
                 time(sess.time_passes(), "all lto passes", || {
                     let temp_no_opt_bc_filename =
                         output_names.temp_path_ext("no-opt.lto.bc", module_name);
@@ -540,6 +633,65 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
         }
     }
 
+    if config.emit_pre_thin_lto_bc {
+        let out = output_names.temp_path(OutputType::Bitcode, module_name);
+        let thin = lto::ThinBuffer::new(llmod);
+        if let Err(e) = File::create(&out).and_then(|mut f| f.write_all(thin.data())) {
+            cgcx.handler.err(&format!("failed to write bitcode to {}: {}",
+                                      out.display(), e));
+        }
+    } else {
+        codegen(cgcx, llmod, &config, &output_names, module_name);
+    }
+
+    llvm::LLVMRustDisposeTargetMachine(tm);
+}
+
+// Unsafe due to LLVM calls.
+unsafe fn optimize_and_codegen_thin(cgcx: &CodegenContext,
+                                    module: lto::ThinModule,
+                                    config: ModuleConfig,
+                                    output_names: OutputFilenames) {
+    let llcx = llvm::LLVMContextCreate();
+    let tm = config.tm;
+
+    // llcx doesn't outlive this function, so we can put this on the stack.
+    let fv = HandlerFreeVars {
+        llcx: llcx,
+        cgcx: cgcx,
+    };
+    let fv = &fv as *const HandlerFreeVars as *mut c_void;
+
+    llvm::LLVMRustSetInlineAsmDiagnosticHandler(llcx, inline_asm_handler, fv);
+    llvm::LLVMContextSetDiagnosticHandler(llcx, diagnostic_handler, fv);
+
+    let module_name = Some(module.name());
+
+    let llmod = time(config.time_passes, &format!("thin lto passes [{}]", cgcx.worker), || {
+        module.optimize(cgcx.handler, llcx, tm, &config)
+    });
+
+    if config.emit_lto_bc {
+        let out = output_names.temp_path_ext("lto.bc", module_name);
+        let out = path2cstr(&out);
+        llvm::LLVMWriteBitcodeToFile(llmod, out.as_ptr());
+    }
+
+    codegen(cgcx, llmod, &config, &output_names, module_name);
+
+    llvm::LLVMDisposeModule(llmod);
+    llvm::LLVMContextDispose(llcx);
+    llvm::LLVMRustDisposeTargetMachine(tm);
+}
+
+// Unsafe due to LLVM calls.
+unsafe fn codegen(cgcx: &CodegenContext,
+                  llmod: ModuleRef,
+                  config: &ModuleConfig,
+                  output_names: &OutputFilenames,
+                  module_name: Option<&str>) {
+    let tm = config.tm;
+
     // A codegen-specific pass manager is used to generate object
     // files for an LLVM module.
     //
@@ -615,6 +767,12 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
         }
     });
 
+    if write_obj {
+        if let Some(ref fn_sizes) = cgcx.fn_sizes {
+            fn_sizes.record_code_sizes(cgcx.handler, tm, &obj_out);
+        }
+    }
+
     if copy_bc_to_obj {
         debug!("copying bitcode {:?} to obj {:?}", bc_out, obj_out);
         if let Err(e) = link_or_copy(&bc_out, &obj_out) {
@@ -628,8 +786,6 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
             cgcx.handler.err(&format!("failed to remove bitcode: {}", e));
         }
     }
-
-    llvm::LLVMRustDisposeTargetMachine(tm);
 }
 
 
@@ -657,7 +813,7 @@ pub fn run_passes(sess: &Session,
     // case, but it would be confusing to have the validity of
     // `-Z lto -C codegen-units=2` depend on details of the crate being
     // compiled, so we complain regardless.
-    if sess.lto() && sess.opts.cg.codegen_units > 1 {
+    if sess.opts.cg.lto == config::Lto::Fat && sess.opts.cg.codegen_units > 1 {
         // This case is impossible to handle because LTO expects to be able
         // to combine the entire crate and all its dependencies into a
         // single compilation unit, but each codegen unit is in a separate
@@ -671,6 +827,18 @@ pub fn run_passes(sess: &Session,
             !sess.opts.output_types.should_trans() ||
             sess.opts.debugging_opts.no_trans);
 
+    // ThinLTO runs in three phases: all codegen units are optimized and
+    // written out as bitcode with a summary, then the summaries of those and
+    // of all upstream crates are analyzed together, and finally every module
+    // is optimized again with the functions it imports and translated.
+    let thin_lto = sess.thin_lto();
+    if thin_lto {
+        lto::check_lto_allowed(sess);
+        if unsafe { !llvm::LLVMRustThinLTOAvailable() } {
+            sess.fatal("ThinLTO is not supported by this version of LLVM");
+        }
+    }
+
     let tm = create_target_machine(sess);
 
     // Figure out what we actually need to build.
@@ -681,6 +849,22 @@ pub fn run_passes(sess: &Session,
     modules_config.opt_level = Some(get_llvm_opt_level(sess.opts.optimize));
     modules_config.opt_size = Some(get_llvm_opt_size(sess.opts.optimize));
 
+    // The profiling runtime expands `%m` to a signature of the instrumented
+    // binary, so that several instrumented programs can share one directory.
+    modules_config.pgo_gen = sess.opts.cg.profile_generate.as_ref().map(|dir| {
+        dir.join("default_%m.profraw").to_string_lossy().into_owned()
+    });
+    modules_config.pgo_use = sess.opts.cg.profile_use.as_ref().map(|path| {
+        path.to_string_lossy().into_owned()
+    });
+
+    // Coverage counters are emitted as `llvm.instrprof.increment` calls
+    // during translation, they still need to be lowered to actual counter
+    // updates and profile data.
+    if sess.opts.debugging_opts.instrument_coverage {
+        modules_config.passes.push("instrprof".to_owned());
+    }
+
     // Save all versions of the bytecode if we're saving our temporaries.
     if sess.opts.cg.save_temps {
         modules_config.emit_no_opt_bc = true;
@@ -727,6 +911,10 @@ pub fn run_passes(sess: &Session,
     modules_config.set_flags(sess, trans);
     metadata_config.set_flags(sess, trans);
 
+    // The modules are only translated after ThinLTO.
+    let thin_lto_config = modules_config.clone();
+    modules_config.emit_pre_thin_lto_bc = thin_lto;
+
 
     // Populate a buffer with a list of codegen threads.  Items are processed in
     // LIFO order, just because it's a tiny bit simpler that way.  (The order
@@ -735,7 +923,7 @@ pub fn run_passes(sess: &Session,
 
     {
         let work = build_work_item(sess,
-                                   trans.metadata_module.clone(),
+                                   WorkItemSource::Trans(trans.metadata_module.clone()),
                                    metadata_config.clone(),
                                    crate_output.clone());
         work_items.push(work);
@@ -743,7 +931,7 @@ pub fn run_passes(sess: &Session,
 
     for mtrans in trans.modules.iter() {
         let work = build_work_item(sess,
-                                   mtrans.clone(),
+                                   WorkItemSource::Trans(mtrans.clone()),
                                    modules_config.clone(),
                                    crate_output.clone());
         work_items.push(work);
@@ -757,18 +945,25 @@ pub fn run_passes(sess: &Session,
     // NOTE: This code is not really adapted to incremental compilation where
     //       the compiler decides the number of codegen units (and will
     //       potentially create hundreds of them).
+    let fn_sizes = sess.opts.debugging_opts.print_fn_sizes.as_ref().map(|_| SharedFnSizes::new());
+
     let num_workers = work_items.len() - 1;
     if num_workers <= 1 {
-        run_work_singlethreaded(sess, &trans.exported_symbols, work_items);
+        run_work_singlethreaded(sess, &trans.exported_symbols, work_items, &fn_sizes);
     } else {
-        run_work_multithreaded(sess, work_items, num_workers);
+        run_work_multithreaded(sess, work_items, num_workers, &fn_sizes);
     }
 
     // If in incr. comp. mode, preserve the `.o` files for potential re-use
     for mtrans in trans.modules.iter() {
         let mut files = vec![];
 
-        if modules_config.emit_obj {
+        if thin_lto {
+            // Only the input of ThinLTO can be reused, everything after it
+            // depends on the other modules as well.
+            let path = crate_output.temp_path(OutputType::Bitcode, Some(&mtrans.name));
+            files.push((OutputType::Bitcode, path));
+        } else if modules_config.emit_obj {
             let path = crate_output.temp_path(OutputType::Object, Some(&mtrans.name));
             files.push((OutputType::Object, path));
         }
@@ -781,6 +976,34 @@ pub fn run_passes(sess: &Session,
         save_trans_partition(sess, &mtrans.name, mtrans.symbol_name_hash, &files);
     }
 
+    if thin_lto {
+        let modules = trans.modules.iter().map(|mtrans| {
+            let path = crate_output.temp_path(OutputType::Bitcode, Some(&mtrans.name));
+            (mtrans.name.clone(), path)
+        }).collect();
+        let thin_modules = time(sess.time_passes(), "thin lto analysis", || {
+            lto::run_thin(sess, &trans.exported_symbols, modules)
+        });
+
+        let work_items = thin_modules.into_iter().map(|module| {
+            build_work_item(sess,
+                            WorkItemSource::ThinLto(module),
+                            thin_lto_config.clone(),
+                            crate_output.clone())
+        }).collect::<Vec<_>>();
+
+        let num_workers = cmp::min(work_items.len(), sess.opts.cg.codegen_units);
+        if num_workers <= 1 {
+            run_work_singlethreaded(sess, &trans.exported_symbols, work_items, &fn_sizes);
+        } else {
+            run_work_multithreaded(sess, work_items, num_workers, &fn_sizes);
+        }
+    }
+
+    if let Some(ref fn_sizes) = fn_sizes {
+        fn_sizes.record(sess);
+    }
+
     // All codegen is finished.
     unsafe {
         llvm::LLVMRustDisposeTargetMachine(tm);
@@ -905,7 +1128,7 @@ pub fn run_passes(sess: &Session,
                 remove(sess, &path);
             }
 
-            if modules_config.emit_bc && !keep_numbered_bitcode {
+            if (modules_config.emit_bc || thin_lto) && !keep_numbered_bitcode {
                 let path = crate_output.temp_path(OutputType::Bitcode, module_name);
                 remove(sess, &path);
             }
@@ -942,14 +1165,21 @@ fn dump_incremental_data(trans: &CrateTranslation) {
     println!("incremental: re-using {} out of {} modules", reuse, trans.modules.len());
 }
 
+enum WorkItemSource {
+    /// A codegen unit of the local crate.
+    Trans(ModuleTranslation),
+    /// A module taking part in ThinLTO, after the analysis.
+    ThinLto(lto::ThinModule),
+}
+
 struct WorkItem {
-    mtrans: ModuleTranslation,
+    source: WorkItemSource,
     config: ModuleConfig,
     output_names: OutputFilenames
 }
 
 fn build_work_item(sess: &Session,
-                   mtrans: ModuleTranslation,
+                   source: WorkItemSource,
                    config: ModuleConfig,
                    output_names: OutputFilenames)
                    -> WorkItem
@@ -957,7 +1187,7 @@ fn build_work_item(sess: &Session,
     let mut config = config;
     config.tm = create_target_machine(sess);
     WorkItem {
-        mtrans: mtrans,
+        source: source,
         config: config,
         output_names: output_names
     }
@@ -965,12 +1195,26 @@ fn build_work_item(sess: &Session,
 
 fn execute_work_item(cgcx: &CodegenContext,
                      work_item: WorkItem) {
+    let mtrans = match work_item.source {
+        WorkItemSource::Trans(mtrans) => mtrans,
+        WorkItemSource::ThinLto(module) => {
+            debug!("llvm-optimizing thin lto module {:?}", module.name());
+            unsafe {
+                optimize_and_codegen_thin(cgcx,
+                                          module,
+                                          work_item.config,
+                                          work_item.output_names);
+            }
+            return
+        }
+    };
+
     unsafe {
-        match work_item.mtrans.source {
+        match mtrans.source {
             ModuleSource::Translated(mllvm) => {
-                debug!("llvm-optimizing {:?}", work_item.mtrans.name);
+                debug!("llvm-optimizing {:?}", mtrans.name);
                 optimize_and_codegen(cgcx,
-                                     work_item.mtrans,
+                                     mtrans,
                                      mllvm,
                                      work_item.config,
                                      work_item.output_names);
@@ -979,13 +1223,13 @@ fn execute_work_item(cgcx: &CodegenContext,
                 let incr_comp_session_dir = cgcx.incr_comp_session_dir
                                                 .as_ref()
                                                 .unwrap();
-                let name = &work_item.mtrans.name;
+                let name = &mtrans.name;
                 for (kind, saved_file) in wp.saved_files {
                     let obj_out = work_item.output_names.temp_path(kind, Some(name));
                     let source_file = in_incr_comp_dir(&incr_comp_session_dir,
                                                        &saved_file);
                     debug!("copying pre-existing module `{}` from {:?} to {}",
-                           work_item.mtrans.name,
+                           mtrans.name,
                            source_file,
                            obj_out.display());
                     match link_or_copy(&source_file, &obj_out) {
@@ -1005,8 +1249,9 @@ fn execute_work_item(cgcx: &CodegenContext,
 
 fn run_work_singlethreaded(sess: &Session,
                            exported_symbols: &ExportedSymbols,
-                           work_items: Vec<WorkItem>) {
-    let cgcx = CodegenContext::new_with_session(sess, exported_symbols);
+                           work_items: Vec<WorkItem>,
+                           fn_sizes: &Option<SharedFnSizes>) {
+    let cgcx = CodegenContext::new_with_session(sess, exported_symbols, fn_sizes.clone());
 
     // Since we're running single-threaded, we can pass the session to
     // the proc, allowing `optimize_and_codegen` to perform LTO.
@@ -1017,7 +1262,8 @@ fn run_work_singlethreaded(sess: &Session,
 
 fn run_work_multithreaded(sess: &Session,
                           work_items: Vec<WorkItem>,
-                          num_workers: usize) {
+                          num_workers: usize,
+                          fn_sizes: &Option<SharedFnSizes>) {
     assert!(num_workers > 0);
 
     // Run some workers to process the work items.
@@ -1030,6 +1276,7 @@ fn run_work_multithreaded(sess: &Session,
         let diag_emitter = diag_emitter.clone();
         let plugin_passes = sess.plugin_llvm_passes.borrow().clone();
         let remark = sess.opts.cg.remark.clone();
+        let fn_sizes = fn_sizes.clone();
 
         let (tx, rx) = channel();
         let mut tx = Some(tx);
@@ -1051,7 +1298,8 @@ fn run_work_multithreaded(sess: &Session,
                 plugin_passes: plugin_passes,
                 remark: remark,
                 worker: i,
-                incr_comp_session_dir: incr_comp_session_dir
+                incr_comp_session_dir: incr_comp_session_dir,
+                fn_sizes: fn_sizes,
             };
 
             loop {
@@ -1133,10 +1381,17 @@ pub unsafe fn with_llvm_pmb(llmod: ModuleRef,
     let opt_size = config.opt_size.unwrap_or(llvm::CodeGenOptSizeNone);
     let inline_threshold = config.inline_threshold;
 
+    let pgo_gen_path = config.pgo_gen.as_ref().map(|s| CString::new(s.as_bytes()).unwrap());
+    let pgo_use_path = config.pgo_use.as_ref().map(|s| CString::new(s.as_bytes()).unwrap());
+
     llvm::LLVMRustConfigurePassManagerBuilder(builder, opt_level,
                                               config.merge_functions,
                                               config.vectorize_slp,
-                                              config.vectorize_loop);
+                                              config.vectorize_loop,
+                                              pgo_gen_path.as_ref().map_or(ptr::null(),
+                                                                           |s| s.as_ptr()),
+                                              pgo_use_path.as_ref().map_or(ptr::null(),
+                                                                           |s| s.as_ptr()));
     llvm::LLVMPassManagerBuilderSetSizeLevel(builder, opt_size as u32);
 
     if opt_size != llvm::CodeGenOptSizeNone {
@@ -24,6 +24,7 @@
 //!     int) and rec(x=int, y=int, z=int) will have the same TypeRef.
 
 use super::CrateTranslation;
+use super::EncodedMetadata;
 use super::ModuleLlvm;
 use super::ModuleSource;
 use super::ModuleTranslation;
@@ -44,7 +45,7 @@ use rustc::ty::adjustment::CustomCoerceUnsized;
 use rustc::dep_graph::{DepNode, WorkProduct};
 use rustc::hir::map as hir_map;
 use rustc::util::common::time;
-use session::config::{self, NoDebugInfo};
+use session::config::{self, NoDebugInfo, OutputFilenames, OutputType};
 use rustc_incremental::IncrementalHashesMap;
 use session::{self, DataTypeKind, Session};
 use abi::{self, Abi, FnType};
@@ -62,6 +63,7 @@ use common::{type_is_zero_size, val_ty};
 use common;
 use consts;
 use context::{SharedCrateContext, CrateContextList};
+use coverageinfo;
 use debuginfo;
 use declare;
 use machine;
@@ -1102,9 +1104,33 @@ pub fn find_exported_symbols(tcx: TyCtxt, reachable: NodeSet) -> NodeSet {
     }).collect()
 }
 
+/// Encodes the crate's metadata for a build in which nothing is translated.
+/// Unlike `trans_crate`, this doesn't create any LLVM contexts, and the
+/// metadata is never compressed since it only ever ends up in an `.rmeta`.
+pub fn trans_metadata_only<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     analysis: ty::CrateAnalysis,
+                                     incremental_hashes_map: &IncrementalHashesMap)
+                                     -> EncodedMetadata {
+    let _task = tcx.dep_graph.in_task(DepNode::TransCrate);
+
+    let ty::CrateAnalysis { export_map, reachable, name, .. } = analysis;
+    let exported_symbols = find_exported_symbols(tcx, reachable);
+    let link_meta = link::build_link_meta(incremental_hashes_map, &name);
+
+    let metadata = time(tcx.sess.time_passes(), "write metadata", || {
+        tcx.sess.cstore.encode_metadata(tcx, &export_map, &link_meta, &exported_symbols)
+    });
+
+    EncodedMetadata {
+        link: link_meta,
+        metadata: metadata,
+    }
+}
+
 pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                              analysis: ty::CrateAnalysis,
-                             incremental_hashes_map: &IncrementalHashesMap)
+                             incremental_hashes_map: &IncrementalHashesMap,
+                             outputs: &OutputFilenames)
                              -> CrateTranslation {
     let _task = tcx.dep_graph.in_task(DepNode::TransCrate);
 
@@ -1135,6 +1161,12 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         write_metadata(&shared_ccx, shared_ccx.exported_symbols())
     });
 
+    // Dependent crates only need the metadata to be type checked, so write it
+    // out right away rather than once LLVM is done with this crate.
+    if tcx.sess.opts.output_types.contains_key(&OutputType::Metadata) {
+        link::write_rmeta(tcx.sess, &metadata, outputs, &name);
+    }
+
     let metadata_module = ModuleTranslation {
         name: "metadata".to_string(),
         symbol_name_hash: 0, // we always rebuild metadata, at least for now
@@ -1146,8 +1178,7 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     let no_builtins = attr::contains_name(&krate.attrs, "no_builtins");
 
     // Skip crate items and just output metadata in -Z no-trans mode.
-    if tcx.sess.opts.debugging_opts.no_trans ||
-       !tcx.sess.opts.output_types.should_trans() {
+    if tcx.sess.opts.debugging_opts.no_trans {
         let empty_exported_symbols = ExportedSymbols::empty();
         let linker_info = LinkerInfo::new(&shared_ccx, &empty_exported_symbols);
         return CrateTranslation {
@@ -1236,6 +1267,9 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             if ccx.sess().opts.debuginfo != NoDebugInfo {
                 debuginfo::finalize(&ccx);
             }
+
+            // Write out the coverage mapping of the instrumented functions
+            coverageinfo::finalize(&ccx);
         });
     }
 
@@ -1521,12 +1555,17 @@ fn trans_reuse_previous_work_products(scx: &SharedCrateContext,
 
             debug!("trans_reuse_previous_work_products: id={:?} hash={}", id, hash);
 
+            let explain = scx.sess().opts.debugging_opts.incremental_explain;
+
             if let Some(work_product) = scx.dep_graph().previous_work_product(&id) {
                 if work_product.input_hash == hash {
+                    if explain {
+                        println!("incremental: CGU `{}` re-used", cgu.name());
+                    }
                     debug!("trans_reuse_previous_work_products: reusing {:?}", work_product);
                     return Some(work_product);
                 } else {
-                    if scx.sess().opts.debugging_opts.incremental_info {
+                    if scx.sess().opts.debugging_opts.incremental_info || explain {
                         println!("incremental: CGU `{}` invalidated because of \
                                   changed partitioning hash.",
                                   cgu.name());
@@ -1535,6 +1574,10 @@ fn trans_reuse_previous_work_products(scx: &SharedCrateContext,
                             not reusing {:?} because hash changed to {:?}",
                            work_product, hash);
                 }
+            } else if explain {
+                println!("incremental: CGU `{}` rebuilt, it is new or one of its \
+                          inputs changed",
+                         cgu.name());
             }
 
             None
@@ -16,6 +16,7 @@ use rustc::hir;
 use rustc::hir::def::ExportMap;
 use rustc::hir::def_id::DefId;
 use rustc::traits;
+use coverageinfo;
 use debuginfo;
 use callee::Callee;
 use base;
@@ -148,6 +149,8 @@ pub struct LocalCrateContext<'tcx> {
 
     dbg_cx: Option<debuginfo::CrateDebugContext<'tcx>>,
 
+    coverage_cx: Option<coverageinfo::CrateCoverageContext>,
+
     eh_personality: Cell<Option<ValueRef>>,
     eh_unwind_resume: Cell<Option<ValueRef>>,
     rust_try_fn: Cell<Option<ValueRef>>,
@@ -586,6 +589,12 @@ impl<'tcx> LocalCrateContext<'tcx> {
                 None
             };
 
+            let coverage_cx = if shared.tcx.sess.opts.debugging_opts.instrument_coverage {
+                Some(coverageinfo::CrateCoverageContext::new())
+            } else {
+                None
+            };
+
             let local_ccx = LocalCrateContext {
                 llmod: llmod,
                 llcx: llcx,
@@ -613,6 +622,7 @@ impl<'tcx> LocalCrateContext<'tcx> {
                 str_slice_type: Type::from_ref(ptr::null_mut()),
                 closure_vals: RefCell::new(FxHashMap()),
                 dbg_cx: dbg_cx,
+                coverage_cx: coverage_cx,
                 eh_personality: Cell::new(None),
                 eh_unwind_resume: Cell::new(None),
                 rust_try_fn: Cell::new(None),
@@ -821,6 +831,10 @@ impl<'b, 'tcx> CrateContext<'b, 'tcx> {
         &self.local().dbg_cx
     }
 
+    pub fn coverage_cx<'a>(&'a self) -> &'a Option<coverageinfo::CrateCoverageContext> {
+        &self.local().coverage_cx
+    }
+
     pub fn rust_try_fn<'a>(&'a self) -> &'a Cell<Option<ValueRef>> {
         &self.local().rust_try_fn
     }
@@ -1155,6 +1169,10 @@ fn declare_intrinsic(ccx: &CrateContext, key: &str) -> Option<ValueRef> {
 
     ifn!("llvm.assume", fn(i1) -> void);
 
+    if ccx.sess().opts.debugging_opts.instrument_coverage {
+        ifn!("llvm.instrprof.increment", fn(i8p, t_i64, t_i32, t_i32) -> void);
+    }
+
     if ccx.sess().opts.debuginfo != NoDebugInfo {
         ifn!("llvm.dbg.declare", fn(Type::metadata(ccx), Type::metadata(ccx)) -> void);
         ifn!("llvm.dbg.value", fn(Type::metadata(ccx), t_i64, Type::metadata(ccx)) -> void);
@@ -0,0 +1,316 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Source-based code coverage (`-Z instrument-coverage`).
+//!
+//! Every MIR basic block outside of cleanup code gets its own profile
+//! counter, which is bumped through `llvm.instrprof.increment` on entry to
+//! the block. The source region spanned by the statements and terminator of
+//! the block is recorded against that counter. Once a codegen unit has been
+//! translated, `finalize` encodes the regions of all its functions in the
+//! LLVM coverage mapping format and stores them in the `__llvm_covmap`
+//! section, where `llvm-cov` expects to find them.
+//!
+//! The counters themselves are lowered by LLVM's `instrprof` pass, and the
+//! raw profile is written out at exit by the runtime in `profiler_builtins`.
+
+use llvm::{self, ValueRef};
+use rustc::mir::{self, Mir};
+use rustc_data_structures::indexed_vec::IndexVec;
+
+use builder::Builder;
+use common::{CrateContext, C_array, C_bytes, C_struct, C_u32, C_u64, val_ty};
+use declare;
+use mir::CleanupKind;
+use type_::Type;
+
+use syntax_pos::{Span, BytePos, NO_EXPANSION};
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::fmt::Write;
+
+/// Per codegen unit state: the coverage records of all instrumented
+/// functions, written out by `finalize`.
+pub struct CrateCoverageContext {
+    functions: RefCell<Vec<FunctionCoverage>>,
+}
+
+impl CrateCoverageContext {
+    pub fn new() -> CrateCoverageContext {
+        CrateCoverageContext {
+            functions: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+/// The coverage record of a single function.
+struct FunctionCoverage {
+    /// The symbol name of the function, used as its PGO name.
+    name: String,
+    /// Structural hash of the function's control flow graph.
+    hash: u64,
+    /// The file all regions of this function are in.
+    filename: String,
+    regions: Vec<Region>,
+}
+
+#[derive(Copy, Clone)]
+struct Region {
+    counter: u32,
+    line_start: u32,
+    column_start: u32,
+    line_end: u32,
+    column_end: u32,
+}
+
+/// Per function state, kept by the `MirContext` during translation.
+pub struct FunctionCoverageContext {
+    name_var: ValueRef,
+    hash: u64,
+    num_counters: u32,
+    counters: IndexVec<mir::BasicBlock, Option<u32>>,
+}
+
+/// Assigns a counter to every non-cleanup block of `mir` and records the
+/// source regions they cover. Returns `None` if coverage is disabled or the
+/// function has no source of its own to map to (e.g. shims, or generic code
+/// inlined from another crate).
+pub fn create_function_coverage_context<'a, 'tcx>(ccx: &CrateContext<'a, 'tcx>,
+                                                  llfn: ValueRef,
+                                                  mir: &Mir<'tcx>,
+                                                  cleanup_kinds: &IndexVec<mir::BasicBlock,
+                                                                           CleanupKind>)
+                                                  -> Option<FunctionCoverageContext> {
+    let coverage_cx = match *ccx.coverage_cx() {
+        Some(ref coverage_cx) => coverage_cx,
+        None => return None,
+    };
+
+    let fn_span = mir.span;
+    if fn_span.expn_id != NO_EXPANSION || fn_span.lo == fn_span.hi {
+        return None;
+    }
+    let codemap = ccx.sess().codemap();
+    let fn_file = codemap.lookup_char_pos(fn_span.lo).file;
+    if fn_file.is_imported() {
+        return None;
+    }
+
+    let name = unsafe {
+        CStr::from_ptr(llvm::LLVMGetValueName(llfn)).to_string_lossy().into_owned()
+    };
+
+    let mut num_counters = 0;
+    let mut regions = Vec::new();
+    let mut cfg = String::new();
+    let counters = mir.basic_blocks().iter_enumerated().map(|(bb, data)| {
+        write!(cfg, "{:?}->{:?};", bb, data.terminator().successors()).unwrap();
+        if cleanup_kinds[bb] != CleanupKind::NotCleanup {
+            return None;
+        }
+
+        let counter = num_counters;
+        num_counters += 1;
+
+        // Only consider spans written directly in the body of this
+        // function; code expanded from macros can come from anywhere.
+        let spans = data.statements.iter()
+            .map(|statement| statement.source_info.span)
+            .chain(Some(data.terminator().source_info.span))
+            .filter(|span| {
+                span.expn_id == NO_EXPANSION && span.lo < span.hi &&
+                fn_span.lo <= span.lo && span.hi <= fn_span.hi
+            });
+        let block_span = spans.fold(None, |acc: Option<Span>, span| {
+            Some(match acc {
+                Some(acc) => Span {
+                    lo: BytePos(acc.lo.0.min(span.lo.0)),
+                    hi: BytePos(acc.hi.0.max(span.hi.0)),
+                    expn_id: NO_EXPANSION,
+                },
+                None => span,
+            })
+        });
+
+        if let Some(span) = block_span {
+            let lo = codemap.lookup_char_pos(span.lo);
+            let hi = codemap.lookup_char_pos(span.hi);
+            // LLVM columns are 1-based and the end column points one past
+            // the last character of the region.
+            regions.push(Region {
+                counter: counter,
+                line_start: lo.line as u32,
+                column_start: lo.col.0 as u32 + 1,
+                line_end: hi.line as u32,
+                column_end: hi.col.0 as u32 + 1,
+            });
+        }
+
+        Some(counter)
+    }).collect();
+
+    regions.sort_by_key(|r| (r.line_start, r.column_start, r.line_end, r.column_end));
+
+    let hash = unsafe {
+        let cfg = CString::new(cfg).unwrap();
+        llvm::LLVMRustCoverageComputeHash(cfg.as_ptr())
+    };
+    let name_var = unsafe {
+        let name = CString::new(name.clone()).unwrap();
+        llvm::LLVMRustCoverageCreatePGOFuncNameVar(llfn, name.as_ptr())
+    };
+
+    coverage_cx.functions.borrow_mut().push(FunctionCoverage {
+        name: name,
+        hash: hash,
+        filename: fn_file.name.clone(),
+        regions: regions,
+    });
+
+    Some(FunctionCoverageContext {
+        name_var: name_var,
+        hash: hash,
+        num_counters: num_counters,
+        counters: counters,
+    })
+}
+
+/// Emits the increment of the counter of `bb`, if it has one.
+pub fn increment_counter(bcx: &Builder,
+                         coverage: Option<&FunctionCoverageContext>,
+                         bb: mir::BasicBlock) {
+    let coverage = match coverage {
+        Some(coverage) => coverage,
+        None => return,
+    };
+    let counter = match coverage.counters[bb] {
+        Some(counter) => counter,
+        None => return,
+    };
+
+    let ccx = bcx.ccx;
+    let llfn = ccx.get_intrinsic("llvm.instrprof.increment");
+    let name = bcx.pointercast(coverage.name_var, Type::i8p(ccx));
+    bcx.call(llfn, &[name,
+                     C_u64(ccx, coverage.hash),
+                     C_u32(ccx, coverage.num_counters),
+                     C_u32(ccx, counter)], None);
+}
+
+/// Writes the coverage records of the current codegen unit into the
+/// `__llvm_covmap` section.
+pub fn finalize(ccx: &CrateContext) {
+    let functions = match *ccx.coverage_cx() {
+        Some(ref coverage_cx) => coverage_cx.functions.borrow(),
+        None => return,
+    };
+    if functions.is_empty() {
+        return;
+    }
+
+    debug!("coverageinfo::finalize: {} functions", functions.len());
+
+    let mut filenames: Vec<&str> = Vec::new();
+    let mut mappings = Vec::new();
+    let mut records = Vec::new();
+    for function in functions.iter() {
+        let file_id = match filenames.iter().position(|f| *f == function.filename) {
+            Some(file_id) => file_id,
+            None => {
+                filenames.push(&function.filename);
+                filenames.len() - 1
+            }
+        } as u32;
+
+        let regions = function.regions.iter().map(|r| {
+            llvm::CounterMappingRegion {
+                counter_id: r.counter,
+                file_id: 0,
+                line_start: r.line_start,
+                column_start: r.column_start,
+                line_end: r.line_end,
+                column_end: r.column_end,
+            }
+        }).collect::<Vec<_>>();
+        let mapping = llvm::build_byte_buffer(|s| unsafe {
+            llvm::LLVMRustCoverageWriteMappingToBuffer(&file_id,
+                                                       1,
+                                                       regions.as_ptr(),
+                                                       regions.len() as u32,
+                                                       s);
+        });
+
+        let name_ref = unsafe {
+            let name = CString::new(&function.name[..]).unwrap();
+            llvm::LLVMRustCoverageComputeHash(name.as_ptr())
+        };
+        records.push(C_struct(ccx, &[C_u64(ccx, name_ref),
+                                     C_u32(ccx, mapping.len() as u32),
+                                     C_u64(ccx, function.hash)], true));
+        mappings.push(mapping);
+    }
+
+    let c_filenames = filenames.iter().map(|f| CString::new(*f).unwrap()).collect::<Vec<_>>();
+    let c_filename_ptrs = c_filenames.iter().map(|f| f.as_ptr()).collect::<Vec<_>>();
+    let mut data = llvm::build_byte_buffer(|s| unsafe {
+        llvm::LLVMRustCoverageWriteFilenamesSectionToBuffer(c_filename_ptrs.as_ptr(),
+                                                            c_filename_ptrs.len(),
+                                                            s);
+    });
+    let filenames_size = data.len();
+    for mapping in &mappings {
+        data.extend_from_slice(mapping);
+    }
+    // The records of consecutive modules are concatenated by the linker, so
+    // each of them has to keep the section 8-byte aligned. The padding counts
+    // as part of the coverage mappings.
+    while data.len() % 8 != 0 {
+        data.push(0);
+    }
+    let coverage_size = data.len() - filenames_size;
+
+    let header = C_struct(ccx, &[C_u32(ccx, records.len() as u32),
+                                 C_u32(ccx, filenames_size as u32),
+                                 C_u32(ccx, coverage_size as u32),
+                                 C_u32(ccx, unsafe { llvm::LLVMRustCoverageMappingVersion() })],
+                          false);
+    let record_ty = val_ty(records[0]);
+    let covmap = C_struct(ccx, &[header, C_array(record_ty, &records), C_bytes(ccx, &data)],
+                          false);
+
+    let section_name = if ccx.sess().target.target.options.is_like_osx {
+        "__LLVM_COV,__llvm_covmap\0"
+    } else {
+        "__llvm_covmap\0"
+    };
+
+    unsafe {
+        let covmap_var = declare::define_global(ccx, "__llvm_coverage_mapping",
+                                                val_ty(covmap)).unwrap_or_else(|| {
+            bug!("symbol `__llvm_coverage_mapping` is already defined")
+        });
+        llvm::LLVMSetInitializer(covmap_var, covmap);
+        llvm::LLVMSetGlobalConstant(covmap_var, llvm::True);
+        llvm::LLVMRustSetLinkage(covmap_var, llvm::Linkage::InternalLinkage);
+        llvm::LLVMSetSection(covmap_var, section_name.as_ptr() as *const _);
+        llvm::LLVMSetAlignment(covmap_var, 8);
+
+        // Nothing refers to the mapping, keep it alive through `llvm.used`.
+        let used = C_array(Type::i8p(ccx), &[llvm::LLVMConstPointerCast(covmap_var,
+                                                                         Type::i8p(ccx).to_ref())]);
+        let used_var = declare::define_global(ccx, "llvm.used", val_ty(used)).unwrap_or_else(|| {
+            bug!("symbol `llvm.used` is already defined")
+        });
+        llvm::LLVMSetInitializer(used_var, used);
+        llvm::LLVMRustSetLinkage(used_var, llvm::Linkage::AppendingLinkage);
+        llvm::LLVMSetSection(used_var, "llvm.metadata\0".as_ptr() as *const _);
+    }
+}
@@ -648,10 +648,11 @@ pub fn type_metadata<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
 
 pub fn file_metadata(cx: &CrateContext, path: &str, full_path: &Option<String>) -> DIFile {
     // FIXME (#9639): This needs to handle non-utf8 paths
-    let work_dir = cx.sess().working_dir.to_str().unwrap();
+    let work_dir = &cx.sess().working_dir.0[..];
     let file_name =
         full_path.as_ref().map(|p| p.as_str()).unwrap_or_else(|| {
-            if path.starts_with(work_dir) {
+            // The working directory may have been remapped to nothing.
+            if !work_dir.is_empty() && path.starts_with(work_dir) {
                 &path[work_dir.len() + 1..path.len()]
             } else {
                 path
@@ -748,24 +749,21 @@ pub fn compile_unit_metadata(scc: &SharedCrateContext,
                              debug_context: &CrateDebugContext,
                              sess: &Session)
                              -> DIDescriptor {
-    let work_dir = &sess.working_dir;
+    let work_dir = Path::new(&sess.working_dir.0);
     let compile_unit_name = match sess.local_crate_source_file {
         None => fallback_path(scc),
-        Some(ref abs_path) => {
-            if abs_path.is_relative() {
-                sess.warn("debuginfo: Invalid path to crate's local root source file!");
-                fallback_path(scc)
-            } else {
-                match abs_path.strip_prefix(work_dir) {
-                    Ok(ref p) if p.is_relative() => {
-                        if p.starts_with(Path::new("./")) {
-                            path2cstr(p)
-                        } else {
-                            path2cstr(&Path::new(".").join(p))
-                        }
+        Some(ref path) => {
+            // Both paths went through the same remapping, so the source file
+            // can still be made relative to the working directory.
+            match path.strip_prefix(work_dir) {
+                Ok(ref p) if p.is_relative() => {
+                    if p.starts_with(Path::new("./")) {
+                        path2cstr(p)
+                    } else {
+                        path2cstr(&Path::new(".").join(p))
                     }
-                    _ => fallback_path(scc)
                 }
+                _ => fallback_path(scc)
             }
         }
     };
@@ -65,7 +65,7 @@ pub use rustc::middle;
 pub use rustc::lint;
 pub use rustc::util;
 
-pub use base::trans_crate;
+pub use base::{trans_crate, trans_metadata_only};
 pub use disr::Disr;
 
 pub mod back {
@@ -116,6 +116,7 @@ mod collector;
 mod common;
 mod consts;
 mod context;
+mod coverageinfo;
 mod debuginfo;
 mod declare;
 mod disr;
@@ -175,4 +176,11 @@ pub struct CrateTranslation {
     pub linker_info: back::linker::LinkerInfo
 }
 
+/// The output of a metadata-only build, which translates nothing and only
+/// encodes the crate's metadata.
+pub struct EncodedMetadata {
+    pub link: middle::cstore::LinkMeta,
+    pub metadata: Vec<u8>,
+}
+
 __build_diagnostic_array! { librustc_trans, DIAGNOSTICS }
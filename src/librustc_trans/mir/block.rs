@@ -21,6 +21,7 @@ use builder::Builder;
 use common::{self, Funclet};
 use common::{C_bool, C_str_slice, C_struct, C_u32, C_undef};
 use consts;
+use coverageinfo;
 use Disr;
 use machine::{llalign_of_min, llbitsize_of_real};
 use meth;
@@ -105,6 +106,8 @@ impl<'a, 'tcx> MirContext<'a, 'tcx> {
             }
         };
 
+        coverageinfo::increment_counter(&bcx, self.coverage.as_ref(), bb);
+
         for statement in &data.statements {
             bcx = self.trans_statement(bcx, statement);
         }
@@ -21,6 +21,7 @@ use session::config::FullDebugInfo;
 use base;
 use builder::Builder;
 use common::{self, CrateContext, C_null, Funclet};
+use coverageinfo::{self, FunctionCoverageContext};
 use debuginfo::{self, declare_local, VariableAccess, VariableKind, FunctionDebugContext};
 use monomorphize::{self, Instance};
 use abi::FnType;
@@ -37,7 +38,7 @@ use rustc_data_structures::indexed_vec::{IndexVec, Idx};
 
 pub use self::constant::trans_static_initializer;
 
-use self::analyze::CleanupKind;
+pub use self::analyze::CleanupKind;
 use self::lvalue::LvalueRef;
 use rustc::mir::traversal;
 
@@ -49,6 +50,10 @@ pub struct MirContext<'a, 'tcx:'a> {
 
     debug_context: debuginfo::FunctionDebugContext,
 
+    /// The profile counters of this function, if it is instrumented for
+    /// code coverage.
+    coverage: Option<FunctionCoverageContext>,
+
     llfn: ValueRef,
 
     ccx: &'a CrateContext<'a, 'tcx>,
@@ -227,6 +232,9 @@ pub fn trans_mir<'a, 'tcx: 'a>(
     // Compute debuginfo scopes from MIR scopes.
     let scopes = debuginfo::create_mir_scopes(ccx, mir, &debug_context);
 
+    let coverage = coverageinfo::create_function_coverage_context(ccx, llfn, mir,
+                                                                   &cleanup_kinds);
+
     let mut mircx = MirContext {
         mir: mir,
         llfn: llfn,
@@ -240,6 +248,7 @@ pub fn trans_mir<'a, 'tcx: 'a>(
         scopes: scopes,
         locals: IndexVec::new(),
         debug_context: debug_context,
+        coverage: coverage,
         param_substs: {
             assert!(!instance.substs.needs_infer());
             instance.substs
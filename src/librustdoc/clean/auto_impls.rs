@@ -0,0 +1,286 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Impls which apply to a type without having been written for it.
+//!
+//! These are the auto trait impls, `Send` and `Sync`, which the compiler
+//! works out from a type's fields, and blanket impls such as
+//! `impl<T: Display> ToString for T`. Both are found here, while the type
+//! checker is still around, and added to the crate next to the types they
+//! apply to so that they end up on those types' pages.
+
+use rustc::hir;
+use rustc::hir::def_id::DefId;
+use rustc::infer::InferOk;
+use rustc::traits::{self, ObligationCause, Reveal, SelectionContext};
+use rustc::ty::{self, ToPredicate, Ty, TyCtxt};
+use rustc::ty::subst::Subst;
+use rustc::util::nodemap::{FxHashSet, DefIdSet};
+use syntax_pos::DUMMY_SP;
+
+use clean::{self, Clean, Crate, Item};
+use clean::inline;
+use core::DocContext;
+use fold::DocFolder;
+
+pub fn add_auto_impls(cx: &DocContext, krate: Crate) -> Crate {
+    let mut collector = ImplCollector {
+        cx: cx,
+        blanket_impls: vec![],
+        explicit_impls: FxHashSet(),
+    };
+    if let Some(ref module) = krate.module {
+        collector.collect(module);
+    }
+
+    let tcx = cx.tcx;
+    for did in tcx.sess.cstore.implementations_of_trait(None) {
+        if tcx.sess.cstore.is_default_impl(did) || !is_blanket_impl(tcx, did) {
+            continue
+        }
+        let mut items = vec![];
+        inline::build_impl_uncached(cx, did, &mut items);
+        collector.blanket_impls.extend(items.into_iter().filter(|i| {
+            if let clean::ImplItem(_) = i.inner { true } else { false }
+        }));
+    }
+
+    ImplSynthesizer {
+        cx: cx,
+        blanket_impls: collector.blanket_impls,
+        explicit_impls: collector.explicit_impls,
+        seen: DefIdSet(),
+    }.fold_crate(krate)
+}
+
+/// Whether `did` is a trait impl for a bare type parameter.
+fn is_blanket_impl(tcx: TyCtxt, did: DefId) -> bool {
+    if tcx.impl_trait_ref(did).is_none() {
+        return false
+    }
+    match tcx.item_type(did).sty {
+        ty::TyParam(..) => true,
+        _ => false,
+    }
+}
+
+struct ImplCollector<'a, 'b: 'a, 'tcx: 'b> {
+    cx: &'a DocContext<'b, 'tcx>,
+    blanket_impls: Vec<Item>,
+    /// The (type, trait) pairs which have been implemented by hand.
+    explicit_impls: FxHashSet<(DefId, DefId)>,
+}
+
+impl<'a, 'b, 'tcx> ImplCollector<'a, 'b, 'tcx> {
+    fn collect(&mut self, item: &Item) {
+        match item.inner {
+            clean::ModuleItem(ref m) => {
+                for item in &m.items {
+                    self.collect(item);
+                }
+            }
+            clean::ImplItem(ref i) => {
+                if let (Some(for_did), Some(trait_did)) = (i.for_.def_id(), i.trait_.def_id()) {
+                    self.explicit_impls.insert((for_did, trait_did));
+                }
+                // Blanket impls from other crates are looked up separately.
+                if item.def_id.is_local() && is_blanket_impl(self.cx.tcx, item.def_id) {
+                    self.blanket_impls.push(item.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+struct ImplSynthesizer<'a, 'b: 'a, 'tcx: 'b> {
+    cx: &'a DocContext<'b, 'tcx>,
+    blanket_impls: Vec<Item>,
+    explicit_impls: FxHashSet<(DefId, DefId)>,
+    /// Types re-exported in several places only need their impls once.
+    seen: DefIdSet,
+}
+
+impl<'a, 'b, 'tcx> DocFolder for ImplSynthesizer<'a, 'b, 'tcx> {
+    fn fold_item(&mut self, item: Item) -> Option<Item> {
+        self.fold_item_recur(item).map(|mut item| {
+            if let clean::ModuleItem(ref mut m) = item.inner {
+                let mut impls = vec![];
+                for item in &m.items {
+                    impls.extend(self.impls_for(item));
+                }
+                m.items.extend(impls);
+            }
+            item
+        })
+    }
+}
+
+impl<'a, 'b, 'tcx> ImplSynthesizer<'a, 'b, 'tcx> {
+    fn impls_for(&mut self, item: &Item) -> Vec<Item> {
+        let generics = match item.inner {
+            clean::StructItem(ref s) => &s.generics,
+            clean::EnumItem(ref e) => &e.generics,
+            clean::UnionItem(ref u) => &u.generics,
+            _ => return vec![],
+        };
+        let tcx = self.cx.tcx;
+        let node_id = match tcx.hir.as_local_node_id(item.def_id) {
+            Some(node_id) => node_id,
+            None => return vec![],
+        };
+        if !self.seen.insert(item.def_id) {
+            return vec![]
+        }
+
+        let param_env = ty::ParameterEnvironment::for_item(tcx, node_id);
+        let ty = tcx.item_type(item.def_id).subst(tcx, param_env.free_substs);
+        let for_ = tcx.item_type(item.def_id).clean(self.cx);
+        let mut impls = vec![];
+
+        for trait_did in vec![tcx.lang_items.send_trait(), tcx.lang_items.sync_trait()] {
+            let trait_did = match trait_did {
+                Some(did) => did,
+                None => continue,
+            };
+            if !self.explicit_impls.contains(&(item.def_id, trait_did)) {
+                impls.push(self.auto_trait_impl(item.def_id, generics, &param_env, ty, &for_,
+                                                trait_did));
+            }
+        }
+
+        for blanket in &self.blanket_impls {
+            if !blanket_impl_applies(tcx, &param_env, ty, blanket.def_id) {
+                continue
+            }
+            let mut blanket = blanket.clone();
+            if let clean::ImplItem(ref mut i) = blanket.inner {
+                i.blanket_impl = Some(for_.clone());
+            }
+            impls.push(blanket);
+        }
+        impls
+    }
+
+    /// Works out whether, and under which bounds on its type parameters, `ty`
+    /// implements the auto trait `trait_did`, and builds an impl saying so.
+    fn auto_trait_impl(&self,
+                       item_did: DefId,
+                       generics: &clean::Generics,
+                       param_env: &ty::ParameterEnvironment<'tcx>,
+                       ty: Ty<'tcx>,
+                       for_: &clean::Type,
+                       trait_did: DefId) -> Item {
+        let tcx = self.cx.tcx;
+        let params = param_env.free_substs.types().collect::<Vec<_>>();
+        let bound = |param: Ty<'tcx>| {
+            ty::TraitRef {
+                def_id: trait_did,
+                substs: tcx.mk_substs_trait(param, &[]),
+            }.to_predicate()
+        };
+        let holds_with = |bounded: &[bool]| {
+            let mut caller_bounds = param_env.caller_bounds.clone();
+            caller_bounds.extend(params.iter().zip(bounded).filter(|&(_, &b)| b)
+                                       .map(|(&param, _)| bound(param)));
+            let env = param_env.with_caller_bounds(caller_bounds);
+            tcx.infer_ctxt(env, Reveal::All).enter(|infcx| {
+                traits::type_known_to_meet_bound(&infcx, ty, trait_did, DUMMY_SP)
+            })
+        };
+
+        // If the trait doesn't hold outright, start from every type parameter
+        // being bound by it, then drop the bounds which aren't needed.
+        let mut bounded = vec![false; params.len()];
+        let polarity = if holds_with(&bounded) {
+            clean::ImplPolarity::Positive
+        } else {
+            for b in &mut bounded {
+                *b = true;
+            }
+            if params.is_empty() || !holds_with(&bounded) {
+                for b in &mut bounded {
+                    *b = false;
+                }
+                clean::ImplPolarity::Negative
+            } else {
+                for i in 0..bounded.len() {
+                    bounded[i] = false;
+                    if !holds_with(&bounded) {
+                        bounded[i] = true;
+                    }
+                }
+                clean::ImplPolarity::Positive
+            }
+        };
+
+        let trait_bound = ty::TraitRef {
+            def_id: trait_did,
+            substs: tcx.mk_substs_trait(ty, &[]),
+        }.clean(self.cx);
+        let mut generics = generics.clone();
+        for (param, &b) in generics.type_params.iter_mut().zip(&bounded) {
+            if b {
+                param.bounds.push(trait_bound.clone());
+            }
+        }
+        let trait_ = match trait_bound {
+            clean::TraitBound(polyt, _) => polyt.trait_,
+            clean::RegionBound(..) => unreachable!(),
+        };
+
+        Item {
+            name: None,
+            attrs: clean::Attributes::default(),
+            source: DUMMY_SP.clean(self.cx),
+            visibility: Some(clean::Inherited),
+            stability: None,
+            deprecation: None,
+            // There's no impl to point at, so the type stands in for one.
+            // Anything keeping track of items by their `DefId` has to skip
+            // synthetic impls.
+            def_id: item_did,
+            inner: clean::ImplItem(clean::Impl {
+                unsafety: hir::Unsafety::Normal,
+                generics: generics,
+                provided_trait_methods: FxHashSet(),
+                trait_: Some(trait_),
+                for_: for_.clone(),
+                items: vec![],
+                polarity: Some(polarity),
+                synthetic: true,
+                blanket_impl: None,
+            }),
+        }
+    }
+}
+
+/// Whether the blanket impl `impl_did` applies to `ty`, given the bounds in
+/// `param_env`.
+fn blanket_impl_applies<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                  param_env: &ty::ParameterEnvironment<'tcx>,
+                                  ty: Ty<'tcx>,
+                                  impl_did: DefId) -> bool {
+    tcx.infer_ctxt(param_env.clone(), Reveal::All).enter(|infcx| {
+        let tcx = infcx.tcx;
+        let impl_substs = infcx.fresh_substs_for_item(DUMMY_SP, impl_did);
+        let impl_ty = tcx.item_type(impl_did).subst(tcx, impl_substs);
+        let cause = ObligationCause::dummy();
+        let mut obligations = match infcx.eq_types(true, &cause, impl_ty, ty) {
+            Ok(InferOk { obligations, .. }) => obligations,
+            Err(_) => return false,
+        };
+        let predicates = tcx.item_predicates(impl_did).instantiate(tcx, impl_substs);
+        obligations.extend(traits::predicates_for_generics(cause, &predicates));
+
+        let mut selcx = SelectionContext::new(&infcx);
+        obligations.iter().all(|o| selcx.evaluate_obligation_conservatively(o))
+    })
+}
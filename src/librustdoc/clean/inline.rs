@@ -297,7 +297,11 @@ pub fn build_impl(cx: &DocContext, did: DefId, ret: &mut Vec<clean::Item>) {
     if !cx.renderinfo.borrow_mut().inlined.insert(did) {
         return
     }
+    build_impl_uncached(cx, did, ret)
+}
 
+/// Like `build_impl`, but builds the impl even if it has been inlined before.
+pub fn build_impl_uncached(cx: &DocContext, did: DefId, ret: &mut Vec<clean::Item>) {
     let attrs = load_attrs(cx, did);
     let tcx = cx.tcx;
     let associated_trait = tcx.impl_trait_ref(did);
@@ -440,6 +444,8 @@ pub fn build_impl(cx: &DocContext, did: DefId, ret: &mut Vec<clean::Item>) {
             generics: (tcx.item_generics(did), &predicates).clean(cx),
             items: trait_items,
             polarity: Some(polarity.clean(cx)),
+            synthetic: false,
+            blanket_impl: None,
         }),
         source: tcx.def_span(did).clean(cx),
         name: None,
@@ -0,0 +1,450 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Resolution of intra-doc links.
+//!
+//! A Markdown link whose target is a Rust path, either inline as in
+//! `[text](Foo::bar)` or as a bare reference as in ``[`Foo::bar`]``, is
+//! resolved against the scope of the item being documented. The paths are
+//! resolved by rustc's resolver while it's still around, see
+//! `collect_doc_links`, and the items they name are then recorded in the
+//! documented item's `Attributes::links` and turned into URLs when the
+//! documentation is rendered.
+
+use rustc::hir;
+use rustc::hir::def::Def;
+use rustc::hir::def_id::DefId;
+use rustc::hir::map as hir_map;
+use rustc::ty;
+use rustc::util::nodemap::{FxHashSet, NodeMap};
+use rustc_resolve::Resolver;
+use syntax::ast;
+use syntax::visit::{self, Visitor};
+use syntax_pos::DUMMY_SP;
+
+use clean::{Crate, Item};
+use core::DocContext;
+use fold::DocFolder;
+use html::item_type::ItemType;
+use html::markdown;
+
+/// A candidate intra-doc link in the documentation of an item.
+pub struct DocLink {
+    /// The link as written, e.g. ``[`Foo::bar`]`` is keyed by ``"`Foo::bar`"``.
+    pub link: String,
+    /// Whether it was an inline link rather than a bare reference.
+    pub explicit: bool,
+    /// What the resolver made of the link's path: the definition of its
+    /// longest prefix naming an item, and the number of segments after it.
+    pub resolution: Option<(Def, usize)>,
+}
+
+/// The candidate intra-doc links of every item, by the item's `NodeId`.
+pub type DocLinks = NodeMap<Vec<DocLink>>;
+
+/// Resolves the paths of the candidate intra-doc links in the documentation
+/// of `krate`, each in the module which the documented item is in.
+pub fn collect_doc_links(krate: &ast::Crate, resolver: &mut Resolver) -> DocLinks {
+    let mut collector = DocLinkCollector {
+        resolver: resolver,
+        module: ast::CRATE_NODE_ID,
+        links: NodeMap(),
+    };
+    collector.collect(ast::CRATE_NODE_ID, &krate.attrs);
+    visit::walk_crate(&mut collector, krate);
+    collector.links
+}
+
+struct DocLinkCollector<'a, 'b: 'a> {
+    resolver: &'a mut Resolver<'b>,
+    /// The module which the items being visited are in.
+    module: ast::NodeId,
+    links: DocLinks,
+}
+
+impl<'a, 'b> DocLinkCollector<'a, 'b> {
+    fn collect(&mut self, id: ast::NodeId, attrs: &[ast::Attribute]) {
+        let mut links = vec![];
+        for attr in attrs {
+            let doc = attr.with_desugared_doc(|attr| {
+                if attr.check_name("doc") { attr.value_str() } else { None }
+            });
+            let doc = match doc {
+                Some(doc) => doc,
+                None => continue,
+            };
+            for (link, explicit) in candidate_links(&doc.as_str()) {
+                let resolution = self.resolve(link.trim_matches('`'));
+                links.push(DocLink {
+                    link: link,
+                    explicit: explicit,
+                    resolution: resolution,
+                });
+            }
+        }
+        if !links.is_empty() {
+            self.links.insert(id, links);
+        }
+    }
+
+    /// Resolves `path` in the current module, or failing that, as though it
+    /// was written in the crate root like the paths of imports are. Paths
+    /// starting with `Self` and macros aren't resolved here but are left to
+    /// `LinkCollector`, which knows the types and macros of the crate.
+    fn resolve(&mut self, path: &str) -> Option<(Def, usize)> {
+        if path.ends_with('!') || path == "Self" || path.starts_with("Self::") {
+            return None;
+        }
+        let (path, is_value) = if path.ends_with("()") {
+            (&path[..path.len() - 2], true)
+        } else {
+            (path, false)
+        };
+        let path = if path.starts_with("crate::") { &path[5..] } else { path };
+
+        for &module in &[self.module, ast::CRATE_NODE_ID] {
+            for &value_ns in &[is_value, !is_value] {
+                let resolution = self.resolver.resolve_str_path(module, path, value_ns);
+                if resolution.is_some() {
+                    return resolution;
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<'a, 'b, 'ast> Visitor<'ast> for DocLinkCollector<'a, 'b> {
+    fn visit_item(&mut self, item: &'ast ast::Item) {
+        match item.node {
+            ast::ItemKind::Mod(..) => {
+                let parent = self.module;
+                self.module = item.id;
+                // A module's own documentation is in its scope.
+                self.collect(item.id, &item.attrs);
+                visit::walk_item(self, item);
+                self.module = parent;
+            }
+            // Nothing inside a function body is documented.
+            ast::ItemKind::Fn(..) => self.collect(item.id, &item.attrs),
+            _ => {
+                self.collect(item.id, &item.attrs);
+                visit::walk_item(self, item);
+            }
+        }
+    }
+
+    fn visit_trait_item(&mut self, item: &'ast ast::TraitItem) {
+        self.collect(item.id, &item.attrs);
+    }
+
+    fn visit_impl_item(&mut self, item: &'ast ast::ImplItem) {
+        self.collect(item.id, &item.attrs);
+    }
+
+    fn visit_foreign_item(&mut self, item: &'ast ast::ForeignItem) {
+        self.collect(item.id, &item.attrs);
+    }
+
+    fn visit_variant(&mut self, variant: &'ast ast::Variant, generics: &'ast ast::Generics,
+                     item_id: ast::NodeId) {
+        self.collect(variant.node.data.id(), &variant.node.attrs);
+        visit::walk_variant(self, variant, generics, item_id);
+    }
+
+    fn visit_struct_field(&mut self, field: &'ast ast::StructField) {
+        self.collect(field.id, &field.attrs);
+    }
+
+    fn visit_macro_def(&mut self, macro_def: &'ast ast::MacroDef) {
+        self.collect(macro_def.id, &macro_def.attrs);
+    }
+}
+
+/// Records the items which the links collected by `collect_doc_links` point
+/// to in the documentation of `krate`, warning about those which name
+/// nothing.
+pub fn resolve_intra_links(cx: &DocContext, krate: Crate, doc_links: &DocLinks) -> Crate {
+    LinkCollector { cx: cx, doc_links: doc_links }.fold_crate(krate)
+}
+
+struct LinkCollector<'a, 'b: 'a, 'tcx: 'b> {
+    cx: &'a DocContext<'b, 'tcx>,
+    doc_links: &'a DocLinks,
+}
+
+impl<'a, 'b, 'tcx> DocFolder for LinkCollector<'a, 'b, 'tcx> {
+    fn fold_item(&mut self, mut item: Item) -> Option<Item> {
+        let node_id = self.cx.tcx.hir.as_local_node_id(item.def_id);
+        let doc_links = node_id.and_then(|node_id| self.doc_links.get(&node_id));
+        if let (Some(node_id), Some(doc_links)) = (node_id, doc_links) {
+            let mut links = vec![];
+            for doc_link in doc_links {
+                let key = &doc_link.link;
+                if links.iter().any(|&(ref k, ..)| k == key) {
+                    continue;
+                }
+                let path = key.trim_matches('`');
+                let target = match doc_link.resolution {
+                    Some((def, depth)) => self.target(def, depth, path),
+                    None => self.resolve_locally(path, node_id),
+                };
+                match target {
+                    Some((did, fragment)) => links.push((key.clone(), did, fragment)),
+                    None if doc_link.explicit || looks_like_code(key) => {
+                        let span = self.cx.tcx.hir.span_if_local(item.def_id)
+                                                   .unwrap_or(DUMMY_SP);
+                        self.cx.sess().span_warn(span,
+                            &format!("unresolved link to `{}`", path));
+                    }
+                    None => {}
+                }
+            }
+            item.attrs.links = links;
+        }
+        self.fold_item_recur(item)
+    }
+}
+
+impl<'a, 'b, 'tcx> LinkCollector<'a, 'b, 'tcx> {
+    /// The item whose page documents the resolution of `path`, and the
+    /// fragment of that page it lives at, if any.
+    fn target(&self, def: Def, depth: usize, path: &str) -> Option<(DefId, Option<String>)> {
+        let tcx = self.cx.tcx;
+        let path = if path.ends_with("()") { &path[..path.len() - 2] } else { path };
+        let name = path.rsplit("::").next().unwrap();
+        match (def, depth) {
+            (Def::Variant(did), 0) => {
+                tcx.parent_def_id(did).map(|parent| {
+                    (parent, Some(format!("{}.{}", ItemType::Variant, name)))
+                })
+            }
+            (Def::Method(did), 0) |
+            (Def::AssociatedConst(did), 0) |
+            (Def::AssociatedTy(did), 0) => match tcx.associated_item(did).container {
+                ty::TraitContainer(trait_did) => {
+                    self.member(Def::Trait(trait_did), name).map(|f| (trait_did, Some(f)))
+                }
+                ty::ImplContainer(_) => None,
+            },
+            (Def::Mod(..), 0) | (Def::Struct(..), 0) | (Def::Union(..), 0) |
+            (Def::Enum(..), 0) | (Def::Trait(..), 0) | (Def::TyAlias(..), 0) |
+            (Def::Fn(..), 0) | (Def::Const(..), 0) | (Def::Static(..), 0) |
+            (Def::Macro(..), 0) => Some((def.def_id(), None)),
+            // Anything below a type is documented on the type's page.
+            (_, 1) => self.member(def, name).map(|fragment| (def.def_id(), Some(fragment))),
+            _ => None,
+        }
+    }
+
+    /// Resolves the paths which the resolver doesn't know about: those
+    /// starting with `Self`, and macros.
+    fn resolve_locally(&self, path: &str, node_id: ast::NodeId)
+                       -> Option<(DefId, Option<String>)> {
+        let tcx = self.cx.tcx;
+        if path.ends_with('!') {
+            let name = &path[..path.len() - 1];
+            return tcx.hir.krate().exported_macros.iter()
+                .find(|m| m.name == name)
+                .map(|m| (tcx.hir.local_def_id(m.id), None));
+        }
+        let self_def = match self.self_def(node_id) {
+            Some(def) => def,
+            None => return None,
+        };
+        match path.split("::").count() {
+            1 if path == "Self" => self.target(self_def, 0, path),
+            2 => self.target(self_def, 1, path),
+            _ => None,
+        }
+    }
+
+    /// What `Self` refers to in the docs of `node_id`, if anything.
+    fn self_def(&self, node_id: ast::NodeId) -> Option<Def> {
+        let tcx = self.cx.tcx;
+        for &id in &[node_id, tcx.hir.get_parent(node_id)] {
+            if let Some(hir_map::NodeItem(item)) = tcx.hir.find(id) {
+                let did = tcx.hir.local_def_id(item.id);
+                match item.node {
+                    hir::ItemImpl(..) => {
+                        return tcx.item_type(did).ty_adt_def().map(adt_def);
+                    }
+                    hir::ItemTrait(..) => return Some(Def::Trait(did)),
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
+
+    /// Finds `name` amongst the variants, fields and associated items of the
+    /// type or trait `def`, giving its fragment on the type's page.
+    fn member(&self, def: Def, name: &str) -> Option<String> {
+        let tcx = self.cx.tcx;
+        let (did, is_trait) = match def {
+            Def::Struct(did) | Def::Union(did) | Def::Enum(did) => (did, false),
+            Def::Trait(did) => (did, true),
+            _ => return None,
+        };
+
+        if !is_trait {
+            let adt = tcx.lookup_adt_def(did);
+            if adt.is_enum() {
+                if let Some(v) = adt.variants.iter().find(|v| v.name == name) {
+                    return Some(format!("{}.{}", ItemType::Variant, v.name));
+                }
+            } else if let Some(f) = adt.struct_variant().fields.iter().find(|f| f.name == name) {
+                return Some(format!("{}.{}", ItemType::StructField, f.name));
+            }
+        }
+
+        let containers = if is_trait {
+            vec![did]
+        } else {
+            tcx.populate_inherent_implementations_for_type_if_necessary(did);
+            tcx.inherent_impls.borrow().get(&did).cloned().unwrap_or_default()
+        };
+        let mut seen = FxHashSet();
+        for container in containers {
+            for assoc in tcx.associated_items(container) {
+                if assoc.name != name || !seen.insert(assoc.def_id) {
+                    continue;
+                }
+                let ty = match assoc.kind {
+                    ty::AssociatedKind::Const => ItemType::AssociatedConst,
+                    ty::AssociatedKind::Type => ItemType::AssociatedType,
+                    ty::AssociatedKind::Method if is_trait && !assoc.defaultness.has_value() => {
+                        ItemType::TyMethod
+                    }
+                    ty::AssociatedKind::Method => ItemType::Method,
+                };
+                return Some(format!("{}.{}", ty, name));
+            }
+        }
+        None
+    }
+}
+
+fn adt_def(adt: &ty::AdtDef) -> Def {
+    if adt.is_enum() {
+        Def::Enum(adt.did)
+    } else if adt.is_union() {
+        Def::Union(adt.did)
+    } else {
+        Def::Struct(adt.did)
+    }
+}
+
+/// Whether an unresolved bare reference still clearly meant to name an item,
+/// as opposed to e.g. the `[x]` of a task list.
+fn looks_like_code(key: &str) -> bool {
+    key.starts_with('`') || key.contains("::") || key.ends_with("()") || key.ends_with('!')
+}
+
+/// Whether `s` has the shape of a path: `::`-separated identifiers, optionally
+/// followed by `()` for a function or `!` for a macro.
+fn is_path(s: &str) -> bool {
+    let s = if s.ends_with("()") {
+        &s[..s.len() - 2]
+    } else if s.ends_with('!') {
+        &s[..s.len() - 1]
+    } else {
+        s
+    };
+    let s = if s.starts_with("::") { &s[2..] } else { s };
+    !s.is_empty() && s.split("::").all(|segment| {
+        let mut chars = segment.chars();
+        match chars.next() {
+            Some(c) if c.is_alphabetic() || c == '_' => {}
+            _ => return false,
+        }
+        chars.all(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// Finds the links in `md` which could be intra-doc links, as pairs of the
+/// text naming the target (an inline link's target, or the label of a
+/// reference without a definition) and whether it was an inline link. Code
+/// blocks and code spans are skipped.
+fn candidate_links(md: &str) -> Vec<(String, bool)> {
+    let mut defined = FxHashSet();
+    for line in md.lines() {
+        let line = line.trim_left();
+        if line.starts_with('[') {
+            if let Some(end) = line.find("]:") {
+                defined.insert(line[1..end].to_lowercase());
+            }
+        }
+    }
+
+    let mut links = vec![];
+    for (line, is_code) in md.split('\n').zip(markdown::code_block_lines(md)) {
+        if is_code {
+            continue;
+        }
+
+        let bytes = line.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'`' => {
+                    let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                    let fence = &line[i..i + run];
+                    i = match line[i + run..].find(fence) {
+                        Some(end) => i + run + end + run,
+                        None => i + run,
+                    };
+                }
+                b'[' => {
+                    let start = i + 1;
+                    let end = match line[start..].find(']') {
+                        Some(end) => start + end,
+                        None => break,
+                    };
+                    let label = &line[start..end];
+                    let rest = &line[end + 1..];
+                    if rest.starts_with('(') {
+                        if let Some(close) = rest.find(')') {
+                            let target = rest[1..close].trim();
+                            if is_path(target) {
+                                links.push((target.to_string(), true));
+                            }
+                            i = end + 1 + close + 1;
+                            continue;
+                        }
+                    } else if rest.starts_with('[') {
+                        if let Some(close) = rest.find(']') {
+                            let label = if close == 1 { label } else { &rest[1..close] };
+                            if !defined.contains(&label.to_lowercase()) &&
+                               is_path(label.trim_matches('`')) {
+                                links.push((label.to_string(), false));
+                            }
+                            i = end + 1 + close + 1;
+                            continue;
+                        }
+                    } else if !rest.starts_with(':') {
+                        let follows_word = i > 0 && match bytes[i - 1] {
+                            b'!' | b']' | b'_' => true,
+                            b => (b as char).is_alphanumeric(),
+                        };
+                        if !follows_word && !defined.contains(&label.to_lowercase()) &&
+                           is_path(label.trim_matches('`')) {
+                            links.push((label.to_string(), false));
+                        }
+                    }
+                    i = end + 1;
+                }
+                _ => i += 1,
+            }
+        }
+    }
+    links
+}
@@ -51,7 +51,10 @@ use doctree;
 use visit_ast;
 use html::item_type::ItemType;
 
+pub mod auto_impls;
 pub mod inline;
+pub mod intra_links;
+pub mod span_map;
 mod simplify;
 
 // extract the stability index for a node from tcx, if possible
@@ -283,6 +286,14 @@ impl Item {
             _ => false,
         }
     }
+    /// Whether this is an impl rustdoc worked out itself. These don't exist
+    /// in the source, so their `def_id` is the one of the type they are for.
+    pub fn is_synthetic(&self) -> bool {
+        match self.inner {
+            ImplItem(ref i) => i.synthetic,
+            _ => false,
+        }
+    }
     pub fn is_mod(&self) -> bool {
         self.type_() == ItemType::Module
     }
@@ -521,7 +532,10 @@ impl<'a, I: IntoIterator<Item=&'a ast::NestedMetaItem>> NestedAttributesExt for
 #[derive(Clone, RustcEncodable, RustcDecodable, PartialEq, Debug, Default)]
 pub struct Attributes {
     pub doc_strings: Vec<String>,
-    pub other_attrs: Vec<ast::Attribute>
+    pub other_attrs: Vec<ast::Attribute>,
+    /// Intra-doc links found in `doc_strings`: the link as written, the item
+    /// whose page it points to and the fragment of that page, if any.
+    pub links: Vec<(String, DefId, Option<String>)>,
 }
 
 impl Attributes {
@@ -541,7 +555,8 @@ impl Attributes {
         }).collect();
         Attributes {
             doc_strings: doc_strings,
-            other_attrs: other_attrs
+            other_attrs: other_attrs,
+            links: vec![],
         }
     }
 
@@ -2437,6 +2452,12 @@ pub struct Impl {
     pub for_: Type,
     pub items: Vec<Item>,
     pub polarity: Option<ImplPolarity>,
+    /// Set for the auto trait impls rustdoc works out itself, which don't
+    /// exist anywhere in the source.
+    pub synthetic: bool,
+    /// For a copy of a blanket impl shown on the page of a type it applies
+    /// to, that type.
+    pub blanket_impl: Option<Type>,
 }
 
 impl Clean<Vec<Item>> for doctree::Impl {
@@ -2474,6 +2495,8 @@ impl Clean<Vec<Item>> for doctree::Impl {
                 for_: self.for_.clean(cx),
                 items: items,
                 polarity: Some(self.polarity.clean(cx)),
+                synthetic: false,
+                blanket_impl: None,
             }),
         });
         ret
@@ -0,0 +1,343 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Cross-references for the rendered source of the local crate.
+//!
+//! Every identifier in the crate's source which names an item, a field, a
+//! method or a local variable is recorded along with what it refers to, so
+//! that the source pages can link each use to its definition, and each
+//! definition to the list of places it's used from.
+
+use rustc::hir::{self, intravisit};
+use rustc::hir::def::Def;
+use rustc::hir::def_id::DefId;
+use rustc::hir::intravisit::{NestedVisitorMap, Visitor};
+use rustc::ty;
+use rustc::util::nodemap::FxHashMap;
+use syntax::ast::{self, Name};
+use syntax_pos::{BytePos, NO_EXPANSION, Pos, Span};
+
+use std::mem;
+
+use clean::{inline, TypeKind};
+use core::DocContext;
+
+#[derive(Default)]
+pub struct SpanMap {
+    /// The linked identifiers in each local source file, by file name.
+    pub files: FxHashMap<String, Vec<SourceLink>>,
+    /// Where each local item or variable is defined.
+    pub definitions: FxHashMap<DefId, Location>,
+    /// The places each local item or variable is used from.
+    pub references: FxHashMap<DefId, Vec<Location>>,
+}
+
+/// An identifier in a source file, as a range of byte offsets into the file.
+#[derive(Clone, Debug)]
+pub struct SourceLink {
+    pub lo: usize,
+    pub hi: usize,
+    pub kind: LinkKind,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum LinkKind {
+    /// The identifier is where the given item is defined.
+    Definition(DefId),
+    /// The identifier refers to the given item, defined elsewhere.
+    Reference(DefId),
+}
+
+#[derive(Clone, Debug)]
+pub struct Location {
+    pub name: String,
+    pub filename: String,
+    /// The 1-based line the identifier is on.
+    pub line: usize,
+    /// That line, trimmed.
+    pub snippet: String,
+}
+
+pub fn collect_span_map(cx: &DocContext) -> SpanMap {
+    let mut collector = SpanMapCollector {
+        cx: cx,
+        tables: None,
+        map: SpanMap::default(),
+    };
+    cx.tcx.hir.krate().visit_all_item_likes(&mut collector.as_deep_visitor());
+    // A field initialised with the shorthand `Foo { x }` is also a use of
+    // `x`, so only keep the first link at each place.
+    for links in collector.map.files.values_mut() {
+        links.sort_by_key(|link| link.lo);
+        let mut last = None;
+        links.retain(|link| {
+            let first = last != Some(link.lo);
+            last = Some(link.lo);
+            first
+        });
+    }
+    collector.map
+}
+
+struct SpanMapCollector<'a, 'b: 'a, 'tcx: 'b> {
+    cx: &'a DocContext<'b, 'tcx>,
+    /// The type check results of the body being visited, if any.
+    tables: Option<&'tcx ty::TypeckTables<'tcx>>,
+    map: SpanMap,
+}
+
+impl<'a, 'b, 'tcx> SpanMapCollector<'a, 'b, 'tcx> {
+    fn add_definition(&mut self, did: DefId, span: Span, name: Name) {
+        let location = match self.locate(span, name) {
+            Some(location) => location,
+            None => return,
+        };
+        self.map.definitions.insert(did, location.1.clone());
+        self.add_link(location, LinkKind::Definition(did));
+    }
+
+    fn add_def_reference(&mut self, def: Def, span: Span, name: Name) {
+        let tcx = self.cx.tcx;
+        let (did, kind) = match def {
+            Def::Fn(did) => (did, Some(TypeKind::Function)),
+            Def::Mod(did) => (did, Some(TypeKind::Module)),
+            Def::Static(did, _) => (did, Some(TypeKind::Static)),
+            Def::Const(did) => (did, Some(TypeKind::Const)),
+            Def::Enum(did) => (did, Some(TypeKind::Enum)),
+            Def::TyAlias(did) => (did, Some(TypeKind::Typedef)),
+            Def::Struct(did) => (did, Some(TypeKind::Struct)),
+            Def::Union(did) => (did, Some(TypeKind::Union)),
+            Def::Trait(did) => (did, Some(TypeKind::Trait)),
+            // Constructors are defined, and documented, along with their types.
+            Def::StructCtor(did, ..) => {
+                (tcx.parent_def_id(did).unwrap_or(did), Some(TypeKind::Struct))
+            }
+            Def::Variant(did) | Def::VariantCtor(did, ..) if !did.is_local() => {
+                (tcx.parent_def_id(did).unwrap_or(did), Some(TypeKind::Enum))
+            }
+            Def::Label(..) | Def::PrimTy(..) | Def::SelfTy(..) | Def::Err => return,
+            _ => (def.def_id(), None),
+        };
+        // Items from other crates link to their documentation, so that has to
+        // know where to find them. Anything without a page of its own isn't
+        // linked at all.
+        if !did.is_local() {
+            match kind {
+                Some(kind) => inline::record_extern_fqn(self.cx, did, kind),
+                None => return,
+            }
+        }
+        self.add_reference(did, span, name);
+    }
+
+    fn add_reference(&mut self, did: DefId, span: Span, name: Name) {
+        let location = match self.locate(span, name) {
+            Some(location) => location,
+            None => return,
+        };
+        if did.is_local() {
+            self.map.references.entry(did).or_insert(vec![]).push(location.1.clone());
+        }
+        self.add_link(location, LinkKind::Reference(did));
+    }
+
+    fn add_link(&mut self, (lo, location): (usize, Location), kind: LinkKind) {
+        let hi = lo + location.name.len();
+        self.map.files.entry(location.filename).or_insert(vec![]).push(SourceLink {
+            lo: lo,
+            hi: hi,
+            kind: kind,
+        });
+    }
+
+    /// Finds `name` in `span`, giving its offset into its file and where it
+    /// is. Identifiers which don't come straight from a source file, such as
+    /// those made up by macros, are left out.
+    fn locate(&self, span: Span, name: Name) -> Option<(usize, Location)> {
+        let codemap = self.cx.sess().codemap();
+        let name = name.as_str();
+        if span.expn_id != NO_EXPANSION || name.is_empty() {
+            return None;
+        }
+        let snippet = match codemap.span_to_snippet(span) {
+            Ok(snippet) => snippet,
+            Err(_) => return None,
+        };
+        let offset = match find_word(&snippet, &name) {
+            Some(offset) => offset,
+            None => return None,
+        };
+        let lo = span.lo + BytePos::from_usize(offset);
+
+        let file = codemap.lookup_byte_offset(lo);
+        if file.fm.name.starts_with('<') {
+            return None;
+        }
+        let line = codemap.lookup_char_pos(lo).line;
+        Some((file.pos.to_usize(), Location {
+            name: name.to_string(),
+            filename: file.fm.name.clone(),
+            line: line,
+            snippet: file.fm.get_line(line - 1).unwrap_or("").trim().to_string(),
+        }))
+    }
+
+    /// The definition of the field `name` of the struct or union `ty`, if it's
+    /// in this crate.
+    fn field(&self, ty: ty::Ty<'tcx>, name: Name) -> Option<DefId> {
+        match ty.sty {
+            ty::TyAdt(def, _) if !def.is_enum() => {
+                match def.struct_variant().find_field_named(name) {
+                    Some(field) if field.did.is_local() => Some(field.did),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'a, 'b, 'tcx> Visitor<'tcx> for SpanMapCollector<'a, 'b, 'tcx> {
+    fn nested_visit_map<'this>(&'this mut self) -> NestedVisitorMap<'this, 'tcx> {
+        NestedVisitorMap::OnlyBodies(&self.cx.tcx.hir)
+    }
+
+    fn visit_nested_body(&mut self, id: hir::BodyId) {
+        let tables = self.cx.tcx.body_tables(id);
+        let old_tables = mem::replace(&mut self.tables, Some(tables));
+        let body = self.cx.tcx.hir.body(id);
+        self.visit_body(body);
+        self.tables = old_tables;
+    }
+
+    fn visit_item(&mut self, item: &'tcx hir::Item) {
+        match item.node {
+            hir::ItemUse(..) | hir::ItemImpl(..) | hir::ItemDefaultImpl(..) |
+            hir::ItemExternCrate(..) | hir::ItemForeignMod(..) => {}
+            _ => {
+                let did = self.cx.tcx.hir.local_def_id(item.id);
+                self.add_definition(did, item.span, item.name);
+            }
+        }
+        intravisit::walk_item(self, item);
+    }
+
+    fn visit_trait_item(&mut self, item: &'tcx hir::TraitItem) {
+        let did = self.cx.tcx.hir.local_def_id(item.id);
+        self.add_definition(did, item.span, item.name);
+        intravisit::walk_trait_item(self, item);
+    }
+
+    fn visit_impl_item(&mut self, item: &'tcx hir::ImplItem) {
+        let did = self.cx.tcx.hir.local_def_id(item.id);
+        self.add_definition(did, item.span, item.name);
+        intravisit::walk_impl_item(self, item);
+    }
+
+    fn visit_foreign_item(&mut self, item: &'tcx hir::ForeignItem) {
+        let did = self.cx.tcx.hir.local_def_id(item.id);
+        self.add_definition(did, item.span, item.name);
+        intravisit::walk_foreign_item(self, item);
+    }
+
+    fn visit_struct_field(&mut self, field: &'tcx hir::StructField) {
+        let did = self.cx.tcx.hir.local_def_id(field.id);
+        self.add_definition(did, field.span, field.name);
+        intravisit::walk_struct_field(self, field);
+    }
+
+    fn visit_variant(&mut self,
+                     variant: &'tcx hir::Variant,
+                     generics: &'tcx hir::Generics,
+                     item_id: ast::NodeId) {
+        let did = self.cx.tcx.hir.local_def_id(variant.node.data.id());
+        self.add_definition(did, variant.span, variant.node.name);
+        intravisit::walk_variant(self, variant, generics, item_id);
+    }
+
+    fn visit_pat(&mut self, pat: &'tcx hir::Pat) {
+        if let hir::PatKind::Binding(_, did, ref name, _) = pat.node {
+            self.add_definition(did, name.span, name.node);
+        }
+        intravisit::walk_pat(self, pat);
+    }
+
+    fn visit_expr(&mut self, expr: &'tcx hir::Expr) {
+        if let Some(tables) = self.tables {
+            match expr.node {
+                hir::ExprMethodCall(ref name, ..) => {
+                    if let Some(callee) = tables.method_map.get(&ty::MethodCall::expr(expr.id)) {
+                        self.add_def_reference(Def::Method(callee.def_id), name.span, name.node);
+                    }
+                }
+                hir::ExprField(ref base, ref name) => {
+                    if let Some(did) = self.field(tables.expr_ty_adjusted(base), name.node) {
+                        self.add_reference(did, name.span, name.node);
+                    }
+                }
+                hir::ExprStruct(_, ref fields, _) => {
+                    let ty = tables.expr_ty(expr);
+                    for field in fields {
+                        if let Some(did) = self.field(ty, field.name.node) {
+                            self.add_reference(did, field.name.span, field.name.node);
+                        }
+                    }
+                }
+                hir::ExprPath(hir::QPath::TypeRelative(_, ref segment)) => {
+                    if let Some(&def) = tables.type_relative_path_defs.get(&expr.id) {
+                        self.add_def_reference(def, expr.span, segment.name);
+                    }
+                }
+                _ => {}
+            }
+        }
+        intravisit::walk_expr(self, expr);
+    }
+
+    fn visit_path(&mut self, path: &'tcx hir::Path, _id: ast::NodeId) {
+        if let Some(segment) = path.segments.last() {
+            // Earlier segments could contain the name too, so look for it
+            // after them.
+            let mut span = path.span;
+            if let Ok(snippet) = self.cx.sess().codemap().span_to_snippet(span) {
+                let mut from = 0;
+                for segment in &path.segments[..path.segments.len() - 1] {
+                    match find_word(&snippet[from..], &segment.name.as_str()) {
+                        Some(offset) => from += offset + segment.name.as_str().len(),
+                        None => break,
+                    }
+                }
+                span.lo = span.lo + BytePos::from_usize(from);
+            }
+            self.add_def_reference(path.def, span, segment.name);
+        }
+        intravisit::walk_path(self, path);
+    }
+}
+
+/// The offset of the first occurrence of the identifier `word` in `s`.
+fn find_word(s: &str, word: &str) -> Option<usize> {
+    let mut from = 0;
+    while let Some(offset) = s[from..].find(word) {
+        let start = from + offset;
+        let end = start + word.len();
+        let before = s[..start].chars().next_back().map_or(false, is_ident_char);
+        let after = s[end..].chars().next().map_or(false, is_ident_char);
+        if !before && !after {
+            return Some(start);
+        }
+        from = end;
+    }
+    None
+}
+
+fn is_ident_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
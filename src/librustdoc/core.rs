@@ -37,6 +37,7 @@ use visit_ast::RustdocVisitor;
 use clean;
 use clean::Clean;
 use html::render::RenderInfo;
+use passes;
 use arena::DroplessArena;
 
 pub use rustc::session::config::Input;
@@ -108,7 +109,8 @@ pub fn run_core(search_paths: SearchPaths,
                 externs: config::Externs,
                 input: Input,
                 triple: Option<String>,
-                maybe_sysroot: Option<PathBuf>) -> (clean::Crate, RenderInfo)
+                maybe_sysroot: Option<PathBuf>,
+                deny_doc_warnings: bool) -> (clean::Crate, RenderInfo)
 {
     // Parse, resolve, and typecheck the given crate.
 
@@ -133,7 +135,7 @@ pub fn run_core(search_paths: SearchPaths,
         ..config::basic_options().clone()
     };
 
-    let codemap = Rc::new(codemap::CodeMap::new());
+    let codemap = Rc::new(codemap::CodeMap::new(sessopts.file_path_mapping()));
     let diagnostic_handler = errors::Handler::with_tty_emitter(ColorConfig::Auto,
                                                                true,
                                                                false,
@@ -155,15 +157,20 @@ pub fn run_core(search_paths: SearchPaths,
 
     let name = link::find_crate_name(Some(&sess), &krate.attrs, &input);
 
+    // The resolver doesn't outlive expansion, so the paths of intra-doc links
+    // are resolved right away.
+    let mut doc_links = NodeMap();
     let driver::ExpansionResult { defs, analysis, resolutions, mut hir_forest, .. } = {
-        let result = driver::phase_2_configure_and_expand(&sess,
-                                                          &cstore,
-                                                          krate,
-                                                          None,
-                                                          &name,
-                                                          None,
-                                                          resolve::MakeGlobMap::No,
-                                                          |_| Ok(()));
+        let result = driver::phase_2_configure_and_expand_with_resolver(
+            &sess,
+            &cstore,
+            krate,
+            None,
+            &name,
+            None,
+            resolve::MakeGlobMap::No,
+            |_| Ok(()),
+            |krate, resolver| doc_links = clean::intra_links::collect_doc_links(krate, resolver));
         abort_on_err(result, &sess)
     };
 
@@ -211,6 +218,13 @@ pub fn run_core(search_paths: SearchPaths,
             v.visit(tcx.hir.krate());
             v.clean(&ctxt)
         };
+        let krate = clean::intra_links::resolve_intra_links(&ctxt, krate, &doc_links);
+        let krate = clean::auto_impls::add_auto_impls(&ctxt, krate);
+        passes::check_docs(&ctxt, &krate, deny_doc_warnings);
+        sess.abort_if_errors();
+
+        let span_map = clean::span_map::collect_span_map(&ctxt);
+        ctxt.renderinfo.borrow_mut().span_map = span_map;
 
         (krate, ctxt.renderinfo.into_inner())
     }), &sess)
@@ -30,11 +30,23 @@ use syntax::codemap::CodeMap;
 use syntax::parse::lexer::{self, TokenAndSpan};
 use syntax::parse::token;
 use syntax::parse;
-use syntax_pos::Span;
+use syntax_pos::{BytePos, Pos, Span};
 
 /// Highlights `src`, returning the HTML output.
 pub fn render_with_highlighting(src: &str, class: Option<&str>, id: Option<&str>,
                                 extension: Option<&str>) -> String {
+    render(src, class, id, extension, &[])
+}
+
+/// Highlights `src` like `render_with_highlighting`, and also turns the
+/// identifiers in `links` into links to the accompanying URLs. The identifiers
+/// are given by their start and end offsets in `src`, in order.
+pub fn render_with_links(src: &str, links: &[(usize, usize, String)]) -> String {
+    render(src, None, None, None, links)
+}
+
+fn render(src: &str, class: Option<&str>, id: Option<&str>, extension: Option<&str>,
+          links: &[(usize, usize, String)]) -> String {
     debug!("highlighting: ================\n{}\n==============", src);
     let sess = parse::ParseSess::new();
     let fm = sess.codemap().new_filemap("<stdin>".to_string(), None, src.to_string());
@@ -42,9 +54,17 @@ pub fn render_with_highlighting(src: &str, class: Option<&str>, id: Option<&str>
     let mut out = Vec::new();
     write_header(class, id, &mut out).unwrap();
 
-    let mut classifier = Classifier::new(lexer::StringReader::new(&sess, fm), sess.codemap());
-    if let Err(_) = classifier.write_source(&mut out) {
-        return format!("<pre>{}</pre>", src);
+    {
+        let mut out = LinkWriter {
+            out: &mut out,
+            links: links,
+            start: fm.start_pos,
+        };
+        let mut classifier = Classifier::new(lexer::StringReader::new(&sess, fm),
+                                             sess.codemap());
+        if let Err(_) = classifier.write_source(&mut out) {
+            return format!("<pre>{}</pre>", src);
+        }
     }
 
     if let Some(extension) = extension {
@@ -157,6 +177,48 @@ impl<U: Write> Writer for U {
     }
 }
 
+/// Writes the default rustdoc output into `out`, wrapping the tokens which
+/// have been given URLs in links.
+struct LinkWriter<'a, W: Write + 'a> {
+    out: &'a mut W,
+    /// The tokens to link which haven't been reached yet.
+    links: &'a [(usize, usize, String)],
+    /// The position of the source in the codemap.
+    start: BytePos,
+}
+
+impl<'a, W: Write> Writer for LinkWriter<'a, W> {
+    fn string<T: Display>(&mut self,
+                          text: T,
+                          klass: Class,
+                          tas: Option<&TokenAndSpan>)
+                          -> io::Result<()> {
+        if let Some(tas) = tas {
+            let lo = (tas.sp.lo - self.start).to_usize();
+            let hi = (tas.sp.hi - self.start).to_usize();
+            while self.links.first().map_or(false, |link| link.0 < lo) {
+                self.links = &self.links[1..];
+            }
+            if let Some(&(link_lo, link_hi, ref url)) = self.links.first() {
+                if link_lo == lo && link_hi == hi {
+                    write!(self.out, "<a href='{}'>", url)?;
+                    self.out.string(text, klass, Some(tas))?;
+                    return write!(self.out, "</a>");
+                }
+            }
+        }
+        self.out.string(text, klass, tas)
+    }
+
+    fn enter_span(&mut self, klass: Class) -> io::Result<()> {
+        self.out.enter_span(klass)
+    }
+
+    fn exit_span(&mut self) -> io::Result<()> {
+        self.out.exit_span()
+    }
+}
+
 impl<'a> Classifier<'a> {
     pub fn new(lexer: lexer::StringReader<'a>, codemap: &'a CodeMap) -> Classifier<'a> {
         Classifier {
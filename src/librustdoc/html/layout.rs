@@ -47,8 +47,9 @@ r##"<!DOCTYPE html>
     <title>{title}</title>
 
     <link rel="stylesheet" type="text/css" href="{root_path}normalize.css">
-    <link rel="stylesheet" type="text/css" href="{root_path}rustdoc.css">
-    <link rel="stylesheet" type="text/css" href="{root_path}main.css">
+    <link rel="stylesheet" type="text/css" href="{root_path}rustdoc.css" id="mainThemeStyle">
+    <link rel="stylesheet" type="text/css" href="{root_path}light.css" id="themeStyle">
+    <script src="{root_path}storage.js"></script>
     {css_extension}
 
     {favicon}
@@ -70,6 +71,13 @@ r##"<!DOCTYPE html>
     </nav>
 
     <nav class="sub">
+        <div class="theme-picker">
+            <button id="theme-picker" aria-label="Pick another theme">
+                <img src="{root_path}contrast.svg" width="16" alt="Pick another theme">
+            </button>
+            <div id="theme-choices"></div>
+        </div>
+        <script src="{root_path}theme.js"></script>
         <form class="search-form js-only">
             <div class="search-container">
                 <input class="search-input" name="search"
@@ -125,7 +133,11 @@ r##"<!DOCTYPE html>
 
                 <p>
                     Search functions by type signature (e.g.
-                    <code>vec -> usize</code> or <code>* -> vec</code>)
+                    <code>Vec&lt;T&gt; -> usize</code> or
+                    <code>&amp;str -> Option&lt;char&gt;</code>). The order of
+                    the arguments doesn't matter, a single capital letter like
+                    <code>T</code> stands for any type and <code>*</code> for
+                    any arguments or return type (e.g. <code>* -> vec</code>).
                 </p>
             </div>
         </div>
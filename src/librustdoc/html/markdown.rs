@@ -13,14 +13,14 @@
 //! This module implements markdown formatting through the hoedown C-library
 //! (bundled into the rust runtime). This module self-contains the C bindings
 //! and necessary legwork to render markdown, and exposes all of the
-//! functionality through a unit-struct, `Markdown`, which has an implementation
+//! functionality through a tuple struct, `Markdown`, which has an implementation
 //! of `fmt::Display`. Example usage:
 //!
 //! ```rust,ignore
 //! use rustdoc::html::markdown::Markdown;
 //!
 //! let s = "My *markdown* _text_";
-//! let html = format!("{}", Markdown(s));
+//! let html = format!("{}", Markdown(s, &[]));
 //! // ... something using html
 //! ```
 
@@ -35,6 +35,7 @@ use std::fmt::{self, Write};
 use std::slice;
 use std::str;
 use syntax::feature_gate::UnstableFeatures;
+use syntax::util::lev_distance::lev_distance;
 
 use html::render::derive_id;
 use html::toc::TocBuilder;
@@ -42,10 +43,12 @@ use html::highlight;
 use html::escape::Escape;
 use test;
 
-/// A unit struct which has the `fmt::Display` trait implemented. When
+/// A tuple struct which has the `fmt::Display` trait implemented. When
 /// formatted, this struct will emit the HTML corresponding to the rendered
-/// version of the contained markdown string.
-pub struct Markdown<'a>(pub &'a str);
+/// version of the contained markdown string, with the intra-doc links in the
+/// second field (each the link as written and its URL) pointed at their
+/// targets.
+pub struct Markdown<'a>(pub &'a str, pub &'a [(String, String)]);
 /// A unit struct like `Markdown`, that renders the markdown with a
 /// table of contents.
 pub struct MarkdownWithToc<'a>(pub &'a str);
@@ -485,6 +488,40 @@ pub fn find_testable_code(doc: &str, tests: &mut ::test::Collector) {
     }
 }
 
+/// The attributes of a code block that `LangString` understands, apart from
+/// error codes.
+pub const LANG_STRING_TAGS: &'static [&'static str] = &[
+    "should_panic", "no_run", "ignore", "rust", "test_harness", "compile_fail",
+];
+
+/// Finds the tokens of a code block's info string which look like
+/// misspellings of a `LangString` tag, e.g. `ignroe`, each with the tag it
+/// was likely meant to be. Other unknown tokens are left alone since they're
+/// usually the name of another language.
+pub fn misspelled_lang_string_tags(string: &str) -> Vec<(String, &'static str)> {
+    let tokens = string.split(|c: char|
+        !(c == '_' || c == '-' || c.is_alphanumeric())
+    );
+
+    let mut misspelled = vec![];
+    for token in tokens {
+        if token.is_empty() || LANG_STRING_TAGS.contains(&token) {
+            continue
+        }
+        let lower = token.to_ascii_lowercase().replace('-', "_");
+        let closest = LANG_STRING_TAGS.iter().map(|&tag| {
+            (lev_distance(&lower, tag), tag)
+        }).min();
+        if let Some((distance, tag)) = closest {
+            let max_distance = if tag.len() > 4 { 2 } else { 1 };
+            if distance <= max_distance {
+                misspelled.push((token.to_owned(), tag));
+            }
+        }
+    }
+    misspelled
+}
+
 #[derive(Eq, PartialEq, Clone, Debug)]
 struct LangString {
     should_panic: bool,
@@ -557,10 +594,14 @@ impl LangString {
 
 impl<'a> fmt::Display for Markdown<'a> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let Markdown(md) = *self;
+        let Markdown(md, links) = *self;
         // This is actually common enough to special-case
         if md.is_empty() { return Ok(()) }
-        render(fmt, md, false, 0)
+        if links.is_empty() {
+            render(fmt, md, false, 0)
+        } else {
+            render(fmt, &resolve_links(md, links), false, 0)
+        }
     }
 }
 
@@ -580,6 +621,91 @@ impl<'a> fmt::Display for MarkdownHtml<'a> {
     }
 }
 
+/// Points the intra-doc links in `md` at their URLs: the target of inline
+/// links is replaced, and a definition is added for bare references. Code
+/// blocks and code spans are left alone.
+fn resolve_links(md: &str, links: &[(String, String)]) -> String {
+    let replace = |text: &str| {
+        let mut text = text.to_string();
+        for &(ref link, ref url) in links {
+            text = text.replace(&format!("]({})", link), &format!("]({})", url));
+        }
+        text
+    };
+
+    let mut out = String::with_capacity(md.len());
+    for (i, (line, is_code)) in md.split('\n').zip(code_block_lines(md)).enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        if is_code {
+            out.push_str(line);
+            continue;
+        }
+        let mut last = 0;
+        for (start, end) in code_spans(line) {
+            out.push_str(&replace(&line[last..start]));
+            out.push_str(&line[start..end]);
+            last = end;
+        }
+        out.push_str(&replace(&line[last..]));
+    }
+    out.push('\n');
+    for &(ref link, ref url) in links {
+        out.push_str(&format!("\n[{}]: {}", link, url));
+    }
+    out
+}
+
+/// Whether each line of `md`, split at `'\n'`, is part of a code block,
+/// either fenced or indented. Only an indented line following a blank line
+/// or another such line starts or continues an indented code block.
+pub fn code_block_lines(md: &str) -> Vec<bool> {
+    let mut in_fence = false;
+    let mut in_indented = false;
+    let mut after_blank = true;
+    md.split('\n').map(|line| {
+        let trimmed = line.trim_left();
+        let blank = trimmed.is_empty();
+        let is_code = if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            true
+        } else if in_fence {
+            true
+        } else {
+            let indented = line.starts_with("    ") || line.starts_with('\t');
+            in_indented = indented && !blank && (in_indented || after_blank);
+            in_indented
+        };
+        after_blank = blank;
+        is_code
+    }).collect()
+}
+
+/// The byte ranges of the code spans in `line`, backticks included.
+fn code_spans(line: &str) -> Vec<(usize, usize)> {
+    let bytes = line.as_bytes();
+    let mut spans = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => {
+                let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                match line[i + run..].find(&line[i..i + run]) {
+                    Some(end) => {
+                        spans.push((i, i + run + end + run));
+                        i += run + end + run;
+                    }
+                    None => i += run,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    spans
+}
+
 pub fn plain_summary_line(md: &str) -> String {
     extern fn link(_ob: *mut hoedown_buffer,
                        _link: *const hoedown_buffer,
@@ -629,7 +755,8 @@ pub fn plain_summary_line(md: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{LangString, Markdown, MarkdownHtml};
+    use super::{LangString, Markdown, MarkdownHtml, misspelled_lang_string_tags};
+    use super::{code_block_lines, resolve_links};
     use super::plain_summary_line;
     use html::render::reset_ids;
 
@@ -667,17 +794,36 @@ mod tests {
         t("{.test_harness .rust}", false,        false,  false,  true,  true,  false, Vec::new());
     }
 
+    #[test]
+    fn test_misspelled_lang_string_tags() {
+        fn t(s: &str, expected: &[(&str, &str)]) {
+            let found = misspelled_lang_string_tags(s);
+            let found = found.iter().map(|&(ref t, tag)| (&t[..], tag)).collect::<Vec<_>>();
+            assert_eq!(found, expected);
+        }
+
+        t("rust,ignore", &[]);
+        t("rust,ignroe", &[("ignroe", "ignore")]);
+        t("should-panic", &[("should-panic", "should_panic")]);
+        t("no_rnu,text", &[("no_rnu", "no_run")]);
+        t("Rust", &[("Rust", "rust")]);
+        t("sh", &[]);
+        t("ruby", &[]);
+        t("{.example .rust}", &[]);
+        t("E0123", &[]);
+    }
+
     #[test]
     fn issue_17736() {
         let markdown = "# title";
-        format!("{}", Markdown(markdown));
+        format!("{}", Markdown(markdown, &[]));
         reset_ids(true);
     }
 
     #[test]
     fn test_header() {
         fn t(input: &str, expect: &str) {
-            let output = format!("{}", Markdown(input));
+            let output = format!("{}", Markdown(input, &[]));
             assert_eq!(output, expect);
             reset_ids(true);
         }
@@ -699,7 +845,7 @@ mod tests {
     #[test]
     fn test_header_ids_multiple_blocks() {
         fn t(input: &str, expect: &str) {
-            let output = format!("{}", Markdown(input));
+            let output = format!("{}", Markdown(input, &[]));
             assert_eq!(output, expect);
         }
 
@@ -746,4 +892,46 @@ mod tests {
         t("`Struct<'a, T>`", "<p><code>Struct&lt;&#39;a, T&gt;</code></p>\n");
         t("Struct<'a, T>", "<p>Struct&lt;&#39;a, T&gt;</p>\n");
     }
+
+    #[test]
+    fn test_intra_doc_links() {
+        fn t(input: &str, expect: &str) {
+            let links = [("Foo::bar".to_string(), "struct.Foo.html#method.bar".to_string()),
+                         ("`Baz`".to_string(), "enum.Baz.html".to_string())];
+            let output = format!("{}", Markdown(input, &links));
+            assert_eq!(output, expect);
+            reset_ids(true);
+        }
+
+        t("see [bar](Foo::bar)",
+          "<p>see <a href=\"struct.Foo.html#method.bar\">bar</a></p>\n");
+        t("see [`Baz`]", "<p>see <a href=\"enum.Baz.html\"><code>Baz</code></a></p>\n");
+        t("see [Qux](Qux)", "<p>see <a href=\"Qux\">Qux</a></p>\n");
+        t("see `[bar](Foo::bar)`", "<p>see <code>[bar](Foo::bar)</code></p>\n");
+    }
+
+    #[test]
+    fn test_resolve_links_skips_code() {
+        let links = [("Foo::bar".to_string(), "struct.Foo.html#method.bar".to_string())];
+        let defs = "\n\n[Foo::bar]: struct.Foo.html#method.bar";
+        let t = |input: &str, expect: &str| {
+            assert_eq!(resolve_links(input, &links), format!("{}{}", expect, defs));
+        };
+
+        t("[a](Foo::bar) `[b](Foo::bar)` [c](Foo::bar)",
+          "[a](struct.Foo.html#method.bar) `[b](Foo::bar)` [c](struct.Foo.html#method.bar)");
+        t("``a ` [b](Foo::bar)`` [c](Foo::bar)",
+          "``a ` [b](Foo::bar)`` [c](struct.Foo.html#method.bar)");
+        t("```\n[a](Foo::bar)\n```\n[b](Foo::bar)",
+          "```\n[a](Foo::bar)\n```\n[b](struct.Foo.html#method.bar)");
+        t("text\n\n    [a](Foo::bar)\n\n[b](Foo::bar)",
+          "text\n\n    [a](Foo::bar)\n\n[b](struct.Foo.html#method.bar)");
+    }
+
+    #[test]
+    fn test_code_block_lines() {
+        assert_eq!(code_block_lines("a\n```\nb\n```\nc"), [false, true, true, true, false]);
+        assert_eq!(code_block_lines("a\n    b\n\n    c\n    d"),
+                   [false, false, false, true, true]);
+    }
 }
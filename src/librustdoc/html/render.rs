@@ -63,6 +63,7 @@ use rustc::util::nodemap::{FxHashMap, FxHashSet};
 use rustc_data_structures::flock;
 
 use clean::{self, AttributesExt, GetDefId, SelfTy, Mutability};
+use clean::span_map::{LinkKind, Location, SpanMap};
 use doctree;
 use fold::DocFolder;
 use html::escape::Escape;
@@ -72,7 +73,7 @@ use html::format::{VisSpace, Method, UnsafetySpace, MutableSpace};
 use html::format::fmt_impl_for_trait_page;
 use html::item_type::ItemType;
 use html::markdown::{self, Markdown, MarkdownHtml};
-use html::{highlight, layout};
+use html::{highlight, layout, theme};
 
 /// A pair of name and its optional document.
 pub type NameDoc = (String, Option<String>);
@@ -120,6 +121,11 @@ pub struct SharedContext {
     /// The given user css file which allow to customize the generated
     /// documentation theme.
     pub css_file_extension: Option<PathBuf>,
+    /// The user's own themes, which are offered alongside the built-in ones.
+    pub themes: Vec<PathBuf>,
+    /// Whether to keep this crate's part of the search index and other shared
+    /// files to itself, for a later `--merge`, rather than updating them.
+    pub emit_parts: bool,
 }
 
 /// Indicates where an external crate can be found.
@@ -256,6 +262,7 @@ pub struct Cache {
     stack: Vec<String>,
     parent_stack: Vec<DefId>,
     parent_is_trait_impl: bool,
+    impl_self_type: Option<clean::Type>,
     search_index: Vec<IndexItem>,
     stripped_mod: bool,
     deref_trait_did: Option<DefId>,
@@ -266,7 +273,7 @@ pub struct Cache {
     // then the fully qualified name of the structure isn't presented in `paths`
     // yet when its implementation methods are being indexed. Caches such methods
     // and their parent id here and indexes them at the end of crate parsing.
-    orphan_impl_items: Vec<(DefId, clean::Item)>,
+    orphan_impl_items: Vec<(DefId, clean::Item, Option<IndexItemFunctionType>)>,
 }
 
 /// Temporary storage for data obtained during `RustdocVisitor::clean()`.
@@ -278,19 +285,44 @@ pub struct RenderInfo {
     pub external_typarams: FxHashMap<DefId, String>,
     pub deref_trait_did: Option<DefId>,
     pub deref_mut_trait_did: Option<DefId>,
+    pub span_map: SpanMap,
+}
+
+/// Helper struct to find the source files the crate's items are defined in
+struct SourceCollector {
+    /// The files found so far, in the order they were found
+    sources: Vec<PathBuf>,
+    seen: FxHashSet<PathBuf>,
 }
 
 /// Helper struct to render all source code to HTML pages
-struct SourceCollector<'a> {
+struct SourceRenderer<'a> {
     scx: &'a mut SharedContext,
 
     /// Root destination to place all HTML output into
     dst: PathBuf,
+
+    krate: String,
+
+    /// The identifiers in the source files and what they link to
+    span_map: &'a SpanMap,
+
+    /// Where the page for each source file goes, relative to `dst`
+    hrefs: FxHashMap<String, String>,
 }
 
 /// Wrapper struct to render the source code of a file. This will do things like
-/// adding line numbers to the left-hand side.
-struct Source<'a>(&'a str);
+/// adding line numbers to the left-hand side and linking identifiers, given as
+/// byte ranges into the source, to the given URLs.
+struct Source<'a>(&'a str, &'a [(usize, usize, String)]);
+
+/// Wrapper struct to render the list of places each item defined in a crate's
+/// source is used.
+struct References<'a> {
+    definitions: Vec<(DefId, &'a Location)>,
+    references: &'a FxHashMap<DefId, Vec<Location>>,
+    hrefs: &'a FxHashMap<String, String>,
+}
 
 // Helper structs for rendering items/sidebars and carrying along contextual
 // information
@@ -331,9 +363,14 @@ impl ToJson for IndexItem {
     }
 }
 
-/// A type used for the search index.
+/// A type used for the search index. Names are lowercased and references
+/// and pointers are dropped, so `&mut Vec<T>` is indexed as `vec<t>`.
+#[derive(Clone)]
 struct Type {
     name: Option<String>,
+    generics: Vec<Type>,
+    /// Whether this is a type parameter, which any type can be passed for.
+    is_generic: bool,
 }
 
 impl ToJson for Type {
@@ -342,6 +379,17 @@ impl ToJson for Type {
             Some(ref name) => {
                 let mut data = BTreeMap::new();
                 data.insert("name".to_owned(), name.to_json());
+                // Leave out whatever isn't needed to keep the index small.
+                let generics = self.generics.iter()
+                                            .filter(|g| g.name.is_some())
+                                            .map(|g| g.to_json())
+                                            .collect::<Vec<_>>();
+                if !generics.is_empty() {
+                    data.insert("generics".to_owned(), Json::Array(generics));
+                }
+                if self.is_generic {
+                    data.insert("generic".to_owned(), Json::Boolean(true));
+                }
                 Json::Object(data)
             },
             None => Json::Null
@@ -350,6 +398,7 @@ impl ToJson for Type {
 }
 
 /// Full type of functions/methods in the search index.
+#[derive(Clone)]
 struct IndexItemFunctionType {
     inputs: Vec<Type>,
     output: Option<Type>
@@ -393,6 +442,11 @@ fn init_ids() -> FxHashMap<String, usize> {
      "methods",
      "deref-methods",
      "implementations",
+     "implementations-list",
+     "synthetic-implementations",
+     "synthetic-implementations-list",
+     "blanket-implementations",
+     "blanket-implementations-list",
      ].into_iter().map(|id| (String::from(*id), 1)).collect()
 }
 
@@ -432,6 +486,9 @@ pub fn run(mut krate: clean::Crate,
            dst: PathBuf,
            passes: FxHashSet<String>,
            css_file_extension: Option<PathBuf>,
+           themes: Vec<PathBuf>,
+           emit_parts: bool,
+           local_docs: Vec<String>,
            renderinfo: RenderInfo) -> Result<(), Error> {
     let src_root = match krate.src.parent() {
         Some(p) => p.to_path_buf(),
@@ -450,6 +507,8 @@ pub fn run(mut krate: clean::Crate,
             krate: krate.name.clone(),
         },
         css_file_extension: css_file_extension.clone(),
+        themes: themes,
+        emit_parts: emit_parts,
     };
 
     // If user passed in `--playground-url` arg, we fill in crate name here
@@ -488,12 +547,15 @@ pub fn run(mut krate: clean::Crate,
         }
     }
     try_err!(mkdir(&dst), &dst);
-    krate = render_sources(&dst, &mut scx, krate)?;
-    let cx = Context {
-        current: Vec::new(),
-        dst: dst,
-        render_redirect_pages: false,
-        shared: Arc::new(scx),
+    let sources = if scx.include_sources {
+        let mut collector = SourceCollector {
+            sources: Vec::new(),
+            seen: FxHashSet(),
+        };
+        krate = collector.fold_crate(krate);
+        collector.sources
+    } else {
+        Vec::new()
     };
 
     // Crawl the crate to build various caches used for the output
@@ -503,6 +565,7 @@ pub fn run(mut krate: clean::Crate,
         external_typarams,
         deref_trait_did,
         deref_mut_trait_did,
+        span_map,
     } = renderinfo;
 
     let external_paths = external_paths.into_iter()
@@ -518,6 +581,7 @@ pub fn run(mut krate: clean::Crate,
         parent_stack: Vec::new(),
         search_index: Vec::new(),
         parent_is_trait_impl: false,
+        impl_self_type: None,
         extern_locations: FxHashMap(),
         primitive_locations: FxHashMap(),
         stripped_mod: false,
@@ -536,7 +600,7 @@ pub fn run(mut krate: clean::Crate,
             None => PathBuf::new(),
         };
         cache.extern_locations.insert(n, (e.name.clone(), src_root,
-                                          extern_location(e, &cx.dst)));
+                                          extern_location(e, &dst, &local_docs)));
 
         let did = DefId { krate: n, index: CRATE_DEF_INDEX };
         cache.external_paths.insert(did, (vec![e.name.to_string()], ItemType::Module));
@@ -567,6 +631,16 @@ pub fn run(mut krate: clean::Crate,
     CACHE_KEY.with(|v| *v.borrow_mut() = cache.clone());
     CURRENT_LOCATION_KEY.with(|s| s.borrow_mut().clear());
 
+    // Source pages link to the docs of the items used in them, so they can
+    // only be rendered once the cache is in place.
+    render_sources(&dst, &mut scx, &krate.name, sources, &span_map)?;
+    let cx = Context {
+        current: Vec::new(),
+        dst: dst,
+        render_redirect_pages: false,
+        shared: Arc::new(scx),
+    };
+
     write_shared(&cx, &krate, &*cache, index)?;
 
     // And finally render the whole crate's documentation
@@ -585,7 +659,7 @@ fn build_index(krate: &clean::Crate, cache: &mut Cache) -> String {
 
     // Attach all orphan items to the type's definition if the type
     // has since been learned.
-    for &(did, ref item) in orphan_impl_items {
+    for &(did, ref item, ref search_type) in orphan_impl_items {
         if let Some(&(ref fqp, _)) = paths.get(&did) {
             search_index.push(IndexItem {
                 ty: item.type_(),
@@ -594,7 +668,7 @@ fn build_index(krate: &clean::Crate, cache: &mut Cache) -> String {
                 desc: plain_summary_line(item.doc_value()),
                 parent: Some(did),
                 parent_idx: None,
-                search_type: get_index_search_type(&item),
+                search_type: search_type.clone(),
             });
         }
     }
@@ -653,17 +727,13 @@ fn write_shared(cx: &Context,
     try_err!(mkdir(&cx.dst), &cx.dst);
     let _lock = flock::Lock::panicking_new(&cx.dst.join(".lock"), true, true, true);
 
-    // Add all the static files. These may already exist, but we just
-    // overwrite them anyway to make sure that they're fresh and up-to-date.
-
-    write(cx.dst.join("jquery.js"),
-          include_bytes!("static/jquery-2.1.4.min.js"))?;
-    write(cx.dst.join("main.js"),
-          include_bytes!("static/main.js"))?;
-    write(cx.dst.join("rustdoc.css"),
-          include_bytes!("static/rustdoc.css"))?;
-    write(cx.dst.join("main.css"),
-          include_bytes!("static/styles/main.css"))?;
+    let mut themes = Vec::new();
+    for path in &cx.shared.themes {
+        let mut content = Vec::new();
+        try_err!(File::open(path).and_then(|mut f| f.read_to_end(&mut content)), path);
+        themes.push((path.file_stem().unwrap().to_string_lossy().into_owned(), content));
+    }
+    write_static_files(&cx.dst, &themes)?;
     if let Some(ref css) = cx.shared.css_file_extension {
         let mut content = String::new();
         let css = css.as_path();
@@ -675,37 +745,122 @@ fn write_shared(cx: &Context,
         let mut f = try_err!(File::create(css), css);
         try_err!(write!(f, "{}", &content), css);
     }
-    write(cx.dst.join("normalize.css"),
+
+    // Work out our line in the list of implementors of each trait, which is
+    // kept next to the trait's docs.
+    let mut implementors = Vec::new();
+    for (&did, imps) in &cache.implementors {
+        // Private modules can leak through to this phase of rustdoc, which
+        // could contain implementations for otherwise private types. In some
+        // rare cases we could find an implementation for an item which wasn't
+        // indexed, so we just skip this step in that case.
+        //
+        // FIXME: this is a vague explanation for why this can't be a `get`, in
+        //        theory it should be...
+        let &(ref remote_path, remote_item_type) = match cache.paths.get(&did) {
+            Some(p) => p,
+            None => match cache.external_paths.get(&did) {
+                Some(p) => p,
+                None => continue,
+            }
+        };
+
+        let mut line = format!(r#"implementors["{}"] = ["#, krate.name);
+        for imp in imps {
+            // If the trait and implementation are in the same crate, then
+            // there's no need to emit information about it (there's inlining
+            // going on). If they're in different crates then the crate defining
+            // the trait will be interested in our implementation.
+            if imp.def_id.krate == did.krate { continue }
+            write!(line, r#""{}","#, imp.impl_).unwrap();
+        }
+        line.push_str("];");
+
+        let mut path = PathBuf::new();
+        for part in &remote_path[..remote_path.len() - 1] {
+            path.push(part);
+        }
+        path.push(&format!("{}.{}.js",
+                           remote_item_type.css_class(),
+                           remote_path[remote_path.len() - 1]));
+        implementors.push((path, line));
+    }
+
+    if cx.shared.emit_parts {
+        // Leave the shared files alone, so that crates documented at the same
+        // time don't take turns rewriting them, and keep our part of them for
+        // `--merge` to put together.
+        write_parts(&cx.dst.join("parts").join(&krate.name), &search_index, &implementors,
+                    &themes)
+    } else {
+        update_shared(&cx.dst, &krate.name, search_index, implementors)
+    }
+}
+
+/// Writes the files which are the same for every crate, along with the
+/// built-in themes, the given ones and the theme picker listing them all.
+fn write_static_files(dst: &Path, themes: &[(String, Vec<u8>)]) -> Result<(), Error> {
+    // Add all the static files. These may already exist, but we just
+    // overwrite them anyway to make sure that they're fresh and up-to-date.
+
+    write(dst.join("jquery.js"),
+          include_bytes!("static/jquery-2.1.4.min.js"))?;
+    write(dst.join("main.js"),
+          include_bytes!("static/main.js"))?;
+    write(dst.join("rustdoc.css"),
+          include_bytes!("static/rustdoc.css"))?;
+    write(dst.join("storage.js"),
+          include_bytes!("static/storage.js"))?;
+    write(dst.join("contrast.svg"),
+          include_bytes!("static/contrast.svg"))?;
+    write(dst.join("normalize.css"),
           include_bytes!("static/normalize.css"))?;
-    write(cx.dst.join("FiraSans-Regular.woff"),
+    write(dst.join("FiraSans-Regular.woff"),
           include_bytes!("static/FiraSans-Regular.woff"))?;
-    write(cx.dst.join("FiraSans-Medium.woff"),
+    write(dst.join("FiraSans-Medium.woff"),
           include_bytes!("static/FiraSans-Medium.woff"))?;
-    write(cx.dst.join("FiraSans-LICENSE.txt"),
+    write(dst.join("FiraSans-LICENSE.txt"),
           include_bytes!("static/FiraSans-LICENSE.txt"))?;
-    write(cx.dst.join("Heuristica-Italic.woff"),
+    write(dst.join("Heuristica-Italic.woff"),
           include_bytes!("static/Heuristica-Italic.woff"))?;
-    write(cx.dst.join("Heuristica-LICENSE.txt"),
+    write(dst.join("Heuristica-LICENSE.txt"),
           include_bytes!("static/Heuristica-LICENSE.txt"))?;
-    write(cx.dst.join("SourceSerifPro-Regular.woff"),
+    write(dst.join("SourceSerifPro-Regular.woff"),
           include_bytes!("static/SourceSerifPro-Regular.woff"))?;
-    write(cx.dst.join("SourceSerifPro-Bold.woff"),
+    write(dst.join("SourceSerifPro-Bold.woff"),
           include_bytes!("static/SourceSerifPro-Bold.woff"))?;
-    write(cx.dst.join("SourceSerifPro-LICENSE.txt"),
+    write(dst.join("SourceSerifPro-LICENSE.txt"),
           include_bytes!("static/SourceSerifPro-LICENSE.txt"))?;
-    write(cx.dst.join("SourceCodePro-Regular.woff"),
+    write(dst.join("SourceCodePro-Regular.woff"),
           include_bytes!("static/SourceCodePro-Regular.woff"))?;
-    write(cx.dst.join("SourceCodePro-Semibold.woff"),
+    write(dst.join("SourceCodePro-Semibold.woff"),
           include_bytes!("static/SourceCodePro-Semibold.woff"))?;
-    write(cx.dst.join("SourceCodePro-LICENSE.txt"),
+    write(dst.join("SourceCodePro-LICENSE.txt"),
           include_bytes!("static/SourceCodePro-LICENSE.txt"))?;
-    write(cx.dst.join("LICENSE-MIT.txt"),
+    write(dst.join("LICENSE-MIT.txt"),
           include_bytes!("static/LICENSE-MIT.txt"))?;
-    write(cx.dst.join("LICENSE-APACHE.txt"),
+    write(dst.join("LICENSE-APACHE.txt"),
           include_bytes!("static/LICENSE-APACHE.txt"))?;
-    write(cx.dst.join("COPYRIGHT.txt"),
+    write(dst.join("COPYRIGHT.txt"),
           include_bytes!("static/COPYRIGHT.txt"))?;
+    let mut names = Vec::new();
+    for &(name, contents) in theme::THEMES {
+        write(dst.join(format!("{}.css", name)), contents)?;
+        names.push(name.to_string());
+    }
+    for &(ref name, ref contents) in themes {
+        write(dst.join(format!("{}.css", name)), contents)?;
+        names.push(name.clone());
+    }
+    names.sort();
+    write(dst.join("theme.js"), theme_js(&names).as_bytes())?;
+    Ok(())
+}
 
+/// Puts our line in the search index and our implementors into the shared
+/// files, replacing the ones from any earlier run.
+fn update_shared(dst: &Path, krate: &str, search_index: String,
+                 implementors: Vec<(PathBuf, String)>) -> Result<(), Error> {
     fn collect(path: &Path, krate: &str,
                key: &str) -> io::Result<Vec<String>> {
         let mut ret = Vec::new();
@@ -725,92 +880,281 @@ fn write_shared(cx: &Context,
     }
 
     // Update the search index
-    let dst = cx.dst.join("search-index.js");
-    let mut all_indexes = try_err!(collect(&dst, &krate.name, "searchIndex"), &dst);
+    let path = dst.join("search-index.js");
+    let mut all_indexes = try_err!(collect(&path, krate, "searchIndex"), &path);
     all_indexes.push(search_index);
+    write_search_index(&path, all_indexes)?;
+
+    // Update the list of all implementors for traits
+    for (path, line) in implementors {
+        let path = dst.join("implementors").join(path);
+        let mut all_implementors = try_err!(collect(&path, krate, "implementors"), &path);
+        all_implementors.push(line);
+        write_implementors(&path, all_implementors)?;
+    }
+    Ok(())
+}
+
+fn write_search_index(path: &Path, mut indexes: Vec<String>) -> Result<(), Error> {
     // Sort the indexes by crate so the file will be generated identically even
     // with rustdoc running in parallel.
-    all_indexes.sort();
-    let mut w = try_err!(File::create(&dst), &dst);
-    try_err!(writeln!(&mut w, "var searchIndex = {{}};"), &dst);
-    for index in &all_indexes {
-        try_err!(writeln!(&mut w, "{}", *index), &dst);
+    indexes.sort();
+    let mut w = try_err!(File::create(path), path);
+    try_err!(writeln!(&mut w, "var searchIndex = {{}};"), path);
+    for index in &indexes {
+        try_err!(writeln!(&mut w, "{}", *index), path);
     }
-    try_err!(writeln!(&mut w, "initSearch(searchIndex);"), &dst);
+    try_err!(writeln!(&mut w, "initSearch(searchIndex);"), path);
+    Ok(())
+}
 
-    // Update the list of all implementors for traits
-    let dst = cx.dst.join("implementors");
-    for (&did, imps) in &cache.implementors {
-        // Private modules can leak through to this phase of rustdoc, which
-        // could contain implementations for otherwise private types. In some
-        // rare cases we could find an implementation for an item which wasn't
-        // indexed, so we just skip this step in that case.
-        //
-        // FIXME: this is a vague explanation for why this can't be a `get`, in
-        //        theory it should be...
-        let &(ref remote_path, remote_item_type) = match cache.paths.get(&did) {
-            Some(p) => p,
-            None => match cache.external_paths.get(&did) {
-                Some(p) => p,
-                None => continue,
+fn write_implementors(path: &Path, mut implementors: Vec<String>) -> Result<(), Error> {
+    // Sort the implementors by crate so the file will be generated
+    // identically even with rustdoc running in parallel.
+    implementors.sort();
+    let dir = path.parent().unwrap();
+    try_err!(fs::create_dir_all(dir), dir);
+    let mut f = try_err!(File::create(path), path);
+    try_err!(writeln!(&mut f, "(function() {{var implementors = {{}};"), path);
+    for implementor in &implementors {
+        try_err!(writeln!(&mut f, "{}", *implementor), path);
+    }
+    try_err!(writeln!(&mut f, "{}", r"
+            if (window.register_implementors) {
+                window.register_implementors(implementors);
+            } else {
+                window.pending_implementors = implementors;
             }
-        };
+        "), path);
+    try_err!(writeln!(&mut f, r"}})()"), path);
+    Ok(())
+}
 
-        let mut implementors = format!(r#"implementors["{}"] = ["#, krate.name);
-        for imp in imps {
-            // If the trait and implementation are in the same crate, then
-            // there's no need to emit information about it (there's inlining
-            // going on). If they're in different crates then the crate defining
-            // the trait will be interested in our implementation.
-            if imp.def_id.krate == did.krate { continue }
-            write!(implementors, r#""{}","#, imp.impl_).unwrap();
+/// Writes a crate's part of the shared files to `dst`: its line in the search
+/// index and in the implementors lists, and the themes it was documented with.
+fn write_parts(dst: &Path, search_index: &str, implementors: &[(PathBuf, String)],
+               themes: &[(String, Vec<u8>)]) -> Result<(), Error> {
+    // Whatever was left from an earlier run could be out of date.
+    if dst.exists() {
+        try_err!(fs::remove_dir_all(dst), dst);
+    }
+    try_err!(fs::create_dir_all(dst), dst);
+    write(dst.join("search-index.js"), format!("{}\n", search_index).as_bytes())?;
+    for &(ref path, ref line) in implementors {
+        let path = dst.join("implementors").join(path);
+        let dir = path.parent().unwrap();
+        try_err!(fs::create_dir_all(dir), dir);
+        write(path, format!("{}\n", line).as_bytes())?;
+    }
+    if !themes.is_empty() {
+        let dir = dst.join("themes");
+        try_err!(mkdir(&dir), &dir);
+        for &(ref name, ref contents) in themes {
+            write(dir.join(format!("{}.css", name)), contents)?;
         }
-        implementors.push_str("];");
+    }
+    Ok(())
+}
 
-        let mut mydst = dst.clone();
-        for part in &remote_path[..remote_path.len() - 1] {
-            mydst.push(part);
+/// Combines the docs in `inputs`, each of which was generated with
+/// `--emit-parts`, into one set of docs in `dst` whose search index,
+/// implementors lists and themes cover all of their crates.
+///
+/// Only the parts of the inputs are read, so it doesn't matter how many
+/// rustdocs wrote to them at once, and `dst` can be one of the inputs.
+pub fn merge(inputs: &[PathBuf], dst: &Path) -> Result<(), Error> {
+    try_err!(fs::create_dir_all(dst), dst);
+    let _lock = flock::Lock::panicking_new(&dst.join(".lock"), true, true, true);
+    let canonical_dst = try_err!(fs::canonicalize(dst), dst);
+
+    let mut krates = BTreeMap::new();
+    let mut search_indexes = Vec::new();
+    let mut implementors = BTreeMap::new();
+    let mut themes = BTreeMap::new();
+    for input in inputs {
+        let parts = input.join("parts");
+        let mut names = Vec::new();
+        if parts.is_dir() {
+            for entry in try_err!(fs::read_dir(&parts), &parts) {
+                let entry = try_err!(entry, &parts);
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        if names.is_empty() {
+            return Err(other_error("no crates were documented here with `--emit-parts`",
+                                   input));
         }
-        try_err!(fs::create_dir_all(&mydst), &mydst);
-        mydst.push(&format!("{}.{}.js",
-                            remote_item_type.css_class(),
-                            remote_path[remote_path.len() - 1]));
+        names.sort();
+        let copy = try_err!(fs::canonicalize(input), input) != canonical_dst;
 
-        let mut all_implementors = try_err!(collect(&mydst, &krate.name, "implementors"), &mydst);
-        all_implementors.push(implementors);
-        // Sort the implementors by crate so the file will be generated
-        // identically even with rustdoc running in parallel.
-        all_implementors.sort();
+        for name in names {
+            if let Some(other) = krates.insert(name.clone(), input.clone()) {
+                let msg = format!("crate `{}` is also in `{}`", name, other.display());
+                return Err(other_error(&msg, input));
+            }
+            let part = parts.join(&name);
+
+            let path = part.join("search-index.js");
+            search_indexes.extend(try_err!(read_lines(&path), &path));
+            let root = part.join("implementors");
+            for path in try_err!(files_in(&root), &root) {
+                let lines = try_err!(read_lines(&path), &path);
+                let key = path.strip_prefix(&root).unwrap().to_path_buf();
+                implementors.entry(key).or_insert(Vec::new()).extend(lines);
+            }
+            let root = part.join("themes");
+            for path in try_err!(files_in(&root), &root) {
+                let mut content = Vec::new();
+                try_err!(File::open(&path).and_then(|mut f| f.read_to_end(&mut content)), &path);
+                let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+                themes.insert(name, content);
+            }
 
-        let mut f = try_err!(File::create(&mydst), &mydst);
-        try_err!(writeln!(&mut f, "(function() {{var implementors = {{}};"), &mydst);
-        for implementor in &all_implementors {
-            try_err!(writeln!(&mut f, "{}", *implementor), &mydst);
+            if copy {
+                copy_dir(&input.join(&name), &dst.join(&name))?;
+                let src = input.join("src").join(&name);
+                if src.is_dir() {
+                    copy_dir(&src, &dst.join("src").join(&name))?;
+                }
+                // The result can be merged again, too.
+                copy_dir(&part, &dst.join("parts").join(&name))?;
+            }
         }
-        try_err!(writeln!(&mut f, "{}", r"
-            if (window.register_implementors) {
-                window.register_implementors(implementors);
+
+        let css = input.join("theme.css");
+        if copy && css.is_file() {
+            try_err!(fs::copy(&css, dst.join("theme.css")), &css);
+        }
+    }
+
+    write_static_files(dst, &themes.into_iter().collect::<Vec<_>>())?;
+    write_search_index(&dst.join("search-index.js"), search_indexes)?;
+    for (path, lines) in implementors {
+        write_implementors(&dst.join("implementors").join(path), lines)?;
+    }
+    Ok(())
+}
+
+fn other_error(msg: &str, file: &Path) -> Error {
+    Error::new(io::Error::new(io::ErrorKind::Other, msg), file)
+}
+
+fn read_lines(path: &Path) -> io::Result<Vec<String>> {
+    BufReader::new(File::open(path)?).lines().collect()
+}
+
+/// All the files in `dir` and its subdirectories, if it exists.
+fn files_in(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if dir.is_dir() {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                files.extend(files_in(&path)?);
             } else {
-                window.pending_implementors = implementors;
+                files.push(path);
             }
-        "), &mydst);
-        try_err!(writeln!(&mut f, r"}})()"), &mydst);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Copies the directory `from` and everything in it to `to`.
+fn copy_dir(from: &Path, to: &Path) -> Result<(), Error> {
+    try_err!(fs::create_dir_all(to), to);
+    for entry in try_err!(fs::read_dir(from), from) {
+        let path = try_err!(entry, from).path();
+        let dst = to.join(path.file_name().unwrap());
+        if path.is_dir() {
+            copy_dir(&path, &dst)?;
+        } else {
+            try_err!(fs::copy(&path, &dst), &path);
+        }
     }
     Ok(())
 }
 
-fn render_sources(dst: &Path, scx: &mut SharedContext,
-                  krate: clean::Crate) -> Result<clean::Crate, Error> {
+/// The script which fills in the theme picker with the given themes.
+fn theme_js(themes: &[String]) -> String {
+    let themes = themes.iter().map(|t| as_json(t).to_string()).collect::<Vec<_>>();
+    format!(r#"var themes = document.getElementById("theme-choices");
+var themePicker = document.getElementById("theme-picker");
+var themeNames = [{themes}];
+
+if (themeNames.indexOf(getCurrentValue("rustdoc-theme")) === -1) {{
+    switchTheme(currentTheme, mainTheme, "{default}");
+}}
+
+themePicker.onclick = function(e) {{
+    themes.style.display = themes.style.display === "block" ? "none" : "block";
+    e.stopPropagation();
+}};
+document.addEventListener("click", function() {{
+    themes.style.display = "none";
+}});
+
+themeNames.forEach(function(name) {{
+    var button = document.createElement("button");
+    button.textContent = name;
+    button.onclick = function() {{
+        switchTheme(currentTheme, mainTheme, name);
+    }};
+    themes.appendChild(button);
+}});
+"#, themes = themes.join(","), default = theme::DEFAULT_THEME)
+}
+
+fn render_sources(dst: &Path, scx: &mut SharedContext, krate: &str,
+                  sources: Vec<PathBuf>, span_map: &SpanMap) -> Result<(), Error> {
     info!("emitting source files");
     let dst = dst.join("src");
     try_err!(mkdir(&dst), &dst);
-    let dst = dst.join(&krate.name);
+    let dst = dst.join(krate);
     try_err!(mkdir(&dst), &dst);
-    let mut folder = SourceCollector {
-        dst: dst,
+    let hrefs = sources.iter().map(|p| {
+        (p.to_string_lossy().into_owned(), source_href(&scx.src_root, p))
+    }).collect();
+    let mut renderer = SourceRenderer {
         scx: scx,
+        dst: dst,
+        krate: krate.to_string(),
+        span_map: span_map,
+        hrefs: hrefs,
     };
-    Ok(folder.fold_crate(krate))
+    for p in &sources {
+        // If it turns out that we couldn't read this file, then we probably
+        // can't read any of the files (generating html output from json or
+        // something like that), so just don't include sources for the
+        // entire crate. The other option is maintaining this mapping on a
+        // per-file basis, but that's probably not worth it...
+        if let Err(e) = renderer.emit_source(p) {
+            println!("warning: source code was requested to be rendered, \
+                      but processing `{}` had an error: {}", p.display(), e);
+            println!("         skipping rendering of source code");
+            renderer.scx.include_sources = false;
+            break;
+        }
+    }
+    if renderer.scx.include_sources {
+        let path = renderer.dst.join("references.html");
+        try_err!(renderer.emit_references(), &path);
+    }
+    CURRENT_LOCATION_KEY.with(|s| s.borrow_mut().clear());
+    Ok(())
+}
+
+/// Where the page for the source file `p` goes, relative to the directory
+/// holding the crate's source pages.
+fn source_href(src_root: &Path, p: &Path) -> String {
+    let mut href = String::new();
+    clean_srcpath(src_root, p, false, |component| {
+        href.push_str(component);
+        href.push('/');
+    });
+    href.push_str(&p.file_name().expect("source has no filename").to_string_lossy());
+    href.push_str(".html");
+    href
 }
 
 /// Writes the entire contents of a string to a destination, not attempting to
@@ -861,11 +1205,13 @@ fn clean_srcpath<F>(src_root: &Path, p: &Path, keep_filename: bool, mut f: F) wh
 }
 
 /// Attempts to find where an external crate is located, given that we're
-/// rendering in to the specified source destination.
-fn extern_location(e: &clean::ExternalCrate, dst: &Path) -> ExternalLocation {
+/// rendering in to the specified source destination, and that the crates in
+/// `local_docs` will be documented there too.
+fn extern_location(e: &clean::ExternalCrate, dst: &Path,
+                   local_docs: &[String]) -> ExternalLocation {
     // See if there's documentation generated into the local directory
     let local_location = dst.join(&e.name);
-    if local_location.is_dir() {
+    if local_location.is_dir() || local_docs.contains(&e.name) {
         return Local;
     }
 
@@ -883,78 +1229,62 @@ fn extern_location(e: &clean::ExternalCrate, dst: &Path) -> ExternalLocation {
     }).next().unwrap_or(Unknown) // Well, at least we tried.
 }
 
-impl<'a> DocFolder for SourceCollector<'a> {
+impl DocFolder for SourceCollector {
     fn fold_item(&mut self, item: clean::Item) -> Option<clean::Item> {
-        // If we're including source files, and we haven't seen this file yet,
-        // then we need to render it out to the filesystem.
-        if self.scx.include_sources
-            // skip all invalid spans
-            && item.source.filename != ""
+        // If we haven't seen this file yet, then it needs to be rendered out
+        // to the filesystem.
+        if item.source.filename != ""
             // skip non-local items
             && item.def_id.is_local()
             // Macros from other libraries get special filenames which we can
             // safely ignore.
             && !(item.source.filename.starts_with("<")
                 && item.source.filename.ends_with("macros>")) {
-
-            // If it turns out that we couldn't read this file, then we probably
-            // can't read any of the files (generating html output from json or
-            // something like that), so just don't include sources for the
-            // entire crate. The other option is maintaining this mapping on a
-            // per-file basis, but that's probably not worth it...
-            self.scx
-                .include_sources = match self.emit_source(&item.source.filename) {
-                Ok(()) => true,
-                Err(e) => {
-                    println!("warning: source code was requested to be rendered, \
-                              but processing `{}` had an error: {}",
-                             item.source.filename, e);
-                    println!("         skipping rendering of source code");
-                    false
-                }
-            };
+            let p = PathBuf::from(&item.source.filename);
+            if self.seen.insert(p.clone()) {
+                self.sources.push(p);
+            }
         }
         self.fold_item_recur(item)
     }
 }
 
-impl<'a> SourceCollector<'a> {
+impl<'a> SourceRenderer<'a> {
     /// Renders the given filename into its corresponding HTML source file.
-    fn emit_source(&mut self, filename: &str) -> io::Result<()> {
-        let p = PathBuf::from(filename);
-        if self.scx.local_sources.contains_key(&p) {
-            // We've already emitted this source
-            return Ok(());
-        }
-
+    fn emit_source(&mut self, p: &Path) -> io::Result<()> {
         let mut contents = Vec::new();
-        File::open(&p).and_then(|mut f| f.read_to_end(&mut contents))?;
+        File::open(p).and_then(|mut f| f.read_to_end(&mut contents))?;
 
         let contents = str::from_utf8(&contents).unwrap();
 
         // Remove the utf-8 BOM if any
-        let contents = if contents.starts_with("\u{feff}") {
-            &contents[3..]
+        let (contents, offset) = if contents.starts_with("\u{feff}") {
+            (&contents[3..], 3)
         } else {
-            contents
+            (contents, 0)
         };
 
         // Create the intermediate directories
         let mut cur = self.dst.clone();
         let mut root_path = String::from("../../");
-        let mut href = String::new();
-        clean_srcpath(&self.scx.src_root, &p, false, |component| {
+        let mut src_path = String::new();
+        let mut location = vec!["src".to_string(), self.krate.clone()];
+        clean_srcpath(&self.scx.src_root, p, false, |component| {
             cur.push(component);
             mkdir(&cur).unwrap();
             root_path.push_str("../");
-            href.push_str(component);
-            href.push('/');
+            src_path.push_str("../");
+            location.push(component.to_string());
         });
         let mut fname = p.file_name().expect("source has no filename")
                          .to_os_string();
         fname.push(".html");
         cur.push(&fname);
-        href.push_str(&fname.to_string_lossy());
+
+        // Links to other crates' docs are relative to the page they're on.
+        CURRENT_LOCATION_KEY.with(|s| *s.borrow_mut() = location);
+        let filename = p.to_string_lossy();
+        let links = self.links(&filename, &src_path, offset);
 
         let mut w = BufWriter::new(File::create(&cur)?);
         let title = format!("{} -- source", cur.file_name().unwrap()
@@ -968,12 +1298,86 @@ impl<'a> SourceCollector<'a> {
             keywords: BASIC_KEYWORDS,
         };
         layout::render(&mut w, &self.scx.layout,
-                       &page, &(""), &Source(contents),
+                       &page, &(""), &Source(contents, &links),
                        self.scx.css_file_extension.is_some())?;
         w.flush()?;
-        self.scx.local_sources.insert(p, href);
+        let href = self.hrefs[&*filename].clone();
+        self.scx.local_sources.insert(p.to_path_buf(), href);
         Ok(())
     }
+
+    /// The URLs the identifiers in `filename` link to, for a page `src_path`
+    /// below the crate's source directory whose contents start `offset`
+    /// bytes into the file.
+    ///
+    /// Uses of an item link to where it's defined, or to its docs if it's
+    /// from another crate, and definitions link to the list of their uses.
+    fn links(&self, filename: &str, src_path: &str,
+             offset: usize) -> Vec<(usize, usize, String)> {
+        let links = match self.span_map.files.get(filename) {
+            Some(links) => links,
+            None => return vec![],
+        };
+        links.iter().filter(|link| link.lo >= offset).filter_map(|link| {
+            let url = match link.kind {
+                LinkKind::Reference(did) if did.is_local() => {
+                    self.span_map.definitions.get(&did).and_then(|def| {
+                        self.hrefs.get(&def.filename).map(|href| {
+                            format!("{}{}#{}", src_path, href, def.line)
+                        })
+                    })
+                }
+                LinkKind::Reference(did) => href(did).map(|(url, ..)| url),
+                LinkKind::Definition(did) => {
+                    if self.is_referenced(did) {
+                        Some(format!("{}references.html#ref.{}", src_path, did.index.as_u32()))
+                    } else {
+                        None
+                    }
+                }
+            };
+            url.map(|url| (link.lo - offset, link.hi - offset, url))
+        }).collect()
+    }
+
+    /// Whether `did` is used anywhere in the source pages being rendered.
+    fn is_referenced(&self, did: DefId) -> bool {
+        self.span_map.references.get(&did).map_or(false, |refs| {
+            refs.iter().any(|r| self.hrefs.contains_key(&r.filename))
+        })
+    }
+
+    /// Renders the page listing where each item defined in the crate's
+    /// source is used.
+    fn emit_references(&self) -> io::Result<()> {
+        let mut definitions = self.span_map.definitions.iter().filter(|&(&did, def)| {
+            self.hrefs.contains_key(&def.filename) && self.is_referenced(did)
+        }).map(|(&did, def)| (did, def)).collect::<Vec<_>>();
+        definitions.sort_by(|&(_, a), &(_, b)| {
+            (&a.filename, a.line, &a.name).cmp(&(&b.filename, b.line, &b.name))
+        });
+
+        let mut w = BufWriter::new(File::create(self.dst.join("references.html"))?);
+        let title = format!("{} -- references", self.krate);
+        let desc = format!("Where the items in the source of the `{}` crate are used.",
+                           self.krate);
+        let page = layout::Page {
+            title: &title,
+            css_class: "references",
+            root_path: "../../",
+            description: &desc,
+            keywords: BASIC_KEYWORDS,
+        };
+        let references = References {
+            definitions: definitions,
+            references: &self.span_map.references,
+            hrefs: &self.hrefs,
+        };
+        layout::render(&mut w, &self.scx.layout,
+                       &page, &(""), &references,
+                       self.scx.css_file_extension.is_some())?;
+        w.flush()
+    }
 }
 
 impl DocFolder for Cache {
@@ -999,9 +1403,12 @@ impl DocFolder for Cache {
             self.traits.entry(item.def_id).or_insert_with(|| t.clone());
         }
 
-        // Collect all the implementors of traits.
+        // Collect all the implementors of traits. Impls rustdoc added to
+        // the pages of the types they apply to aren't implementors in their
+        // own right.
         if let clean::ImplItem(ref i) = item.inner {
-            if let Some(did) = i.trait_.def_id() {
+            let added = i.synthetic || i.blanket_impl.is_some();
+            if let (Some(did), false) = (i.trait_.def_id(), added) {
                 self.implementors.entry(did).or_insert(vec![]).push(Implementor {
                     def_id: item.def_id,
                     stability: item.stability.clone(),
@@ -1067,14 +1474,17 @@ impl DocFolder for Cache {
                             desc: plain_summary_line(item.doc_value()),
                             parent: parent,
                             parent_idx: None,
-                            search_type: get_index_search_type(&item),
+                            search_type: get_index_search_type(&item,
+                                                               self.impl_self_type.as_ref()),
                         });
                     }
                 }
                 (Some(parent), None) if is_inherent_impl_item => {
                     // We have a parent, but we don't know where they're
                     // defined yet. Wait for later to index this item.
-                    self.orphan_impl_items.push((parent, item.clone()));
+                    let search_type = get_index_search_type(&item,
+                                                            self.impl_self_type.as_ref());
+                    self.orphan_impl_items.push((parent, item.clone(), search_type));
                 }
                 _ => {}
             }
@@ -1129,6 +1539,7 @@ impl DocFolder for Cache {
 
         // Maintain the parent stack
         let orig_parent_is_trait_impl = self.parent_is_trait_impl;
+        let orig_impl_self_type = self.impl_self_type.take();
         let parent_pushed = match item.inner {
             clean::TraitItem(..) | clean::EnumItem(..) |
             clean::StructItem(..) | clean::UnionItem(..) => {
@@ -1136,8 +1547,15 @@ impl DocFolder for Cache {
                 self.parent_is_trait_impl = false;
                 true
             }
+            // The items of a blanket impl are indexed along with the impl
+            // itself, not with every copy of it.
+            clean::ImplItem(ref i) if i.blanket_impl.is_some() => {
+                self.parent_is_trait_impl = true;
+                false
+            }
             clean::ImplItem(ref i) => {
                 self.parent_is_trait_impl = i.trait_.is_some();
+                self.impl_self_type = Some(i.for_.clone());
                 match i.for_ {
                     clean::ResolvedPath{ did, .. } => {
                         self.parent_stack.push(did);
@@ -1168,7 +1586,7 @@ impl DocFolder for Cache {
                 // primitive rather than always to a struct/enum.
                 // Note: matching twice to restrict the lifetime of the `i` borrow.
                 let did = if let clean::Item { inner: clean::ImplItem(ref i), .. } = item {
-                    match i.for_ {
+                    match *i.blanket_impl.as_ref().unwrap_or(&i.for_) {
                         clean::ResolvedPath { did, .. } |
                         clean::BorrowedRef {
                             type_: box clean::ResolvedPath { did, .. }, ..
@@ -1199,6 +1617,7 @@ impl DocFolder for Cache {
         if parent_pushed { self.parent_stack.pop().unwrap(); }
         self.stripped_mod = orig_stripped_mod;
         self.parent_is_trait_impl = orig_parent_is_trait_impl;
+        self.impl_self_type = orig_impl_self_type;
         ret
     }
 }
@@ -1549,6 +1968,9 @@ impl<'a> fmt::Display for Item<'a> {
         }
         write!(fmt, "<a class='{}' href=''>{}</a>",
                self.item.type_(), self.item.name.as_ref().unwrap())?;
+        if is_private(self.item) {
+            write!(fmt, "<span class='private' title='private item'>🔒</span>")?;
+        }
 
         write!(fmt, "</span>")?; // in-band
         write!(fmt, "<span class='out-of-band'>")?;
@@ -1635,6 +2057,20 @@ fn plain_summary_line(s: Option<&str>) -> String {
     markdown::plain_summary_line(&line[..])
 }
 
+/// The URLs of the intra-doc links in `item`'s documentation, for the page
+/// being rendered. Links to items without documentation are left out.
+fn item_links(item: &clean::Item) -> Vec<(String, String)> {
+    item.attrs.links.iter().filter_map(|&(ref link, did, ref fragment)| {
+        href(did).map(|(mut url, ..)| {
+            if let Some(ref fragment) = *fragment {
+                url.push('#');
+                url.push_str(fragment);
+            }
+            (link.clone(), url)
+        })
+    }).collect()
+}
+
 fn document(w: &mut fmt::Formatter, cx: &Context, item: &clean::Item) -> fmt::Result {
     document_stability(w, cx, item)?;
     document_full(w, item)?;
@@ -1649,14 +2085,14 @@ fn document_short(w: &mut fmt::Formatter, item: &clean::Item, link: AssocItemLin
         } else {
             format!("{}", &plain_summary_line(Some(s)))
         };
-        write!(w, "<div class='docblock'>{}</div>", Markdown(&markdown))?;
+        write!(w, "<div class='docblock'>{}</div>", Markdown(&markdown, &item_links(item)))?;
     }
     Ok(())
 }
 
 fn document_full(w: &mut fmt::Formatter, item: &clean::Item) -> fmt::Result {
     if let Some(s) = item.doc_value() {
-        write!(w, "<div class='docblock'>{}</div>", Markdown(s))?;
+        write!(w, "<div class='docblock'>{}</div>", Markdown(s, &item_links(item)))?;
     }
     Ok(())
 }
@@ -1812,22 +2248,29 @@ fn item_module(w: &mut fmt::Formatter, cx: &Context,
                         unsafety_flag = "<a title='unsafe function' href='#'><sup>⚠</sup></a>";
                     }
                 }
+                let private_flag = if is_private(myitem) {
+                    "<span class='private' title='private item'>🔒</span>"
+                } else {
+                    ""
+                };
 
                 let doc_value = myitem.doc_value().unwrap_or("");
                 write!(w, "
                        <tr class='{stab} module-item'>
                            <td><a class='{class}' href='{href}'
-                                  title='{title}'>{name}</a>{unsafety_flag}</td>
+                                  title='{title}'>{name}</a>{unsafety_flag}{private_flag}</td>
                            <td class='docblock-short'>
                                {stab_docs} {docs}
                            </td>
                        </tr>",
                        name = *myitem.name.as_ref().unwrap(),
                        stab_docs = stab_docs,
-                       docs = shorter(Some(&Markdown(doc_value).to_string())),
+                       docs = shorter(Some(&Markdown(doc_value,
+                                                     &item_links(myitem)).to_string())),
                        class = myitem.type_(),
                        stab = myitem.stability_class(),
                        unsafety_flag = unsafety_flag,
+                       private_flag = private_flag,
                        href = item_path(myitem.type_(), myitem.name.as_ref().unwrap()),
                        title = full_path(cx, myitem))?;
             }
@@ -1840,6 +2283,12 @@ fn item_module(w: &mut fmt::Formatter, cx: &Context,
     Ok(())
 }
 
+/// Whether `it` was declared without `pub`. Such items only make it this far
+/// when they aren't stripped, e.g. with `--document-private-items`.
+fn is_private(it: &clean::Item) -> bool {
+    it.visibility == Some(clean::Inherited)
+}
+
 fn maybe_ignore_item(it: &clean::Item) -> bool {
     match it.inner {
         clean::StrippedItem(..) => true,
@@ -2776,18 +3225,38 @@ fn render_assoc_items(w: &mut fmt::Formatter,
             }).is_some();
             render_deref_methods(w, cx, impl_, containing_item, has_deref_mut)?;
         }
-        write!(w, "<h2 id='implementations'>Trait \
-                   Implementations</h2>")?;
-        for i in &traits {
-            let did = i.trait_did().unwrap();
-            let assoc_link = AssocItemLink::GotoSource(did, &i.inner_impl().provided_trait_methods);
-            render_impl(w, cx, i, assoc_link,
-                        RenderMode::Normal, containing_item.stable_since())?;
-        }
+
+        let (synthetic, traits): (Vec<_>, Vec<_>) = traits.into_iter().partition(|t| {
+            t.inner_impl().synthetic
+        });
+        let (blanket, traits): (Vec<_>, Vec<_>) = traits.into_iter().partition(|t| {
+            t.inner_impl().blanket_impl.is_some()
+        });
+        render_trait_impls(w, cx, "implementations", "Trait Implementations",
+                           &traits, containing_item)?;
+        render_trait_impls(w, cx, "synthetic-implementations", "Auto Trait Implementations",
+                           &synthetic, containing_item)?;
+        render_trait_impls(w, cx, "blanket-implementations", "Blanket Implementations",
+                           &blanket, containing_item)?;
     }
     Ok(())
 }
 
+fn render_trait_impls(w: &mut fmt::Formatter, cx: &Context, id: &str, title: &str,
+                      traits: &[&Impl], containing_item: &clean::Item) -> fmt::Result {
+    if traits.is_empty() {
+        return Ok(());
+    }
+    write!(w, "<h2 id='{id}'>{title}</h2><div id='{id}-list'>", id = id, title = title)?;
+    for i in traits {
+        let did = i.trait_did().unwrap();
+        let assoc_link = AssocItemLink::GotoSource(did, &i.inner_impl().provided_trait_methods);
+        render_impl(w, cx, i, assoc_link,
+                    RenderMode::Normal, containing_item.stable_since())?;
+    }
+    write!(w, "</div>")
+}
+
 fn render_deref_methods(w: &mut fmt::Formatter, cx: &Context, impl_: &Impl,
                         container_item: &clean::Item, deref_mut: bool) -> fmt::Result {
     let deref_type = impl_.inner_impl().trait_.as_ref().unwrap();
@@ -2817,7 +3286,13 @@ fn render_impl(w: &mut fmt::Formatter, cx: &Context, i: &Impl, link: AssocItemLi
         write!(w, "<h3 class='impl'><span class='in-band'><code>{}</code>", i.inner_impl())?;
         write!(w, "</span><span class='out-of-band'>")?;
         let since = i.impl_item.stability.as_ref().map(|s| &s.since[..]);
-        if let Some(l) = (Item { item: &i.impl_item, cx: cx }).src_href() {
+        // Auto trait impls worked out by rustdoc have no source to link to.
+        let src_href = if i.inner_impl().synthetic {
+            None
+        } else {
+            (Item { item: &i.impl_item, cx: cx }).src_href()
+        };
+        if let Some(l) = src_href {
             write!(w, "<div class='ghost'></div>")?;
             render_stability_since_raw(w, since, outer_version)?;
             write!(w, "<a class='srclink' href='{}' title='{}'>[src]</a>",
@@ -2828,7 +3303,8 @@ fn render_impl(w: &mut fmt::Formatter, cx: &Context, i: &Impl, link: AssocItemLi
         write!(w, "</span>")?;
         write!(w, "</h3>\n")?;
         if let Some(ref dox) = i.impl_item.doc_value() {
-            write!(w, "<div class='docblock'>{}</div>", Markdown(dox))?;
+            write!(w, "<div class='docblock'>{}</div>",
+                   Markdown(dox, &item_links(&i.impl_item)))?;
         }
     }
 
@@ -3045,7 +3521,7 @@ impl<'a> fmt::Display for Sidebar<'a> {
 
 impl<'a> fmt::Display for Source<'a> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let Source(s) = *self;
+        let Source(s, links) = *self;
         let lines = s.lines().count();
         let mut cols = 0;
         let mut tmp = lines;
@@ -3058,11 +3534,41 @@ impl<'a> fmt::Display for Source<'a> {
             write!(fmt, "<span id=\"{0}\">{0:1$}</span>\n", i, cols)?;
         }
         write!(fmt, "</pre>")?;
-        write!(fmt, "{}", highlight::render_with_highlighting(s, None, None, None))?;
+        write!(fmt, "{}", highlight::render_with_links(s, links))?;
         Ok(())
     }
 }
 
+impl<'a> fmt::Display for References<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "<h1 class='fqn'><span class='in-band'>References</span></h1>")?;
+        for &(did, def) in &self.definitions {
+            write!(fmt, "<h3 id='ref.{}' class='reference'><code>{}</code> ",
+                   did.index.as_u32(), Escape(&def.name))?;
+            self.write_location(fmt, def)?;
+            write!(fmt, "</h3><ul class='references'>")?;
+            for r in &self.references[&did] {
+                if !self.hrefs.contains_key(&r.filename) {
+                    continue
+                }
+                write!(fmt, "<li>")?;
+                self.write_location(fmt, r)?;
+                write!(fmt, " <code>{}</code></li>", Escape(&r.snippet))?;
+            }
+            write!(fmt, "</ul>")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> References<'a> {
+    fn write_location(&self, fmt: &mut fmt::Formatter, location: &Location) -> fmt::Result {
+        let href = &self.hrefs[&location.filename];
+        write!(fmt, "<a href='{0}#{2}'>{1}:{2}</a>",
+               href, Escape(&href[..href.len() - ".html".len()]), location.line)
+    }
+}
+
 fn item_macro(w: &mut fmt::Formatter, cx: &Context, it: &clean::Item,
               t: &clean::Macro) -> fmt::Result {
     w.write_str(&highlight::render_with_highlighting(&t.source,
@@ -3085,7 +3591,11 @@ fn make_item_keywords(it: &clean::Item) -> String {
     format!("{}, {}", BASIC_KEYWORDS, it.name.as_ref().unwrap())
 }
 
-fn get_index_search_type(item: &clean::Item) -> Option<IndexItemFunctionType> {
+/// Computes the signature of a function or method for the search index.
+/// `self_type` is the type of the impl the item belongs to, if any, which
+/// stands in for `Self` so that e.g. `Vec::len` is found as `Vec<T> -> usize`.
+fn get_index_search_type(item: &clean::Item,
+                         self_type: Option<&clean::Type>) -> Option<IndexItemFunctionType> {
     let decl = match item.inner {
         clean::FunctionItem(ref f) => &f.decl,
         clean::MethodItem(ref m) => &m.decl,
@@ -3093,30 +3603,81 @@ fn get_index_search_type(item: &clean::Item) -> Option<IndexItemFunctionType> {
         _ => return None
     };
 
-    let inputs = decl.inputs.values.iter().map(|arg| get_index_type(&arg.type_)).collect();
+    let inputs = decl.inputs.values.iter().map(|arg| {
+        get_index_type(&arg.type_, self_type)
+    }).collect();
     let output = match decl.output {
-        clean::FunctionRetTy::Return(ref return_type) => Some(get_index_type(return_type)),
+        clean::FunctionRetTy::Return(ref return_type) => {
+            Some(get_index_type(return_type, self_type))
+        }
         _ => None
     };
 
     Some(IndexItemFunctionType { inputs: inputs, output: output })
 }
 
-fn get_index_type(clean_type: &clean::Type) -> Type {
-    Type { name: get_index_type_name(clean_type).map(|s| s.to_ascii_lowercase()) }
-}
-
-fn get_index_type_name(clean_type: &clean::Type) -> Option<String> {
+fn get_index_type(clean_type: &clean::Type, self_type: Option<&clean::Type>) -> Type {
     match *clean_type {
-        clean::ResolvedPath { ref path, .. } => {
-            let segments = &path.segments;
-            Some(segments[segments.len() - 1].name.clone())
-        },
-        clean::Generic(ref s) => Some(s.clone()),
-        clean::Primitive(ref p) => Some(format!("{:?}", p)),
-        clean::BorrowedRef { ref type_, .. } => get_index_type_name(type_),
+        clean::ResolvedPath { ref path, is_generic, .. } => {
+            let segment = &path.segments[path.segments.len() - 1];
+            let generics = match segment.params {
+                clean::PathParameters::AngleBracketed { ref types, .. } => {
+                    types.iter().map(|t| get_index_type(t, self_type)).collect()
+                }
+                clean::PathParameters::Parenthesized { ref inputs, .. } => {
+                    inputs.iter().map(|t| get_index_type(t, self_type)).collect()
+                }
+            };
+            Type {
+                name: Some(segment.name.to_ascii_lowercase()),
+                generics: generics,
+                is_generic: is_generic,
+            }
+        }
+        clean::Generic(ref s) => {
+            match self_type {
+                Some(self_type) if *s == "Self" => get_index_type(self_type, None),
+                _ => Type {
+                    name: Some(s.to_ascii_lowercase()),
+                    generics: vec![],
+                    is_generic: true,
+                },
+            }
+        }
+        clean::Primitive(ref p) => index_primitive(*p, vec![]),
+        clean::Tuple(ref types) => {
+            let types = types.iter().map(|t| get_index_type(t, self_type)).collect();
+            index_primitive(clean::PrimitiveType::Tuple, types)
+        }
+        clean::Vector(ref t) => {
+            index_primitive(clean::PrimitiveType::Slice, vec![get_index_type(t, self_type)])
+        }
+        clean::FixedVector(ref t, _) => {
+            index_primitive(clean::PrimitiveType::Array, vec![get_index_type(t, self_type)])
+        }
+        clean::BorrowedRef { ref type_, .. } |
+        clean::RawPointer(_, ref type_) |
+        clean::Unique(ref type_) => get_index_type(type_, self_type),
+        clean::ImplTrait(ref bounds) => {
+            let trait_ = bounds.iter().filter_map(|b| match *b {
+                clean::TraitBound(ref poly, _) => Some(&poly.trait_),
+                clean::RegionBound(..) => None,
+            }).next();
+            match trait_ {
+                Some(trait_) => get_index_type(trait_, self_type),
+                None => Type { name: None, generics: vec![], is_generic: false },
+            }
+        }
         // FIXME: add all from clean::Type.
-        _ => None
+        _ => Type { name: None, generics: vec![], is_generic: false },
+    }
+}
+
+fn index_primitive(p: clean::PrimitiveType, generics: Vec<Type>) -> Type {
+    Type {
+        name: Some(format!("{:?}", p).to_ascii_lowercase()),
+        generics: generics,
+        is_generic: false,
     }
 }
 
@@ -218,6 +218,259 @@
             $(".search-input")[0].value = params.search || '';
         }
 
+        /**
+         * The number of typos tolerated when matching `word`, which grows
+         * with its length so that short queries don't match everything.
+         * @param  {string} word [The word searched for]
+         * @return {number}      [The largest Levenshtein distance accepted]
+         */
+        function maxLevDistance(word) {
+            return Math.min(MAX_LEV_DISTANCE, Math.floor((word.length + 1) / 3));
+        }
+
+        /**
+         * Splits `text` at each `sep` which isn't nested inside brackets.
+         * @param  {string} text [The text to split]
+         * @param  {string} sep  [The separator]
+         * @return {[string]}    [The parts]
+         */
+        function splitTopLevel(text, sep) {
+            var parts = [], depth = 0, start = 0;
+            for (var i = 0; i < text.length; ++i) {
+                var c = text.charAt(i);
+                if (c === "<" || c === "(" || c === "[") {
+                    ++depth;
+                } else if ((c === ">" && text.charAt(i - 1) !== "-") ||
+                           c === ")" || c === "]") {
+                    --depth;
+                } else if (depth === 0 && text.substr(i, sep.length) === sep) {
+                    parts.push(text.substring(start, i));
+                    i += sep.length - 1;
+                    start = i + 1;
+                }
+            }
+            parts.push(text.substring(start));
+            return parts;
+        }
+
+        /**
+         * Parses a type written in a query, like `&[u8]` or `Option<char>`,
+         * into the form used by the search index: lowercased names with their
+         * generic arguments, without references or pointers and with only the
+         * last segment of paths. A single capital letter (like `T`), `_` or
+         * `*` stands for any type.
+         * @param  {string} text [The type]
+         * @return {[Object]}    [The type, or null if it can't be parsed]
+         */
+        function parseQueryType(text) {
+            var tokens = text.match(/[A-Za-z_][A-Za-z0-9_]*|::|\S/g) || [];
+            var pos = 0;
+
+            function isIdent(token) {
+                return token !== undefined && /^[A-Za-z_]/.test(token);
+            }
+
+            function parseList(close, list) {
+                while (tokens[pos] !== close) {
+                    var type = parse();
+                    if (type === null) {
+                        return false;
+                    }
+                    list.push(type);
+                    if (tokens[pos] === ",") {
+                        ++pos;
+                    } else if (tokens[pos] !== close) {
+                        return false;
+                    }
+                }
+                ++pos;
+                return true;
+            }
+
+            function parse() {
+                var token = tokens[pos++];
+                if (token === "&") {
+                    if (tokens[pos] === "'") {
+                        pos += 2;
+                    }
+                    if (tokens[pos] === "mut") {
+                        ++pos;
+                    }
+                    return parse();
+                }
+                if (token === "*") {
+                    if (tokens[pos] === "const" || tokens[pos] === "mut") {
+                        ++pos;
+                        return parse();
+                    }
+                    return {name: "*", generics: [], any: true};
+                }
+                if ((token === "dyn" || token === "impl") && isIdent(tokens[pos])) {
+                    return parse();
+                }
+                if (token === "[") {
+                    var elem = parse();
+                    if (elem === null) {
+                        return null;
+                    }
+                    var name = "slice";
+                    if (tokens[pos] === ";") {
+                        name = "array";
+                        while (pos < tokens.length && tokens[pos] !== "]") {
+                            ++pos;
+                        }
+                    }
+                    if (tokens[pos++] !== "]") {
+                        return null;
+                    }
+                    return {name: name, generics: [elem]};
+                }
+                if (token === "(") {
+                    var elems = [];
+                    if (!parseList(")", elems)) {
+                        return null;
+                    }
+                    return {name: "tuple", generics: elems};
+                }
+                if (!isIdent(token)) {
+                    return null;
+                }
+                while (tokens[pos] === "::") {
+                    token = tokens[pos + 1];
+                    if (!isIdent(token)) {
+                        return null;
+                    }
+                    pos += 2;
+                }
+                var type = {
+                    name: token.toLowerCase(),
+                    generics: [],
+                    any: token === "_" || /^[A-Z]$/.test(token),
+                };
+                if (tokens[pos] === "<") {
+                    ++pos;
+                    if (!parseList(">", type.generics)) {
+                        return null;
+                    }
+                }
+                return type;
+            }
+
+            var type = parse();
+            return pos === tokens.length ? type : null;
+        }
+
+        /**
+         * Parses a comma separated list of types from a query. An empty list
+         * or `*` matches anything, which is represented by `[]`.
+         * @param  {string} text [The types]
+         * @return {[Object]}    [The types, or null if they can't be parsed]
+         */
+        function parseQueryTypes(text) {
+            text = text.trim();
+            if (text === "" || text === "*") {
+                return [];
+            }
+            var types = [];
+            var parts = splitTopLevel(text, ",");
+            for (var i = 0; i < parts.length; ++i) {
+                var type = parseQueryType(parts[i]);
+                if (type === null) {
+                    return null;
+                }
+                types.push(type);
+            }
+            return types;
+        }
+
+        /**
+         * Checks how well a type from the search index matches one from the
+         * query. Generic arguments in the query must be found in the same
+         * order among those of the type, which may have others as well.
+         * @param  {Object} query [The type from the query]
+         * @param  {Object} type  [The type from the index]
+         * @return {number}       [The number of typos, or -1 for no match]
+         */
+        function typeDistance(query, type) {
+            if (!type) {
+                return -1;
+            }
+            if (query.any) {
+                return 0;
+            }
+            // anything can be passed for a type parameter, but an exact match
+            // ranks first
+            if (type.generic) {
+                return query.name === type.name ? 0 : 1;
+            }
+            var lev = levenshtein(query.name, type.name);
+            if (lev > maxLevDistance(query.name)) {
+                return -1;
+            }
+            var generics = type.generics || [];
+            var j = 0;
+            for (var i = 0; i < query.generics.length; ++i) {
+                var found = -1;
+                while (found < 0 && j < generics.length) {
+                    found = typeDistance(query.generics[i], generics[j++]);
+                }
+                if (found < 0) {
+                    return -1;
+                }
+                lev += found;
+            }
+            return lev;
+        }
+
+        /**
+         * Checks how well a function's type matches the query. Each type in
+         * `inputs` needs an argument of its own, in any order, and arguments
+         * the query doesn't mention make the match worse.
+         * @param  {[Object]} inputs [The argument types from the query]
+         * @param  {Object}   output [The return type from the query, if any]
+         * @param  {Object}   type   [The function type from the index]
+         * @return {number}          [The distance, or -1 for no match]
+         */
+        function signatureDistance(inputs, output, type) {
+            var used = [];
+            var lev = 0;
+            for (var i = 0; i < inputs.length; ++i) {
+                var best = -1, bestIndex = -1;
+                for (var j = 0; j < type.inputs.length; ++j) {
+                    if (used[j]) {
+                        continue;
+                    }
+                    var dist = typeDistance(inputs[i], type.inputs[j]);
+                    if (dist >= 0 && (best < 0 || dist < best)) {
+                        best = dist;
+                        bestIndex = j;
+                    }
+                }
+                if (best < 0) {
+                    return -1;
+                }
+                used[bestIndex] = true;
+                lev += best;
+            }
+            if (inputs.length > 0) {
+                lev += type.inputs.length - inputs.length;
+            }
+
+            if (output) {
+                // `()` also finds functions without a return type
+                if (output.name === "tuple" && output.generics.length === 0 &&
+                    !type.output) {
+                    return lev;
+                }
+                var dist = typeDistance(output, type.output);
+                if (dist < 0) {
+                    return -1;
+                }
+                lev += dist;
+            }
+            return lev;
+        }
+
         /**
          * Executes the query and builds an index of results
          * @param  {[Object]} query     [The user query]
@@ -282,12 +535,16 @@
                 }
             // searching by type
             } else if (val.search("->") > -1) {
-                var trimmer = function (s) { return s.trim(); };
-                var parts = val.split("->").map(trimmer);
-                var input = parts[0];
-                // sort inputs so that order does not matter
-                var inputs = input.split(",").map(trimmer).sort().toString();
-                var output = parts[1];
+                // parse the original query, as the case of generics matters
+                var parts = splitTopLevel(query.query, "->");
+                var inputs = null, output = null;
+                if (parts.length === 2) {
+                    inputs = parseQueryTypes(parts[0]);
+                    output = parseQueryTypes(parts[1]);
+                }
+                if (inputs === null || output === null || output.length > 1) {
+                    return [];
+                }
 
                 for (var i = 0; i < nSearchWords; ++i) {
                     var type = searchIndex[i].type;
@@ -295,16 +552,9 @@
                         continue;
                     }
 
-                    // sort index inputs so that order does not matter
-                    var typeInputs = type.inputs.map(function (input) {
-                        return input.name;
-                    }).sort();
-
-                    // allow searching for void (no output) functions as well
-                    var typeOutput = type.output ? type.output.name : "";
-                    if ((inputs === "*" || inputs === typeInputs.toString()) &&
-                        (output === "*" || output == typeOutput)) {
-                        results.push({id: i, index: -1, dontValidate: true});
+                    var lev = signatureDistance(inputs, output[0], type);
+                    if (lev >= 0 && typePassesFilter(typeFilter, searchIndex[i].ty)) {
+                        results.push({id: i, index: -1, lev: lev, dontValidate: true});
                     }
                 }
             } else {
@@ -327,7 +577,7 @@
                             }
                         } else if (
                             (lev_distance = levenshtein(searchWords[j], val)) <=
-                                MAX_LEV_DISTANCE) {
+                                maxLevDistance(val)) {
                             if (typePassesFilter(typeFilter, searchIndex[j].ty)) {
                                 results.push({
                                     id: j,
@@ -476,7 +726,7 @@
                         parent.name.toLowerCase().indexOf(keys[i]) > -1) ||
                     // lastly check to see if the name was a levenshtein match
                     levenshtein(name.toLowerCase(), keys[i]) <=
-                        MAX_LEV_DISTANCE)) {
+                        maxLevDistance(keys[i]))) {
                     return false;
                 }
             }
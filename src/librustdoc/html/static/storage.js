@@ -0,0 +1,45 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*jslint browser: true, es5: true */
+
+// This is loaded from the page's <head>, before anything is shown, so that
+// the page is drawn with the chosen theme right away.
+
+var currentTheme = document.getElementById("themeStyle");
+var mainTheme = document.getElementById("mainThemeStyle");
+
+function updateLocalStorage(name, value) {
+    try {
+        window.localStorage.setItem(name, value);
+    } catch (e) {
+        // Storage isn't available, e.g. for pages opened from the disk in
+        // some browsers, so the choice only lasts for this page.
+    }
+}
+
+function getCurrentValue(name) {
+    try {
+        return window.localStorage.getItem(name);
+    } catch (e) {
+        return null;
+    }
+}
+
+// Themes sit next to `rustdoc.css`, so their URL is worked out from its URL.
+function switchTheme(styleElem, mainStyleElem, newTheme) {
+    var newHref = mainStyleElem.href.replace(/rustdoc\.css$/, newTheme + ".css");
+    if (styleElem.href !== newHref) {
+        styleElem.href = newHref;
+    }
+    updateLocalStorage("rustdoc-theme", newTheme);
+}
+
+switchTheme(currentTheme, mainTheme, getCurrentValue("rustdoc-theme") || "light");
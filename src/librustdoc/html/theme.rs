@@ -0,0 +1,151 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checking of user-provided themes.
+//!
+//! The layout of the generated pages lives in `rustdoc.css` and the colors in
+//! a theme. A theme is only complete if it styles everything the default one
+//! does, so themes passed with `--themes` are checked for having a rule for
+//! every selector of the default theme.
+
+use std::collections::BTreeSet;
+
+/// The theme every other one is checked against.
+pub const DEFAULT_THEME: &'static str = "light";
+
+/// The themes built into rustdoc, by name.
+pub const THEMES: &'static [(&'static str, &'static [u8])] = &[
+    ("dark", include_bytes!("static/themes/dark.css")),
+    ("light", include_bytes!("static/themes/light.css")),
+];
+
+/// The selectors of `against` which `theme` has no rule for.
+pub fn missing_selectors(theme: &str, against: &str) -> Vec<String> {
+    let found = selectors(theme);
+    selectors(against).into_iter().filter(|s| !found.contains(s)).collect()
+}
+
+/// The selectors of the rules in `css`, written the same way however they
+/// were formatted. Selectors inside an at-rule such as `@media` are prefixed
+/// with it.
+fn selectors(css: &str) -> BTreeSet<String> {
+    let mut selectors = BTreeSet::new();
+    // The at-rules we're in, and whether we're in a rule's declarations.
+    let mut groups: Vec<String> = vec![];
+    let mut in_rule = false;
+    let mut prelude = String::new();
+    let mut quote = None;
+
+    let mut chars = strip_comments(css).chars().collect::<Vec<_>>().into_iter();
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == q {
+                quote = None;
+            }
+            continue
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '{' if !in_rule => {
+                let name = normalize(&prelude);
+                prelude.clear();
+                if name.starts_with("@media") || name.starts_with("@supports") {
+                    groups.push(name);
+                    continue
+                }
+                in_rule = true;
+                for selector in name.split(',') {
+                    let mut full = groups.join(" ");
+                    if !full.is_empty() {
+                        full.push(' ');
+                    }
+                    full.push_str(&normalize(selector));
+                    selectors.insert(full);
+                }
+            }
+            '}' => {
+                if in_rule {
+                    in_rule = false;
+                } else {
+                    groups.pop();
+                }
+                prelude.clear();
+            }
+            // Statements such as `@import` have no block.
+            ';' if !in_rule => prelude.clear(),
+            _ if !in_rule => prelude.push(c),
+            _ => {}
+        }
+    }
+    selectors
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Collapses the whitespace in `selector`, and removes it around combinators.
+fn normalize(selector: &str) -> String {
+    let mut out = String::new();
+    for word in selector.split_whitespace() {
+        let joins = match (out.chars().next_back(), word.chars().next()) {
+            (None, _) => true,
+            (Some(c), _) if c == '>' || c == '+' || c == '~' => true,
+            (_, Some(c)) if c == '>' || c == '+' || c == '~' => true,
+            _ => false,
+        };
+        if !joins {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    out
+}
+
+#[cfg(test)]
+mod theme_tests {
+    use super::{missing_selectors, selectors, THEMES};
+    use std::str;
+
+    #[test]
+    fn should_normalize_selectors() {
+        let found = selectors("/* a { } */ a  >b,\n.c  .d{color:red}\n\
+                               @media (max-width: 700px) { .e { content: '}' } }");
+        let expected = [".c .d", "@media (max-width: 700px) .e", "a>b"];
+        assert_eq!(found.iter().map(|s| &s[..]).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn should_find_missing_selectors() {
+        assert_eq!(missing_selectors("a > b {} .c {}", ".c, a>b { color: red; }"),
+                   Vec::<String>::new());
+        assert_eq!(missing_selectors(".c {}", ".c {} .d {}"), vec![".d".to_string()]);
+    }
+
+    #[test]
+    fn builtin_themes_should_match() {
+        let light = str::from_utf8(THEMES[1].1).unwrap();
+        let dark = str::from_utf8(THEMES[0].1).unwrap();
+        assert_eq!(missing_selectors(dark, light), Vec::<String>::new());
+        assert_eq!(missing_selectors(light, dark), Vec::<String>::new());
+    }
+}
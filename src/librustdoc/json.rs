@@ -0,0 +1,463 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rustdoc's JSON backend, selected with `--output-format json`.
+//!
+//! The format is unstable, so it is only available with `-Z unstable-options`.
+//!
+//! The documented crate is written to `<output>/<crate name>.json` as a
+//! single `Crate` object. The types in this module are the schema: each one
+//! is written as an object keyed by its field names, `Option`s are `null` or
+//! their value, and `Vec`s are arrays. They are built from the `clean` types
+//! after all passes have run, so changes to `clean` don't leak into the
+//! output unnoticed.
+//!
+//! Types, generics, where-clauses and function declarations are written as
+//! the Rust source text rustdoc would show for them, without any markup.
+//!
+//! `FORMAT_VERSION` is bumped whenever the schema changes, so consumers can
+//! reject documents they don't understand.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use rustc::hir;
+use rustc::hir::def_id::DefId;
+use rustc::middle::stability::StabilityLevel;
+use serialize::json::as_json;
+use syntax::abi::Abi;
+use syntax::print::pprust;
+
+use clean;
+use doctree;
+use html::format::{TyParamBounds, WhereClause};
+use html::item_type::ItemType;
+
+/// The version of the schema below.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The whole document.
+#[derive(RustcEncodable)]
+pub struct Crate {
+    /// The `FORMAT_VERSION` of the rustdoc that wrote the document.
+    pub format_version: u32,
+    /// The name of the documented crate.
+    pub name: String,
+    /// The path of the crate root's source file.
+    pub src: String,
+    /// The crate root module, or `null` if it was stripped.
+    pub root: Option<Item>,
+    /// The crates this one depends on.
+    pub externs: Vec<ExternalCrate>,
+    /// The primitive types documented in this crate.
+    pub primitives: Vec<Primitive>,
+    /// The traits from other crates that items in this crate refer to. These
+    /// are `"trait"` items without a name or span.
+    pub external_traits: Vec<Item>,
+}
+
+/// A crate the documented crate depends on.
+#[derive(RustcEncodable)]
+pub struct ExternalCrate {
+    /// The crate's number, as used in `Id`s.
+    pub krate: u32,
+    /// The crate's name.
+    pub name: String,
+    /// The path of the crate's root source file.
+    pub src: String,
+    /// The crate-level documentation.
+    pub docs: Option<String>,
+}
+
+/// A primitive type documented in the crate, through `#[doc(primitive)]`.
+#[derive(RustcEncodable)]
+pub struct Primitive {
+    /// The module carrying the documentation.
+    pub id: Id,
+    /// The name of the type, e.g. `"u8"` or `"slice"`.
+    pub name: &'static str,
+    /// The documentation.
+    pub docs: Option<String>,
+}
+
+/// Identifies an item within the document, as `"<crate number>:<index>"`.
+/// The documented crate is number 0.
+pub type Id = String;
+
+/// An item: a module, a type, a function, an impl, a field, ...
+///
+/// The fields after `stability` are only set for some kinds of item; each
+/// says for which.
+#[derive(RustcEncodable)]
+pub struct Item {
+    /// The item's `Id`, or `null` for synthetic impls, which don't exist in
+    /// the source.
+    pub id: Option<Id>,
+    /// The name, or `null` for items without one, like impls.
+    pub name: Option<String>,
+    /// The kind of the item, with the names used in rustdoc's URLs: one of
+    /// `"mod"`, `"externcrate"`, `"import"`, `"struct"`, `"union"`,
+    /// `"enum"`, `"fn"`, `"type"`, `"static"`, `"constant"`, `"trait"`,
+    /// `"impl"`, `"tymethod"`, `"method"`, `"structfield"`, `"variant"`,
+    /// `"macro"`, `"primitive"`, `"associatedtype"` and
+    /// `"associatedconstant"`.
+    pub kind: &'static str,
+    /// Whether the item was stripped from the documentation, e.g. because
+    /// it is private. Only its `items` are still documented.
+    pub stripped: bool,
+    /// Where the item is defined, or `null` if that isn't known.
+    pub span: Option<Span>,
+    /// `"public"` for `pub` items, `"inherited"` for private ones and `null`
+    /// for items that take the visibility of their parent, like variants.
+    pub visibility: Option<&'static str>,
+    /// The documentation, as Markdown.
+    pub docs: Option<String>,
+    /// The other attributes, as written in the source.
+    pub attrs: Vec<String>,
+    /// Set if the item is `#[deprecated]`.
+    pub deprecation: Option<Deprecation>,
+    /// Set if the item has a stability attribute.
+    pub stability: Option<Stability>,
+
+    /// Modifiers, any of `"unsafe"`, `"const"` and `"mut"` for functions,
+    /// methods, traits, impls and statics, `"negative"` for `impl !Trait`
+    /// and `"synthetic"` for impls rustdoc worked out itself, which don't
+    /// appear in the source.
+    pub qualifiers: Vec<&'static str>,
+    /// The ABI of functions and methods that aren't `extern "Rust"`.
+    pub abi: Option<&'static str>,
+    /// The generic parameters, e.g. `<'a, T: Clone>`.
+    pub generics: Option<String>,
+    /// The where-clause, e.g. `where T: Clone`.
+    pub where_clause: Option<String>,
+    /// The arguments and return type of functions and methods, e.g.
+    /// `(&self, x: u8) -> bool`.
+    pub decl: Option<String>,
+    /// The type of fields, statics and constants, the aliased type of type
+    /// definitions, the default of associated types, the field types of
+    /// tuple variants, e.g. `(u8, String)`, and the self type of impls.
+    pub type_: Option<String>,
+    /// The trait of trait impls.
+    pub trait_: Option<String>,
+    /// The supertraits of traits and the bounds of associated types.
+    pub bounds: Option<String>,
+    /// The value of statics and constants, the source of macros, the path
+    /// of imports and the original name of renamed extern crates.
+    pub value: Option<String>,
+    /// `"plain"`, `"tuple"` or `"unit"` for structs, unions and variants.
+    pub struct_type: Option<&'static str>,
+    /// The items of modules, traits and impls, the fields of structs, unions
+    /// and struct variants, and the variants of enums.
+    pub items: Vec<Item>,
+    /// Whether some of the fields or variants in `items` were stripped.
+    pub items_stripped: bool,
+}
+
+/// A range in a source file. Lines start at 1 and columns at 0.
+#[derive(RustcEncodable)]
+pub struct Span {
+    pub filename: String,
+    pub lo_line: usize,
+    pub lo_col: usize,
+    pub hi_line: usize,
+    pub hi_col: usize,
+}
+
+/// The contents of a `#[deprecated]` attribute.
+#[derive(RustcEncodable)]
+pub struct Deprecation {
+    pub since: String,
+    pub note: String,
+}
+
+/// The contents of a `#[stable]` or `#[unstable]` attribute.
+#[derive(RustcEncodable)]
+pub struct Stability {
+    /// `"stable"` or `"unstable"`.
+    pub level: &'static str,
+    pub feature: String,
+    /// The version the item was stabilized in, for stable items.
+    pub since: String,
+    /// Why the item is unstable, for unstable items.
+    pub reason: String,
+    /// The tracking issue, for unstable items.
+    pub issue: Option<u32>,
+}
+
+/// Writes `krate` as JSON into the directory `dst`.
+pub fn run(krate: clean::Crate, dst: PathBuf) -> io::Result<()> {
+    let json = Crate::from(&krate);
+    fs::create_dir_all(&dst)?;
+    let mut w = BufWriter::new(File::create(dst.join(format!("{}.json", krate.name)))?);
+    writeln!(w, "{}", as_json(&json))?;
+    w.flush()
+}
+
+fn id(did: DefId) -> Id {
+    format!("{}:{}", did.krate.as_u32(), did.index.as_u32())
+}
+
+fn docs(attrs: &clean::Attributes) -> Option<String> {
+    if attrs.doc_strings.is_empty() {
+        None
+    } else {
+        Some(attrs.doc_strings.join("\n"))
+    }
+}
+
+fn non_empty(s: String) -> Option<String> {
+    if s.is_empty() { None } else { Some(s) }
+}
+
+fn struct_type(ty: doctree::StructType) -> &'static str {
+    match ty {
+        doctree::Plain => "plain",
+        doctree::Tuple => "tuple",
+        doctree::Unit => "unit",
+    }
+}
+
+impl<'a> From<&'a clean::Crate> for Crate {
+    fn from(krate: &'a clean::Crate) -> Crate {
+        let mut external_traits = krate.external_traits.iter().collect::<Vec<_>>();
+        external_traits.sort_by_key(|&(&did, _)| did);
+
+        Crate {
+            format_version: FORMAT_VERSION,
+            name: krate.name.clone(),
+            src: krate.src.to_string_lossy().into_owned(),
+            root: krate.module.as_ref().map(Item::from),
+            externs: krate.externs.iter().map(|&(cnum, ref e)| {
+                ExternalCrate {
+                    krate: cnum.as_u32(),
+                    name: e.name.clone(),
+                    src: e.src.to_string_lossy().into_owned(),
+                    docs: docs(&e.attrs),
+                }
+            }).collect(),
+            primitives: krate.primitives.iter().map(|&(did, prim, ref attrs)| {
+                Primitive {
+                    id: id(did),
+                    name: prim.as_str(),
+                    docs: docs(attrs),
+                }
+            }).collect(),
+            external_traits: external_traits.into_iter().map(|(&did, t)| {
+                let mut item = Item::new(did, None, ItemType::Trait);
+                item.trait_fields(t);
+                item
+            }).collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a clean::Item> for Item {
+    fn from(it: &'a clean::Item) -> Item {
+        let mut item = Item::new(it.def_id, it.name.clone(), ItemType::from(it));
+        item.span = if it.source.filename.is_empty() {
+            None
+        } else {
+            Some(Span {
+                filename: it.source.filename.clone(),
+                lo_line: it.source.loline,
+                lo_col: it.source.locol,
+                hi_line: it.source.hiline,
+                hi_col: it.source.hicol,
+            })
+        };
+        item.visibility = it.visibility.as_ref().map(|v| match *v {
+            clean::Public => "public",
+            clean::Inherited => "inherited",
+        });
+        item.docs = docs(&it.attrs);
+        item.attrs = it.attrs.other_attrs.iter().map(pprust::attribute_to_string).collect();
+        item.deprecation = it.deprecation.as_ref().map(|d| {
+            Deprecation { since: d.since.clone(), note: d.note.clone() }
+        });
+        item.stability = it.stability.as_ref().map(|s| {
+            Stability {
+                level: match s.level {
+                    StabilityLevel::Stable => "stable",
+                    StabilityLevel::Unstable => "unstable",
+                },
+                feature: s.feature.clone(),
+                since: s.since.clone(),
+                reason: s.unstable_reason.clone(),
+                issue: s.issue,
+            }
+        });
+
+        let inner = match it.inner {
+            clean::StrippedItem(box ref inner) => {
+                item.stripped = true;
+                inner
+            }
+            ref inner => inner,
+        };
+        match *inner {
+            clean::ExternCrateItem(_, ref orig) => item.value = orig.clone(),
+            clean::ImportItem(ref import) => {
+                item.value = Some(match *import {
+                    clean::Import::Simple(_, ref src) => format!("{:#}", src),
+                    clean::Import::Glob(ref src) => format!("{:#}::*", src),
+                });
+            }
+            clean::StructItem(clean::Struct { struct_type: ty, ref generics, ref fields,
+                                              fields_stripped }) |
+            clean::UnionItem(clean::Union { struct_type: ty, ref generics, ref fields,
+                                            fields_stripped }) => {
+                item.generics(generics);
+                item.struct_type = Some(struct_type(ty));
+                item.items = fields.iter().map(Item::from).collect();
+                item.items_stripped = fields_stripped;
+            }
+            clean::EnumItem(ref e) => {
+                item.generics(&e.generics);
+                item.items = e.variants.iter().map(Item::from).collect();
+                item.items_stripped = e.variants_stripped;
+            }
+            clean::FunctionItem(ref f) | clean::ForeignFunctionItem(ref f) => {
+                item.function(&f.generics, &f.decl, f.unsafety, f.constness, f.abi);
+            }
+            clean::MethodItem(ref m) => {
+                item.function(&m.generics, &m.decl, m.unsafety, m.constness, m.abi);
+            }
+            clean::TyMethodItem(ref m) => {
+                item.function(&m.generics, &m.decl, m.unsafety, hir::Constness::NotConst,
+                              m.abi);
+            }
+            clean::ModuleItem(ref m) => item.items = m.items.iter().map(Item::from).collect(),
+            clean::TypedefItem(ref t, _) => {
+                item.generics(&t.generics);
+                item.type_ = Some(format!("{:#}", t.type_));
+            }
+            clean::StaticItem(ref s) | clean::ForeignStaticItem(ref s) => {
+                if s.mutability == clean::Mutable {
+                    item.qualifiers.push("mut");
+                }
+                item.type_ = Some(format!("{:#}", s.type_));
+                item.value = non_empty(s.expr.clone());
+            }
+            clean::ConstantItem(ref c) => {
+                item.type_ = Some(format!("{:#}", c.type_));
+                item.value = non_empty(c.expr.clone());
+            }
+            clean::TraitItem(ref t) => item.trait_fields(t),
+            clean::ImplItem(ref i) => {
+                if i.unsafety == hir::Unsafety::Unsafe {
+                    item.qualifiers.push("unsafe");
+                }
+                if i.polarity == Some(clean::ImplPolarity::Negative) {
+                    item.qualifiers.push("negative");
+                }
+                if i.synthetic {
+                    item.id = None;
+                    item.qualifiers.push("synthetic");
+                }
+                item.generics(&i.generics);
+                item.trait_ = i.trait_.as_ref().map(|t| format!("{:#}", t));
+                item.type_ = Some(format!("{:#}", i.for_));
+                item.items = i.items.iter().map(Item::from).collect();
+            }
+            clean::DefaultImplItem(ref i) => {
+                if i.unsafety == hir::Unsafety::Unsafe {
+                    item.qualifiers.push("unsafe");
+                }
+                item.trait_ = Some(format!("{:#}", i.trait_));
+            }
+            clean::StructFieldItem(ref ty) => item.type_ = Some(format!("{:#}", ty)),
+            clean::VariantItem(ref v) => match v.kind {
+                clean::VariantKind::CLike => item.struct_type = Some("unit"),
+                clean::VariantKind::Tuple(ref tys) => {
+                    item.struct_type = Some("tuple");
+                    let tys = tys.iter().map(|ty| format!("{:#}", ty)).collect::<Vec<_>>();
+                    item.type_ = Some(format!("({})", tys.join(", ")));
+                }
+                clean::VariantKind::Struct(ref s) => {
+                    item.struct_type = Some(struct_type(s.struct_type));
+                    item.items = s.fields.iter().map(Item::from).collect();
+                    item.items_stripped = s.fields_stripped;
+                }
+            },
+            clean::MacroItem(ref m) => item.value = Some(m.source.clone()),
+            clean::PrimitiveItem(prim) => item.type_ = Some(prim.as_str().to_string()),
+            clean::AssociatedConstItem(ref ty, ref default) => {
+                item.type_ = Some(format!("{:#}", ty));
+                item.value = default.clone();
+            }
+            clean::AssociatedTypeItem(ref bounds, ref default) => {
+                item.bounds = non_empty(format!("{:#}", TyParamBounds(bounds)));
+                item.type_ = default.as_ref().map(|ty| format!("{:#}", ty));
+            }
+            clean::StrippedItem(..) => unreachable!(),
+        }
+        item
+    }
+}
+
+impl Item {
+    fn new(did: DefId, name: Option<String>, ty: ItemType) -> Item {
+        Item {
+            id: Some(id(did)),
+            name: name,
+            kind: ty.css_class(),
+            stripped: false,
+            span: None,
+            visibility: None,
+            docs: None,
+            attrs: vec![],
+            deprecation: None,
+            stability: None,
+            qualifiers: vec![],
+            abi: None,
+            generics: None,
+            where_clause: None,
+            decl: None,
+            type_: None,
+            trait_: None,
+            bounds: None,
+            value: None,
+            struct_type: None,
+            items: vec![],
+            items_stripped: false,
+        }
+    }
+
+    fn generics(&mut self, generics: &clean::Generics) {
+        self.generics = non_empty(format!("{:#}", generics));
+        let where_clause = format!("{:#}", WhereClause(generics, 0));
+        self.where_clause = non_empty(where_clause.trim().to_string());
+    }
+
+    fn function(&mut self, generics: &clean::Generics, decl: &clean::FnDecl,
+                unsafety: hir::Unsafety, constness: hir::Constness, abi: Abi) {
+        if unsafety == hir::Unsafety::Unsafe {
+            self.qualifiers.push("unsafe");
+        }
+        if constness == hir::Constness::Const {
+            self.qualifiers.push("const");
+        }
+        if abi != Abi::Rust {
+            self.abi = Some(abi.name());
+        }
+        self.generics(generics);
+        self.decl = Some(format!("{:#}", decl));
+    }
+
+    fn trait_fields(&mut self, t: &clean::Trait) {
+        if t.unsafety == hir::Unsafety::Unsafe {
+            self.qualifiers.push("unsafe");
+        }
+        self.generics(&t.generics);
+        self.bounds = non_empty(format!("{:#}", TyParamBounds(&t.bounds)));
+        self.items = t.items.iter().map(Item::from).collect();
+    }
+}
@@ -54,10 +54,12 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::default::Default;
 use std::env;
 use std::fmt::Display;
+use std::fs::File;
 use std::io;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::process;
+use std::str;
 use std::sync::mpsc::channel;
 
 use externalfiles::ExternalHtml;
@@ -80,8 +82,10 @@ pub mod html {
     pub mod layout;
     pub mod markdown;
     pub mod render;
+    pub mod theme;
     pub mod toc;
 }
+pub mod json;
 pub mod markdown;
 pub mod passes;
 pub mod plugins;
@@ -118,7 +122,7 @@ pub fn opts() -> Vec<RustcOptGroup> {
         stable(optopt("r", "input-format", "the input type of the specified file",
                       "[rust]")),
         stable(optopt("w", "output-format", "the output type to write",
-                      "[html]")),
+                      "[html|json]")),
         stable(optopt("o", "output", "where to place the output", "PATH")),
         stable(optopt("", "crate-name", "specify the name of this crate", "NAME")),
         stable(optmulti("L", "library-path", "directory to add to crate search path",
@@ -134,6 +138,9 @@ pub fn opts() -> Vec<RustcOptGroup> {
         stable(optmulti("", "plugins", "space separated list of plugins to also load",
                         "PLUGINS")),
         stable(optflag("", "no-defaults", "don't run the default passes")),
+        stable(optflag("", "document-private-items",
+                       "document private items, running the default passes \
+                        other than `strip-private`")),
         stable(optflag("", "test", "run code examples as tests")),
         stable(optmulti("", "test-args", "arguments to pass to the test runner",
                         "ARGS")),
@@ -159,6 +166,21 @@ pub fn opts() -> Vec<RustcOptGroup> {
         unstable(optopt("e", "extend-css",
                         "to redefine some css rules with a given file to generate doc with your \
                          own theme", "PATH")),
+        unstable(optmulti("", "themes",
+                          "additional themes to offer in the generated docs, each of which \
+                           must style everything the default theme does", "FILES")),
+        unstable(optflag("", "emit-parts",
+                         "write this crate's part of the search index and implementors lists \
+                          to `parts/` in the output directory instead of updating them, so \
+                          that crates can be documented at the same time and put together \
+                          with `--merge`")),
+        unstable(optmulti("", "extern-local-docs",
+                          "link to a dependency's docs as if they were in the output directory, \
+                           where they'll be once the crates are put together with `--merge`",
+                          "CRATE")),
+        unstable(optflag("", "merge",
+                         "put the docs in the given directories, generated with `--emit-parts`, \
+                          together in the output directory")),
         unstable(optmulti("Z", "",
                           "internal and debugging options (only on nightly build)", "FLAG")),
         stable(optopt("", "sysroot", "Override the system root", "PATH")),
@@ -166,6 +188,8 @@ pub fn opts() -> Vec<RustcOptGroup> {
                         "URL to send code snippets to, may be reset by --markdown-playground-url \
                          or `#![doc(html_playground_url=...)]`",
                         "URL")),
+        unstable(optflag("", "deny-doc-warnings",
+                         "turn warnings about broken documentation into errors")),
     ]
 }
 
@@ -209,9 +233,29 @@ pub fn main_args(args: &[String]) -> isize {
         for &name in passes::DEFAULT_PASSES {
             println!("{:>20}", name);
         }
+        println!("\nDefault passes for rustdoc with --document-private-items:");
+        for &name in passes::DEFAULT_PRIVATE_PASSES {
+            println!("{:>20}", name);
+        }
         return 0;
     }
 
+    if matches.opt_present("merge") {
+        if matches.free.is_empty() {
+            print_error("missing directory operand");
+            return 1;
+        }
+        let inputs = matches.free.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let output = matches.opt_str("o").map(PathBuf::from).unwrap_or(PathBuf::from("doc"));
+        return match html::render::merge(&inputs, &output) {
+            Ok(()) => 0,
+            Err(e) => {
+                print_error(format!("failed to merge documentation: {}", e));
+                1
+            }
+        };
+    }
+
     if matches.free.is_empty() {
         print_error("missing file operand");
         return 1;
@@ -257,6 +301,18 @@ pub fn main_args(args: &[String]) -> isize {
         }
     }
 
+    let mut themes = Vec::new();
+    for theme in matches.opt_strs("themes") {
+        let path = PathBuf::from(&theme);
+        match check_theme(&path) {
+            Ok(()) => themes.push(path),
+            Err(err) => {
+                print_error(format!("invalid theme `{}`: {}", theme, err));
+                return 1;
+            }
+        }
+    }
+
     let external_html = match ExternalHtml::load(
             &matches.opt_strs("html-in-header"),
             &matches.opt_strs("html-before-content"),
@@ -283,6 +339,13 @@ pub fn main_args(args: &[String]) -> isize {
     }
 
     let output_format = matches.opt_str("w");
+    if output_format.as_ref().map_or(false, |s| s == "json") &&
+       !nightly_options::is_unstable_enabled(&matches) {
+        print_error("the `json` output format is unstable and requires `-Z unstable-options`");
+        return 1;
+    }
+    let emit_parts = matches.opt_present("emit-parts");
+    let local_docs = matches.opt_strs("extern-local-docs");
     let res = acquire_input(input, externs, &matches, move |out| {
         let Output { krate, passes, renderinfo } = out;
         info!("going to format");
@@ -292,10 +355,18 @@ pub fn main_args(args: &[String]) -> isize {
                                   output.unwrap_or(PathBuf::from("doc")),
                                   passes.into_iter().collect(),
                                   css_file_extension,
+                                  themes,
+                                  emit_parts,
+                                  local_docs,
                                   renderinfo)
                     .expect("failed to generate documentation");
                 0
             }
+            Some("json") => {
+                json::run(krate, output.unwrap_or(PathBuf::from("doc")))
+                    .expect("failed to generate documentation");
+                0
+            }
             Some(s) => {
                 print_error(format!("unknown output format: {}", s));
                 1
@@ -317,6 +388,34 @@ fn print_error<T>(error_message: T) where T: Display {
     ).unwrap();
 }
 
+/// Checks that the theme at `path` can be used alongside the built-in ones.
+fn check_theme(path: &Path) -> Result<(), String> {
+    use html::theme::{self, DEFAULT_THEME, THEMES};
+
+    let name = match path.file_stem() {
+        Some(name) if path.extension().map_or(false, |e| e == "css") => {
+            name.to_string_lossy().into_owned()
+        }
+        _ => return Err("themes must be `.css` files".to_string()),
+    };
+    // The other stylesheets rustdoc writes out would clash with it too.
+    let taken = ["normalize", "rustdoc", "theme"];
+    if THEMES.iter().any(|&(builtin, _)| builtin == name) || taken.contains(&&name[..]) {
+        return Err(format!("the name `{}` is already taken", name));
+    }
+    let mut css = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut css)).map_err(|e| e.to_string())?;
+
+    let default = THEMES.iter().find(|&&(name, _)| name == DEFAULT_THEME).unwrap().1;
+    let missing = theme::missing_selectors(&css, str::from_utf8(default).unwrap());
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("it has no rules for these selectors of the default theme: {}",
+                    missing.join(", ")))
+    }
+}
+
 /// Looks inside the command line arguments to extract the relevant input format
 /// and files and then generates the necessary rustdoc output for formatting.
 fn acquire_input<R, F>(input: &str,
@@ -357,6 +456,7 @@ fn parse_externs(matches: &getopts::Matches) -> Result<Externs, String> {
 fn rust_input<R, F>(cratefile: &str, externs: Externs, matches: &getopts::Matches, f: F) -> R
 where R: 'static + Send, F: 'static + Send + FnOnce(Output) -> R {
     let mut default_passes = !matches.opt_present("no-defaults");
+    let document_private = matches.opt_present("document-private-items");
     let mut passes = matches.opt_strs("passes");
     let mut plugins = matches.opt_strs("plugins");
 
@@ -370,6 +470,7 @@ where R: 'static + Send, F: 'static + Send + FnOnce(Output) -> R {
     let maybe_sysroot = matches.opt_str("sysroot").map(PathBuf::from);
     let crate_name = matches.opt_str("crate-name");
     let plugin_path = matches.opt_str("plugin-path");
+    let deny_doc_warnings = matches.opt_present("deny-doc-warnings");
 
     let cr = PathBuf::from(cratefile);
     info!("starting to run rustc");
@@ -379,7 +480,8 @@ where R: 'static + Send, F: 'static + Send + FnOnce(Output) -> R {
         use rustc::session::config::Input;
 
         let (mut krate, renderinfo) =
-            core::run_core(paths, cfgs, externs, Input::File(cr), triple, maybe_sysroot);
+            core::run_core(paths, cfgs, externs, Input::File(cr), triple, maybe_sysroot,
+                           deny_doc_warnings);
 
         info!("finished with rustc");
 
@@ -409,7 +511,12 @@ where R: 'static + Send, F: 'static + Send + FnOnce(Output) -> R {
         }
 
         if default_passes {
-            for name in passes::DEFAULT_PASSES.iter().rev() {
+            let defaults = if document_private {
+                passes::DEFAULT_PRIVATE_PASSES
+            } else {
+                passes::DEFAULT_PASSES
+            };
+            for name in defaults.iter().rev() {
                 passes.insert(0, name.to_string());
             }
         }
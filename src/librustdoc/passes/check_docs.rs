@@ -0,0 +1,304 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Warnings about documentation which won't render the way it was meant to:
+//! unclosed code blocks, misspelled code block attributes, unbalanced HTML
+//! and docs on `#[doc(hidden)]` items.
+//!
+//! Unlike the other passes this one leaves the crate alone, and it runs while
+//! the compiler's session is still around so the diagnostics can point at
+//! the items they are about. That's why it isn't one of the `PASSES`.
+
+use rustc::util::nodemap::DefIdSet;
+use std::ascii::AsciiExt;
+use syntax_pos::Span;
+
+use clean::{self, AttributesExt, NestedAttributesExt};
+use core::DocContext;
+use html::markdown::misspelled_lang_string_tags;
+
+/// Checks the docs of every local item in `krate`, reporting problems as
+/// errors rather than warnings if `deny` is set.
+pub fn check_docs(cx: &DocContext, krate: &clean::Crate, deny: bool) {
+    if let Some(ref module) = krate.module {
+        let mut checker = DocChecker {
+            cx: cx,
+            deny: deny,
+            seen: DefIdSet(),
+        };
+        checker.check_item(module);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Problem {
+    UnclosedCodeBlock,
+    MisspelledTag(String, &'static str),
+    UnclosedHtmlTag(String),
+    UnexpectedHtmlEndTag(String),
+}
+
+struct DocChecker<'a, 'b: 'a, 'tcx: 'b> {
+    cx: &'a DocContext<'b, 'tcx>,
+    deny: bool,
+    /// Items inlined from elsewhere in the crate show up more than once.
+    seen: DefIdSet,
+}
+
+impl<'a, 'b, 'tcx> DocChecker<'a, 'b, 'tcx> {
+    fn check_item(&mut self, item: &clean::Item) {
+        // Synthetic impls have no docs, and their `DefId` is their type's.
+        if item.is_synthetic() || !self.seen.insert(item.def_id) {
+            return
+        }
+        // Items from other crates aren't ours to complain about.
+        let span = match self.cx.tcx.hir.span_if_local(item.def_id) {
+            Some(span) => span,
+            None => return,
+        };
+
+        let mut attrs = item.attrs.clone();
+        attrs.collapse_doc_comments();
+        attrs.unindent_doc_comments();
+        let doc = attrs.doc_value().unwrap_or("");
+
+        // Nothing in or below a hidden item is rendered, so there's no point
+        // in checking any further.
+        if item.attrs.lists("doc").has_word("hidden") {
+            if !doc.trim().is_empty() {
+                self.report(span, "documentation on a hidden item is never rendered",
+                            "the item is `#[doc(hidden)]`; use a regular comment instead");
+            }
+            return
+        }
+
+        for problem in check_markdown(doc) {
+            match problem {
+                Problem::UnclosedCodeBlock => {
+                    self.report(span, "unclosed code block in documentation",
+                                "code blocks are closed by a line of at least as many \
+                                 backticks or tildes as opened them");
+                }
+                Problem::MisspelledTag(token, tag) => {
+                    self.report(span, &format!("unknown attribute `{}` in code block", token),
+                                &format!("did you mean `{}`?", tag));
+                }
+                Problem::UnclosedHtmlTag(tag) => {
+                    self.report(span, &format!("unclosed HTML tag `{}` in documentation", tag),
+                                "put code in backticks, or escape the `<` with `\\` if \
+                                 this isn't meant to be HTML");
+                }
+                Problem::UnexpectedHtmlEndTag(tag) => {
+                    self.report(span,
+                                &format!("unexpected HTML end tag `</{}>` in documentation",
+                                         tag),
+                                "there's no matching start tag");
+                }
+            }
+        }
+
+        for child in children(item) {
+            self.check_item(child);
+        }
+    }
+
+    fn report(&self, span: Span, msg: &str, help: &str) {
+        let sess = self.cx.sess();
+        let mut diag = if self.deny {
+            sess.struct_span_err(span, msg)
+        } else {
+            sess.struct_span_warn(span, msg)
+        };
+        diag.help(help);
+        diag.emit();
+    }
+}
+
+fn children(item: &clean::Item) -> &[clean::Item] {
+    match item.inner {
+        clean::ModuleItem(ref m) => &m.items,
+        clean::StructItem(ref s) => &s.fields,
+        clean::UnionItem(ref u) => &u.fields,
+        clean::EnumItem(ref e) => &e.variants,
+        clean::TraitItem(ref t) => &t.items,
+        clean::ImplItem(ref i) => &i.items,
+        clean::VariantItem(clean::Variant {
+            kind: clean::VariantKind::Struct(ref s)
+        }) => &s.fields,
+        _ => &[],
+    }
+}
+
+/// HTML elements which never have an end tag.
+const VOID_ELEMENTS: &'static [&'static str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta",
+    "param", "source", "track", "wbr",
+];
+
+fn check_markdown(md: &str) -> Vec<Problem> {
+    let mut problems = vec![];
+    // The character and length of the fence of the code block we're in.
+    let mut fence = None;
+    let mut open_tags = vec![];
+
+    for line in md.lines() {
+        let trimmed = line.trim_left();
+        let indent = line.len() - trimmed.len();
+
+        if indent < 4 {
+            let c = trimmed.chars().next().unwrap_or(' ');
+            let len = trimmed.chars().take_while(|&x| x == c).count();
+            if (c == '`' || c == '~') && len >= 3 {
+                let rest = &trimmed[len..];
+                match fence {
+                    None => {
+                        fence = Some((c, len));
+                        for (token, tag) in misspelled_lang_string_tags(rest) {
+                            problems.push(Problem::MisspelledTag(token, tag));
+                        }
+                        continue
+                    }
+                    Some((open, open_len)) if open == c && len >= open_len &&
+                                              rest.trim().is_empty() => {
+                        fence = None;
+                        continue
+                    }
+                    Some(..) => {}
+                }
+            }
+        }
+
+        // Code, whether fenced or indented, is never HTML.
+        if fence.is_none() && indent < 4 {
+            check_html(line, &mut open_tags, &mut problems);
+        }
+    }
+
+    if fence.is_some() {
+        problems.push(Problem::UnclosedCodeBlock);
+    }
+    for tag in open_tags {
+        problems.push(Problem::UnclosedHtmlTag(tag));
+    }
+    problems
+}
+
+/// Looks for HTML tags on a line outside of code spans, keeping track of the
+/// elements which are open in `open_tags`.
+fn check_html(line: &str, open_tags: &mut Vec<String>, problems: &mut Vec<Problem>) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => {
+                // Skip to the end of the code span, which is closed by the
+                // same number of backticks as opened it.
+                let ticks = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                let mut j = i + ticks;
+                i = bytes.len();
+                while j < bytes.len() {
+                    let run = bytes[j..].iter().take_while(|&&b| b == b'`').count();
+                    if run == ticks {
+                        i = j + run;
+                        break
+                    }
+                    j += if run == 0 { 1 } else { run };
+                }
+            }
+            b'<' => {
+                i += 1;
+                let end_tag = bytes.get(i) == Some(&b'/');
+                if end_tag {
+                    i += 1;
+                }
+                let start = i;
+                if !bytes.get(i).map_or(false, |&b| is_tag_name_byte(b, true)) {
+                    continue
+                }
+                while bytes.get(i).map_or(false, |&b| is_tag_name_byte(b, false)) {
+                    i += 1;
+                }
+                let name = &line[start..i];
+                // Tags only have whitespace or attributes after their name,
+                // and have to be closed on the same line to count.
+                let valid = match bytes.get(i) {
+                    Some(&b'>') | Some(&b'/') | Some(&b' ') | Some(&b'\t') => true,
+                    _ => false,
+                };
+                let close = match line[i..].find('>') {
+                    Some(close) if valid => i + close,
+                    _ => continue,
+                };
+                let self_closing = bytes[close - 1] == b'/';
+                i = close + 1;
+
+                let lower = name.to_ascii_lowercase();
+                if VOID_ELEMENTS.contains(&&lower[..]) || self_closing {
+                    continue
+                }
+                if !end_tag {
+                    open_tags.push(name.to_owned());
+                    continue
+                }
+                match open_tags.iter().rposition(|t| t.to_ascii_lowercase() == lower) {
+                    Some(pos) => {
+                        for tag in open_tags.drain(pos..).skip(1) {
+                            problems.push(Problem::UnclosedHtmlTag(tag));
+                        }
+                    }
+                    None => problems.push(Problem::UnexpectedHtmlEndTag(name.to_owned())),
+                }
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+fn is_tag_name_byte(b: u8, first: bool) -> bool {
+    match b {
+        b'a'...b'z' | b'A'...b'Z' => true,
+        b'0'...b'9' | b'-' => !first,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod check_docs_tests {
+    use super::check_markdown;
+    use super::Problem::*;
+
+    #[test]
+    fn should_find_unclosed_code_blocks() {
+        assert_eq!(check_markdown("```\nlet x = 1;\n```\n"), vec![]);
+        assert_eq!(check_markdown("````\n```\n````"), vec![]);
+        assert_eq!(check_markdown("text\n```\nlet x = 1;\n"), vec![UnclosedCodeBlock]);
+        assert_eq!(check_markdown("~~~\nlet x = 1;\n```"), vec![UnclosedCodeBlock]);
+    }
+
+    #[test]
+    fn should_find_misspelled_tags() {
+        assert_eq!(check_markdown("```rust,ignroe\n```"),
+                   vec![MisspelledTag("ignroe".to_string(), "ignore")]);
+        assert_eq!(check_markdown("```text\n```"), vec![]);
+    }
+
+    #[test]
+    fn should_find_unbalanced_html() {
+        assert_eq!(check_markdown("<div>\n<p>a<br>b</p>\n</div>"), vec![]);
+        assert_eq!(check_markdown("a `Vec<T>` and <img src=\"x\"/>"), vec![]);
+        assert_eq!(check_markdown("a \\<b> and 1 < 2 > 0"), vec![]);
+        assert_eq!(check_markdown("```\n<div>\n```\n    <div>"), vec![]);
+        assert_eq!(check_markdown("returns a Vec<T>"), vec![UnclosedHtmlTag("T".to_string())]);
+        assert_eq!(check_markdown("<div><span>a</div>"),
+                   vec![UnclosedHtmlTag("span".to_string())]);
+        assert_eq!(check_markdown("a</b>"), vec![UnexpectedHtmlEndTag("b".to_string())]);
+    }
+}
@@ -18,6 +18,9 @@ use fold;
 use fold::FoldItem::Strip;
 use plugins;
 
+mod check_docs;
+pub use self::check_docs::check_docs;
+
 mod collapse_docs;
 pub use self::collapse_docs::collapse_docs;
 
@@ -58,6 +61,14 @@ pub const DEFAULT_PASSES: &'static [&'static str] = &[
     "unindent-comments",
 ];
 
+/// The default passes used with `--document-private-items`, which keep private
+/// items but still hide private imports.
+pub const DEFAULT_PRIVATE_PASSES: &'static [&'static str] = &[
+    "strip-hidden",
+    "strip-priv-imports",
+    "collapse-docs",
+    "unindent-comments",
+];
 
 struct Stripper<'a> {
     retained: &'a mut DefIdSet,
@@ -137,7 +148,9 @@ impl<'a> fold::DocFolder for Stripper<'a> {
         };
 
         let i = if fastreturn {
-            if self.update_retained {
+            // Synthetic impls share the `DefId` of their type, which may
+            // well have been stripped.
+            if self.update_retained && !i.is_synthetic() {
                 self.retained.insert(i.def_id);
             }
             return Some(i);
@@ -57,7 +57,8 @@ impl<'a> fold::DocFolder for Stripper<'a> {
                 _ => return None,
             }
         } else {
-            if self.update_retained {
+            // Synthetic impls share the `DefId` of their type.
+            if self.update_retained && !i.is_synthetic() {
                 self.retained.insert(i.def_id);
             }
         }
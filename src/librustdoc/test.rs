@@ -71,7 +71,7 @@ pub fn run(input: &str,
         ..config::basic_options().clone()
     };
 
-    let codemap = Rc::new(CodeMap::new());
+    let codemap = Rc::new(CodeMap::new(sessopts.file_path_mapping()));
     let handler =
         errors::Handler::with_tty_emitter(ColorConfig::Auto, true, false, Some(codemap.clone()));
 
@@ -211,7 +211,7 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
         }
     }
     let data = Arc::new(Mutex::new(Vec::new()));
-    let codemap = Rc::new(CodeMap::new());
+    let codemap = Rc::new(CodeMap::new(sessopts.file_path_mapping()));
     let emitter = errors::emitter::EmitterWriter::new(box Sink(data.clone()),
                                                       Some(codemap.clone()));
     let old = io::set_panic(Some(box Sink(data.clone())));
@@ -278,17 +278,21 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
     if no_run { return }
 
     // Run the code!
-    //
+    let cmd = Command::new(&outdir.lock().unwrap().path().join("rust_out"));
+    run_test_executable(cmd, libdir, should_panic);
+}
+
+/// Runs a compiled doctest, checking its exit status against `should_panic`.
+fn run_test_executable(mut cmd: Command, libdir: PathBuf, should_panic: bool) {
     // We're careful to prepend the *target* dylib search path to the child's
     // environment to ensure that the target loads the right libraries at
     // runtime. It would be a sad day if the *host* libraries were loaded as a
     // mistake.
-    let mut cmd = Command::new(&outdir.lock().unwrap().path().join("rust_out"));
     let var = DynamicLibrary::envvar();
     let newpath = {
         let path = env::var_os(var).unwrap_or(OsString::new());
         let mut path = env::split_paths(&path).collect::<Vec<_>>();
-        path.insert(0, libdir.clone());
+        path.insert(0, libdir);
         env::join_paths(path).unwrap()
     };
     cmd.env(var, &newpath);
@@ -372,6 +376,239 @@ fn partition_source(s: &str) -> (String, String) {
     (before, after)
 }
 
+/// Doctests which don't need a crate of their own are compiled together into
+/// a single executable, each in a function of its own that `main` picks by
+/// index. Every test still runs in a separate process, so panics and exit
+/// codes stay apart. If the combined crate doesn't compile, the tests which
+/// break it are found by bisecting and fall back to being compiled one by
+/// one, which also reports the errors against the test that caused them; the
+/// others are still compiled together.
+struct CombinedTests {
+    cratename: String,
+    cfgs: Vec<String>,
+    libs: SearchPaths,
+    externs: Externs,
+    opts: TestOptions,
+    maybe_sysroot: Option<PathBuf>,
+    sources: Mutex<Vec<String>>,
+    executable: Mutex<Option<Option<CombinedExecutable>>>,
+}
+
+struct CombinedExecutable {
+    _outdir: TempDir,
+    exe: PathBuf,
+    libdir: PathBuf,
+    /// The indices of the tests in the executable, in the order `main`
+    /// numbers them.
+    tests: Vec<usize>,
+}
+
+impl CombinedTests {
+    /// Adds a test to the crate, returning its index.
+    fn add(&self, test: &str) -> usize {
+        let mut sources = self.sources.lock().unwrap();
+        sources.push(test.to_owned());
+        sources.len() - 1
+    }
+
+    /// Runs the test at `index`, compiling the combined crate first if no
+    /// other test has. Returns `false` if the test isn't part of the crate
+    /// because it didn't compile, in which case it needs to be compiled on
+    /// its own instead.
+    fn run(&self, index: usize, should_panic: bool, no_run: bool) -> bool {
+        let (exe, libdir, position) = {
+            let mut executable = self.executable.lock().unwrap();
+            if executable.is_none() {
+                *executable = Some(self.compile());
+            }
+            match *executable {
+                Some(Some(ref e)) => match e.tests.iter().position(|&t| t == index) {
+                    Some(position) => (e.exe.clone(), e.libdir.clone(), position),
+                    None => return false,
+                },
+                _ => return false,
+            }
+        };
+
+        if !no_run {
+            let mut cmd = Command::new(&exe);
+            cmd.arg(position.to_string());
+            run_test_executable(cmd, libdir, should_panic);
+        }
+        true
+    }
+
+    fn compile(&self) -> Option<CombinedExecutable> {
+        let sources = self.sources.lock().unwrap().clone();
+        let all = (0..sources.len()).collect::<Vec<_>>();
+        if let Some(executable) = self.build(&sources, &all, false) {
+            return Some(executable);
+        }
+
+        // Something doesn't compile. Look for the tests which are fine by
+        // only type checking, which is enough to find type errors and denied
+        // lints, and build those.
+        let mut good = vec![];
+        self.bisect(&sources, &all, &mut good);
+        if good.is_empty() {
+            return None;
+        }
+        self.build(&sources, &good, false)
+    }
+
+    /// Adds the tests in `tests`, which don't compile together, to `good`
+    /// unless they are the ones at fault.
+    fn bisect(&self, sources: &[String], tests: &[usize], good: &mut Vec<usize>) {
+        if tests.len() < 2 {
+            return;
+        }
+        let (left, right) = tests.split_at(tests.len() / 2);
+        for half in &[left, right] {
+            if self.build(sources, half, true).is_some() {
+                good.extend_from_slice(half);
+            } else {
+                self.bisect(sources, half, good);
+            }
+        }
+    }
+
+    /// Compiles the tests in `tests` into one executable, or only type
+    /// checks them if `check_only` is set. Returns `None` if they don't
+    /// compile.
+    fn build(&self, sources: &[String], tests: &[usize], check_only: bool)
+             -> Option<CombinedExecutable> {
+        let src = {
+            let tests = tests.iter().map(|&i| &sources[i][..]).collect::<Vec<_>>();
+            make_combined(&tests, &self.cratename, &self.opts)
+        };
+        let cfgs = self.cfgs.clone();
+        let libs = self.libs.clone();
+        let externs = self.externs.clone();
+        let maybe_sysroot = self.maybe_sysroot.clone();
+        let built = rustc_driver::in_rustc_thread(move || {
+            compile_combined(src, cfgs, libs, externs, maybe_sysroot, check_only)
+        }).unwrap_or(None);
+        built.map(|(outdir, libdir)| CombinedExecutable {
+            exe: outdir.path().join("rust_out"),
+            _outdir: outdir,
+            libdir: libdir,
+            tests: tests.to_vec(),
+        })
+    }
+}
+
+/// Whether a test can be part of the combined crate rather than needing one
+/// of its own: it has to be run (or at least compiled) normally, without the
+/// test harness or expected errors, and must not contain anything which only
+/// works at the top of a crate, like `fn main`, crate attributes or
+/// `extern crate`.
+fn can_combine(test: &str, should_ignore: bool, as_test_harness: bool,
+               compile_fail: bool, error_codes: &[String]) -> bool {
+    if should_ignore || as_test_harness || compile_fail || !error_codes.is_empty() {
+        return false
+    }
+    let (crate_attrs, _) = partition_source(test);
+    crate_attrs.trim().is_empty() &&
+        !test.contains("fn main") &&
+        !test.contains("extern crate") &&
+        !test.contains("#![")
+}
+
+/// Builds the source of the combined crate, where the test at index `i` runs
+/// when `i` is the executable's only argument.
+fn make_combined(tests: &[&str], cratename: &str, opts: &TestOptions) -> String {
+    let mut prog = String::new();
+
+    for attr in &opts.attrs {
+        prog.push_str(&format!("#![{}]\n", attr));
+    }
+
+    if !opts.no_crate_inject && cratename != "std" &&
+       tests.iter().any(|test| test.contains(cratename)) {
+        prog.push_str(&format!("extern crate {};\n", cratename));
+    }
+
+    // The tests are functions at the root of the crate so that paths in them
+    // resolve just like they would in the `main` of a test of its own.
+    for (i, test) in tests.iter().enumerate() {
+        prog.push_str(&format!("\nfn __rustdoctest_{}() {{\n{}\n}}\n", i, test.trim()));
+    }
+
+    prog.push_str("\nfn main() {\n");
+    prog.push_str("    match ::std::env::args().nth(1).and_then(|i| i.parse::<usize>().ok()) {\n");
+    for i in 0..tests.len() {
+        prog.push_str(&format!("        Some({0}) => __rustdoctest_{0}(),\n", i));
+    }
+    prog.push_str("        _ => ::std::process::exit(2),\n");
+    prog.push_str("    }\n}\n");
+
+    info!("combined test program: {}", prog);
+
+    prog
+}
+
+/// Compiles the combined crate `src`, returning the directory holding the
+/// executable and the directory of the libraries it needs, or `None` if it
+/// doesn't compile. If `check_only` is set, it is only type checked.
+fn compile_combined(src: String, cfgs: Vec<String>, libs: SearchPaths, externs: Externs,
+                    maybe_sysroot: Option<PathBuf>, check_only: bool)
+                    -> Option<(TempDir, PathBuf)> {
+    let input = config::Input::Str {
+        name: driver::anon_src(),
+        input: src,
+    };
+    let outputs = OutputTypes::new(&[(OutputType::Exe, None)]);
+
+    let sessopts = config::Options {
+        maybe_sysroot: maybe_sysroot.or_else(
+            || Some(env::current_exe().unwrap().parent().unwrap().parent().unwrap().to_path_buf())),
+        search_paths: libs,
+        crate_types: vec![config::CrateTypeExecutable],
+        output_types: outputs,
+        externs: externs,
+        cg: config::CodegenOptions {
+            prefer_dynamic: true,
+            .. config::basic_codegen_options()
+        },
+        unstable_features: UnstableFeatures::from_environment(),
+        ..config::basic_options().clone()
+    };
+
+    // Any errors are thrown away, the tests report them when they are
+    // compiled on their own.
+    let codemap = Rc::new(CodeMap::new(sessopts.file_path_mapping()));
+    let emitter = errors::emitter::EmitterWriter::new(box io::sink(), Some(codemap.clone()));
+    io::set_panic(Some(box io::sink()));
+    let diagnostic_handler = errors::Handler::with_emitter(true, false, box emitter);
+
+    let dep_graph = DepGraph::new(false);
+    let cstore = Rc::new(CStore::new(&dep_graph));
+    let mut sess = session::build_session_(
+        sessopts, &dep_graph, None, diagnostic_handler, codemap, cstore.clone(),
+    );
+    rustc_lint::register_builtins(&mut sess.lint_store.borrow_mut(), Some(&sess));
+    sess.parse_sess.config =
+        config::build_configuration(&sess, config::parse_cfgspecs(cfgs));
+
+    let outdir = TempDir::new("rustdoctest").ok().expect("rustdoc needs a tempdir");
+    let libdir = sess.target_filesearch(PathKind::All).get_lib_path();
+    let out = Some(outdir.path().to_path_buf());
+    let mut control = driver::CompileController::basic();
+    if check_only {
+        control.after_analysis.stop = Compilation::Stop;
+    }
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        driver::compile_input(&sess, &cstore, &input, &out, &None, None, &control)
+    }));
+
+    // Stopping after analysis is reported as an error without any errors.
+    match res {
+        Ok(Ok(())) | Ok(Err(0)) => Some((outdir, libdir)),
+        _ => None,
+    }
+}
+
 pub struct Collector {
     pub tests: Vec<testing::TestDescAndFn>,
     names: Vec<String>,
@@ -384,11 +621,22 @@ pub struct Collector {
     cratename: String,
     opts: TestOptions,
     maybe_sysroot: Option<PathBuf>,
+    combined: Arc<CombinedTests>,
 }
 
 impl Collector {
     pub fn new(cratename: String, cfgs: Vec<String>, libs: SearchPaths, externs: Externs,
                use_headers: bool, opts: TestOptions, maybe_sysroot: Option<PathBuf>) -> Collector {
+        let combined = Arc::new(CombinedTests {
+            cratename: cratename.clone(),
+            cfgs: cfgs.clone(),
+            libs: libs.clone(),
+            externs: externs.clone(),
+            opts: opts.clone(),
+            maybe_sysroot: maybe_sysroot.clone(),
+            sources: Mutex::new(Vec::new()),
+            executable: Mutex::new(None),
+        });
         Collector {
             tests: Vec::new(),
             names: Vec::new(),
@@ -401,6 +649,7 @@ impl Collector {
             cratename: cratename,
             opts: opts,
             maybe_sysroot: maybe_sysroot,
+            combined: combined,
         }
     }
 
@@ -420,6 +669,12 @@ impl Collector {
         let cratename = self.cratename.to_string();
         let opts = self.opts.clone();
         let maybe_sysroot = self.maybe_sysroot.clone();
+        let combined = if can_combine(&test, should_ignore, as_test_harness,
+                                      compile_fail, &error_codes) {
+            Some((self.combined.clone(), self.combined.add(&test)))
+        } else {
+            None
+        };
         debug!("Creating test {}: {}", name, test);
         self.tests.push(testing::TestDescAndFn {
             desc: testing::TestDesc {
@@ -429,6 +684,11 @@ impl Collector {
                 should_panic: testing::ShouldPanic::No,
             },
             testfn: testing::DynTestFn(box move |()| {
+                if let Some((combined, index)) = combined {
+                    if combined.run(index, should_panic, no_run) {
+                        return
+                    }
+                }
                 match {
                     rustc_driver::in_rustc_thread(move || {
                         runtest(&test,
@@ -162,26 +162,37 @@ impl FileLoader for RealFileLoader {
 pub struct CodeMap {
     pub files: RefCell<Vec<Rc<FileMap>>>,
     expansions: RefCell<Vec<ExpnInfo>>,
-    file_loader: Box<FileLoader>
+    file_loader: Box<FileLoader>,
+    // This is used to apply the file path remapping as specified via
+    // --remap-path-prefix to all FileMaps allocated within this CodeMap.
+    path_mapping: FilePathMapping,
 }
 
 impl CodeMap {
-    pub fn new() -> CodeMap {
+    pub fn new(path_mapping: FilePathMapping) -> CodeMap {
         CodeMap {
             files: RefCell::new(Vec::new()),
             expansions: RefCell::new(Vec::new()),
-            file_loader: Box::new(RealFileLoader)
+            file_loader: Box::new(RealFileLoader),
+            path_mapping: path_mapping,
         }
     }
 
-    pub fn with_file_loader(file_loader: Box<FileLoader>) -> CodeMap {
+    pub fn with_file_loader(file_loader: Box<FileLoader>,
+                            path_mapping: FilePathMapping)
+                            -> CodeMap {
         CodeMap {
             files: RefCell::new(Vec::new()),
             expansions: RefCell::new(Vec::new()),
-            file_loader: file_loader
+            file_loader: file_loader,
+            path_mapping: path_mapping,
         }
     }
 
+    pub fn path_mapping(&self) -> &FilePathMapping {
+        &self.path_mapping
+    }
+
     pub fn file_exists(&self, path: &Path) -> bool {
         self.file_loader.file_exists(path)
     }
@@ -204,6 +215,9 @@ impl CodeMap {
 
     /// Creates a new filemap without setting its line information. If you don't
     /// intend to set the line information yourself, you should use new_filemap_and_lines.
+    ///
+    /// The path remapping of this CodeMap is applied to `filename` and
+    /// `abs_path`, the original name is kept in `unmapped_path`.
     pub fn new_filemap(&self, filename: FileName, abs_path: Option<FileName>,
                        mut src: String) -> Rc<FileMap> {
         let start_pos = self.next_start_pos();
@@ -216,9 +230,14 @@ impl CodeMap {
 
         let end_pos = start_pos + src.len();
 
+        let unmapped_path = PathBuf::from(&filename);
+        let (filename, _) = self.path_mapping.map_prefix(filename);
+        let abs_path = abs_path.map(|path| self.path_mapping.map_prefix(path).0);
+
         let filemap = Rc::new(FileMap {
             name: filename,
             abs_path: abs_path,
+            unmapped_path: Some(unmapped_path),
             src: Some(Rc::new(src)),
             start_pos: Pos::from_usize(start_pos),
             end_pos: Pos::from_usize(end_pos),
@@ -274,9 +293,15 @@ impl CodeMap {
             mbc.pos = mbc.pos + start_pos;
         }
 
+        // The paths may have been remapped when the external crate was
+        // compiled already, remapping them again is harmless.
+        let (filename, _) = self.path_mapping.map_prefix(filename);
+        let abs_path = abs_path.map(|path| self.path_mapping.map_prefix(path).0);
+
         let filemap = Rc::new(FileMap {
             name: filename,
             abs_path: abs_path,
+            unmapped_path: None,
             src: None,
             start_pos: start_pos,
             end_pos: end_pos,
@@ -564,6 +589,17 @@ impl CodeMap {
         self.lookup_char_pos(sp.lo).file.name.to_string()
     }
 
+    /// The path of the file containing `sp` as it was passed to the
+    /// compiler, before applying any path remapping. Use this rather than
+    /// `span_to_filename` to locate files relative to the source.
+    pub fn span_to_unmapped_path(&self, sp: Span) -> PathBuf {
+        let file = self.lookup_char_pos(sp.lo).file;
+        match file.unmapped_path {
+            Some(ref path) => path.clone(),
+            None => PathBuf::from(&file.name),
+        }
+    }
+
     pub fn span_to_lines(&self, sp: Span) -> FileLinesResult {
         debug!("span_to_lines(sp={:?})", sp);
 
@@ -855,6 +891,42 @@ impl CodeMapper for CodeMap {
 // Tests
 //
 
+#[derive(Clone)]
+pub struct FilePathMapping {
+    mapping: Vec<(String, String)>,
+}
+
+impl FilePathMapping {
+    pub fn empty() -> FilePathMapping {
+        FilePathMapping {
+            mapping: vec![]
+        }
+    }
+
+    pub fn new(mapping: Vec<(String, String)>) -> FilePathMapping {
+        FilePathMapping {
+            mapping: mapping
+        }
+    }
+
+    /// Applies any path prefix substitution as defined by the mapping.
+    /// The return value is the remapped path and a boolean indicating whether
+    /// the path was affected by the mapping.
+    pub fn map_prefix(&self, path: String) -> (String, bool) {
+        // NOTE: We are iterating over the mapping entries from last to first
+        //       because entries specified later on the command line should
+        //       take precedence.
+        for &(ref from, ref to) in self.mapping.iter().rev() {
+            if path.starts_with(from) {
+                let mapped = path.replacen(from, to, 1);
+                return (mapped, true);
+            }
+        }
+
+        (path, false)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -863,7 +935,7 @@ mod tests {
 
     #[test]
     fn t1 () {
-        let cm = CodeMap::new();
+        let cm = CodeMap::new(FilePathMapping::empty());
         let fm = cm.new_filemap("blork.rs".to_string(),
                                 None,
                                 "first line.\nsecond line".to_string());
@@ -880,7 +952,7 @@ mod tests {
     #[test]
     #[should_panic]
     fn t2 () {
-        let cm = CodeMap::new();
+        let cm = CodeMap::new(FilePathMapping::empty());
         let fm = cm.new_filemap("blork.rs".to_string(),
                                 None,
                                 "first line.\nsecond line".to_string());
@@ -891,7 +963,7 @@ mod tests {
     }
 
     fn init_code_map() -> CodeMap {
-        let cm = CodeMap::new();
+        let cm = CodeMap::new(FilePathMapping::empty());
         let fm1 = cm.new_filemap("blork.rs".to_string(),
                                  None,
                                  "first line.\nsecond line".to_string());
@@ -958,7 +1030,7 @@ mod tests {
     }
 
     fn init_code_map_mbc() -> CodeMap {
-        let cm = CodeMap::new();
+        let cm = CodeMap::new(FilePathMapping::empty());
         // € is a three byte utf8 char.
         let fm1 =
             cm.new_filemap("blork.rs".to_string(),
@@ -1030,7 +1102,7 @@ mod tests {
     /// lines in the middle of a file.
     #[test]
     fn span_to_snippet_and_lines_spanning_multiple_lines() {
-        let cm = CodeMap::new();
+        let cm = CodeMap::new(FilePathMapping::empty());
         let inputtext = "aaaaa\nbbbbBB\nCCC\nDDDDDddddd\neee\n";
         let selection = "     \n    ~~\n~~~\n~~~~~     \n   \n";
         cm.new_filemap_and_lines("blork.rs", None, inputtext);
@@ -1105,7 +1177,7 @@ mod tests {
     /// Test merging two spans on the same line
     #[test]
     fn span_merging() {
-        let cm = CodeMap::new();
+        let cm = CodeMap::new(FilePathMapping::empty());
         let inputtext  = "bbbb BB bb CCC\n";
         let selection1 = "     ~~       \n";
         let selection2 = "           ~~~\n";
@@ -1125,7 +1197,7 @@ mod tests {
     /// Test failing to merge two spans on different lines
     #[test]
     fn span_merging_fail() {
-        let cm = CodeMap::new();
+        let cm = CodeMap::new(FilePathMapping::empty());
         let inputtext  = "bbbb BB\ncc CCC\n";
         let selection1 = "     ~~\n      \n";
         let selection2 = "       \n   ~~~\n";
@@ -1258,4 +1330,29 @@ r"blork2.rs:2:1: 2:12
 ";
         assert_eq!(sstr, res_str);
     }
+
+    #[test]
+    fn t_remap_path_prefix() {
+        let mapping = FilePathMapping::new(vec![
+            ("/src".to_string(), "/a".to_string()),
+            ("/src/sub".to_string(), "/b".to_string()),
+        ]);
+        let cm = CodeMap::new(mapping);
+
+        let fm = cm.new_filemap("/src/lib.rs".to_string(),
+                                Some("/src/lib.rs".to_string()),
+                                "".to_string());
+        assert_eq!(fm.name, "/a/lib.rs");
+        assert_eq!(fm.abs_path, Some("/a/lib.rs".to_string()));
+        assert_eq!(fm.unmapped_path, Some(PathBuf::from("/src/lib.rs")));
+
+        // The mapping given last wins.
+        let fm = cm.new_filemap("/src/sub/mod.rs".to_string(), None, "".to_string());
+        assert_eq!(fm.name, "/b/mod.rs");
+
+        let fm = cm.new_filemap("other.rs".to_string(), None, "".to_string());
+        assert_eq!(fm.name, "other.rs");
+        assert_eq!(cm.span_to_unmapped_path(mk_sp(fm.start_pos, fm.end_pos)),
+                   PathBuf::from("other.rs"));
+    }
 }
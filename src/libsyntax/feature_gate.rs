@@ -325,6 +325,10 @@ declare_features! (
 
     // `extern "msp430-interrupt" fn()`
     (active, abi_msp430_interrupt, "1.16.0", Some(38487)),
+
+    // Used to identify the crate containing the LLVM profiling runtime
+    // rustc internal
+    (active, profiler_runtime, "1.16.0", None),
 );
 
 declare_features! (
@@ -644,6 +648,13 @@ pub const BUILTIN_ATTRIBUTES: &'static [(&'static str, AttributeType, AttributeG
                                               contains compiler-rt intrinsics and will never be \
                                               stable",
                                           cfg_fn!(compiler_builtins))),
+    ("profiler_runtime", Whitelisted, Gated(Stability::Unstable,
+                                            "profiler_runtime",
+                                            "the `#[profiler_runtime]` attribute is used to \
+                                             identify the `profiler_builtins` crate which \
+                                             contains the profiler runtime and will never be \
+                                             stable",
+                                            cfg_fn!(profiler_runtime))),
 
     ("allow_internal_unstable", Normal, Gated(Stability::Unstable,
                                               "allow_internal_unstable",
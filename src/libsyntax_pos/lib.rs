@@ -32,6 +32,7 @@
 use std::cell::{Cell, RefCell};
 use std::ops::{Add, Sub};
 use std::rc::Rc;
+use std::path::PathBuf;
 use std::cmp;
 
 use std::fmt;
@@ -291,6 +292,9 @@ pub struct FileMap {
     pub name: FileName,
     /// The absolute path of the file that the source came from.
     pub abs_path: Option<FileName>,
+    /// The path of the file that the source came from, before any path
+    /// remapping was applied to `name`. `None` for imported filemaps.
+    pub unmapped_path: Option<PathBuf>,
     /// The complete source code
     pub src: Option<Rc<String>>,
     /// The start position of this source in the CodeMap
@@ -405,6 +409,7 @@ impl Decodable for FileMap {
             Ok(FileMap {
                 name: name,
                 abs_path: abs_path,
+                unmapped_path: None,
                 start_pos: start_pos,
                 end_pos: end_pos,
                 src: None,
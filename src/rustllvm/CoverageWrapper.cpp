@@ -0,0 +1,101 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#include "rustllvm.h"
+
+#if LLVM_VERSION_GE(3, 9)
+#include "llvm/ProfileData/Coverage/CoverageMapping.h"
+#include "llvm/ProfileData/Coverage/CoverageMappingWriter.h"
+#include "llvm/ProfileData/InstrProf.h"
+#endif
+
+//===----------------------------------------------------------------------===
+//
+// This file defines the interfaces used by `-Z instrument-coverage` to encode
+// the LLVM coverage mapping format. Only the encoding is done here, the
+// `__llvm_covmap` global itself is assembled on the Rust side.
+//
+//===----------------------------------------------------------------------===
+
+using namespace llvm;
+
+// A single code region, mapped to the counter of the MIR basic block which
+// covers it. Must be kept in sync with `CounterMappingRegion` in
+// `librustc_llvm/ffi.rs`.
+struct LLVMRustCounterMappingRegion {
+  unsigned CounterId;
+  unsigned FileId;
+  unsigned LineStart;
+  unsigned ColumnStart;
+  unsigned LineEnd;
+  unsigned ColumnEnd;
+};
+
+extern "C" void LLVMRustCoverageWriteFilenamesSectionToBuffer(
+    const char *const Filenames[], size_t FilenamesLen,
+    RustStringRef BufferOut) {
+#if LLVM_VERSION_GE(3, 9)
+  SmallVector<StringRef, 32> FilenameRefs;
+  for (size_t I = 0; I < FilenamesLen; I++) {
+    FilenameRefs.push_back(StringRef(Filenames[I]));
+  }
+  RawRustStringOstream OS(BufferOut);
+  coverage::CoverageFilenamesSectionWriter(FilenameRefs).write(OS);
+#else
+  report_fatal_error("code coverage instrumentation requires LLVM 3.9 or later");
+#endif
+}
+
+extern "C" void LLVMRustCoverageWriteMappingToBuffer(
+    const unsigned *VirtualFileMappingIDs, unsigned NumVirtualFileMappingIDs,
+    const LLVMRustCounterMappingRegion *RustRegions, unsigned NumRegions,
+    RustStringRef BufferOut) {
+#if LLVM_VERSION_GE(3, 9)
+  SmallVector<coverage::CounterMappingRegion, 32> Regions;
+  for (unsigned I = 0; I < NumRegions; I++) {
+    const LLVMRustCounterMappingRegion &R = RustRegions[I];
+    Regions.push_back(coverage::CounterMappingRegion::makeRegion(
+        coverage::Counter::getCounter(R.CounterId), R.FileId, R.LineStart,
+        R.ColumnStart, R.LineEnd, R.ColumnEnd));
+  }
+  auto FileIDs =
+      makeArrayRef(VirtualFileMappingIDs, NumVirtualFileMappingIDs);
+  RawRustStringOstream OS(BufferOut);
+  coverage::CoverageMappingWriter(FileIDs, None, Regions).write(OS);
+#else
+  report_fatal_error("code coverage instrumentation requires LLVM 3.9 or later");
+#endif
+}
+
+extern "C" LLVMValueRef LLVMRustCoverageCreatePGOFuncNameVar(LLVMValueRef F,
+                                                             const char *FuncName) {
+#if LLVM_VERSION_GE(3, 9)
+  StringRef FuncNameRef(FuncName);
+  return wrap(createPGOFuncNameVar(*cast<Function>(unwrap(F)), FuncNameRef));
+#else
+  report_fatal_error("code coverage instrumentation requires LLVM 3.9 or later");
+#endif
+}
+
+extern "C" uint64_t LLVMRustCoverageComputeHash(const char *Name) {
+#if LLVM_VERSION_GE(3, 9)
+  return IndexedInstrProf::ComputeHash(StringRef(Name));
+#else
+  report_fatal_error("code coverage instrumentation requires LLVM 3.9 or later");
+#endif
+}
+
+extern "C" uint32_t LLVMRustCoverageMappingVersion() {
+#if LLVM_VERSION_GE(3, 9)
+  return coverage::CovMapVersion::CurrentVersion;
+#else
+  report_fatal_error("code coverage instrumentation requires LLVM 3.9 or later");
+#endif
+}
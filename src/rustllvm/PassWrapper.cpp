@@ -15,6 +15,8 @@
 #include "llvm/Analysis/TargetLibraryInfo.h"
 #include "llvm/Analysis/TargetTransformInfo.h"
 #include "llvm/IR/AutoUpgrade.h"
+#include "llvm/Object/ObjectFile.h"
+#include "llvm/Object/SymbolSize.h"
 #include "llvm/Support/CBindingWrapping.h"
 #include "llvm/Support/FileSystem.h"
 #include "llvm/Support/Host.h"
@@ -23,7 +25,14 @@
 #include "llvm/Transforms/IPO/PassManagerBuilder.h"
 
 #if LLVM_VERSION_GE(4, 0)
+#include "llvm/Bitcode/BitcodeReader.h"
+#include "llvm/IR/ModuleSummaryIndex.h"
+#include "llvm/IR/LegacyPassManager.h"
+#include "llvm/Transforms/IPO.h"
 #include "llvm/Transforms/IPO/AlwaysInliner.h"
+#include "llvm/Transforms/IPO/FunctionImport.h"
+#include "llvm/Transforms/Utils/FunctionImportUtils.h"
+#include "llvm/LTO/LTO.h"
 #endif
 
 #include "llvm-c/Transforms/PassManagerBuilder.h"
@@ -368,12 +377,28 @@ extern "C" void LLVMRustAddAnalysisPasses(LLVMTargetMachineRef TM,
 
 extern "C" void LLVMRustConfigurePassManagerBuilder(
     LLVMPassManagerBuilderRef PMBR, LLVMRustCodeGenOptLevel OptLevel,
-    bool MergeFunctions, bool SLPVectorize, bool LoopVectorize) {
+    bool MergeFunctions, bool SLPVectorize, bool LoopVectorize,
+    const char *PGOGenPath, const char *PGOUsePath) {
   // Ignore mergefunc for now as enabling it causes crashes.
   // unwrap(PMBR)->MergeFunctions = MergeFunctions;
   unwrap(PMBR)->SLPVectorize = SLPVectorize;
   unwrap(PMBR)->OptLevel = fromRust(OptLevel);
   unwrap(PMBR)->LoopVectorize = LoopVectorize;
+
+#if LLVM_VERSION_GE(3, 9)
+  if (PGOGenPath) {
+    assert(!PGOUsePath);
+    unwrap(PMBR)->EnablePGOInstrGen = true;
+    unwrap(PMBR)->PGOInstrGen = PGOGenPath;
+  }
+  if (PGOUsePath) {
+    assert(!PGOGenPath);
+    unwrap(PMBR)->PGOInstrUse = PGOUsePath;
+  }
+#else
+  if (PGOGenPath || PGOUsePath)
+    report_fatal_error("profile-guided optimization requires LLVM 3.9 or later");
+#endif
 }
 
 // Unfortunately, the LLVM C API doesn't provide a way to set the `LibraryInfo`
@@ -493,6 +518,51 @@ extern "C" void LLVMRustPrintModule(LLVMPassManagerRef PMR, LLVMModuleRef M,
   PM->run(*unwrap(M));
 }
 
+// Writes the name and size of every function symbol defined in the object
+// file at `Path` to `Out`, one `name\tsize` pair per line. The global symbol
+// prefix of the target (e.g. `_` on Darwin) is stripped from the names so that
+// they match the names in the IR.
+extern "C" bool LLVMRustGetObjectFunctionSizes(LLVMTargetMachineRef TMR,
+                                               const char *Path,
+                                               RustStringRef Out) {
+#if LLVM_VERSION_GE(3, 9)
+  Expected<object::OwningBinary<object::ObjectFile>> ObjOrErr =
+      object::ObjectFile::createObjectFile(Path);
+  if (!ObjOrErr) {
+    LLVMRustSetLastError(toString(ObjOrErr.takeError()).c_str());
+    return false;
+  }
+  object::ObjectFile &Obj = *ObjOrErr->getBinary();
+  char Prefix = unwrap(TMR)->createDataLayout().getGlobalPrefix();
+
+  RawRustStringOstream OS(Out);
+  for (auto &SymAndSize : object::computeSymbolSizes(Obj)) {
+    object::SymbolRef Sym = SymAndSize.first;
+    Expected<object::SymbolRef::Type> Type = Sym.getType();
+    if (!Type) {
+      consumeError(Type.takeError());
+      continue;
+    }
+    if (*Type != object::SymbolRef::ST_Function)
+      continue;
+    Expected<StringRef> NameOrErr = Sym.getName();
+    if (!NameOrErr) {
+      consumeError(NameOrErr.takeError());
+      continue;
+    }
+    StringRef Name = *NameOrErr;
+    if (Prefix && Name.startswith(StringRef(&Prefix, 1)))
+      Name = Name.drop_front();
+    OS << Name << '\t' << SymAndSize.second << '\n';
+  }
+  return true;
+#else
+  LLVMRustSetLastError("function sizes are not supported by this version "
+                       "of LLVM");
+  return false;
+#endif
+}
+
 extern "C" void LLVMRustPrintPasses() {
   LLVMInitializePasses();
   struct MyListener : PassRegistrationListener {
@@ -586,3 +656,296 @@ extern "C" void LLVMRustSetModulePIELevel(LLVMModuleRef M) {
   unwrap(M)->setPIELevel(PIELevel::Level::Large);
 #endif
 }
+
+// Here you'll find the implementation of ThinLTO as used by Rust. The
+// functions below mirror what LLVM's own `ThinLTOCodeGenerator` does, split
+// up so that rustc can drive each step itself: modules are serialized along
+// with their summaries, a combined index is computed over all of them on the
+// main thread, and afterwards every module is renamed, internalized and has
+// its imports pulled in independently, which is what allows the backends to
+// run in parallel.
+
+#if LLVM_VERSION_GE(4, 0)
+
+// A serialized module along with its summary, as produced by
+// `LLVMRustThinLTOBufferCreate`.
+struct LLVMRustThinLTOBuffer {
+  std::string data;
+};
+
+// One entry of the list of modules passed to `LLVMRustCreateThinLTOData`.
+// Must be kept in sync with `ThinLTOModule` in `librustc_llvm/ffi.rs`.
+struct LLVMRustThinLTOModule {
+  const char *identifier;
+  const char *data;
+  size_t len;
+};
+
+// The result of the whole-program analysis, shared by all the backends.
+struct LLVMRustThinLTOData {
+  // The combined summary index of all the modules.
+  ModuleSummaryIndex Index;
+
+  // The serialized modules, keyed by their identifier, from which functions
+  // are lazily imported.
+  StringMap<MemoryBufferRef> ModuleMap;
+
+  // Per-module results of the analysis.
+  StringMap<FunctionImporter::ImportMapTy> ImportLists;
+  StringMap<FunctionImporter::ExportSetTy> ExportLists;
+  StringMap<GVSummaryMapTy> ModuleToDefinedGVSummaries;
+};
+
+extern "C" bool LLVMRustThinLTOAvailable() { return true; }
+
+extern "C" LLVMRustThinLTOBuffer *LLVMRustThinLTOBufferCreate(LLVMModuleRef M) {
+  auto Ret = llvm::make_unique<LLVMRustThinLTOBuffer>();
+  {
+    raw_string_ostream OS(Ret->data);
+    {
+      legacy::PassManager PM;
+      PM.add(createWriteThinLTOBitcodePass(OS));
+      PM.run(*unwrap(M));
+    }
+  }
+  return Ret.release();
+}
+
+extern "C" void LLVMRustThinLTOBufferFree(LLVMRustThinLTOBuffer *Buffer) {
+  delete Buffer;
+}
+
+extern "C" const char *
+LLVMRustThinLTOBufferPtr(const LLVMRustThinLTOBuffer *Buffer) {
+  return Buffer->data.data();
+}
+
+extern "C" size_t
+LLVMRustThinLTOBufferLen(const LLVMRustThinLTOBuffer *Buffer) {
+  return Buffer->data.length();
+}
+
+// Loads all the summaries of `Modules` into one combined index and performs
+// the whole-program analysis on it: which functions to import where, which
+// copy of each linkonce/weak symbol prevails, and which symbols can be
+// internalized. Symbols in `PreservedSymbols` are always kept external.
+extern "C" LLVMRustThinLTOData *
+LLVMRustCreateThinLTOData(LLVMRustThinLTOModule *Modules, unsigned NumModules,
+                          const char **PreservedSymbols,
+                          unsigned NumPreservedSymbols) {
+  auto Ret = llvm::make_unique<LLVMRustThinLTOData>();
+
+  for (unsigned I = 0; I < NumModules; I++) {
+    LLVMRustThinLTOModule *Module = &Modules[I];
+    StringRef Buffer(Module->data, Module->len);
+    MemoryBufferRef MemBuffer(Buffer, Module->identifier);
+
+    Ret->ModuleMap[Module->identifier] = MemBuffer;
+
+    Expected<std::unique_ptr<ModuleSummaryIndex>> IndexOrErr =
+        getModuleSummaryIndex(MemBuffer);
+    if (!IndexOrErr) {
+      LLVMRustSetLastError(toString(IndexOrErr.takeError()).c_str());
+      return nullptr;
+    }
+    Ret->Index.mergeFrom(std::move(*IndexOrErr), I);
+  }
+
+  Ret->Index.collectDefinedGVSummariesPerModule(
+      Ret->ModuleToDefinedGVSummaries);
+
+  ComputeCrossModuleImport(Ret->Index, Ret->ModuleToDefinedGVSummaries,
+                           Ret->ImportLists, Ret->ExportLists);
+
+  // Pick one copy of each linkonce/weak symbol to prevail, the others are
+  // turned into `available_externally` definitions by the backends.
+  DenseMap<GlobalValue::GUID, const GlobalValueSummary *> PrevailingCopy;
+  for (auto &I : Ret->Index) {
+    if (I.second.size() > 1)
+      PrevailingCopy[I.first] = I.second.front().get();
+  }
+  auto IsPrevailing = [&](GlobalValue::GUID GUID, const GlobalValueSummary *S) {
+    const auto &Prevailing = PrevailingCopy.find(GUID);
+    if (Prevailing == PrevailingCopy.end())
+      return true;
+    return Prevailing->second == S;
+  };
+  auto RecordNewLinkage = [&](StringRef ModuleIdentifier,
+                              GlobalValue::GUID GUID,
+                              GlobalValue::LinkageTypes NewLinkage) {};
+  thinLTOResolveWeakForLinkerInIndex(Ret->Index, IsPrevailing,
+                                     RecordNewLinkage);
+
+  // Everything that is neither exported to another module nor preserved
+  // can be internalized.
+  DenseSet<GlobalValue::GUID> PreservedGUIDs;
+  for (unsigned I = 0; I < NumPreservedSymbols; I++) {
+    PreservedGUIDs.insert(GlobalValue::getGUID(PreservedSymbols[I]));
+  }
+  auto IsExported = [&](StringRef ModuleIdentifier, GlobalValue::GUID GUID) {
+    const auto &ExportList = Ret->ExportLists.find(ModuleIdentifier);
+    return (ExportList != Ret->ExportLists.end() &&
+            ExportList->second.count(GUID)) ||
+           PreservedGUIDs.count(GUID);
+  };
+  thinLTOInternalizeAndPromoteInIndex(Ret->Index, IsExported);
+
+  return Ret.release();
+}
+
+extern "C" void LLVMRustFreeThinLTOData(LLVMRustThinLTOData *Data) {
+  delete Data;
+}
+
+extern "C" LLVMModuleRef LLVMRustParseBitcodeForThinLTO(LLVMContextRef Context,
+                                                       const char *Data,
+                                                       size_t Len,
+                                                       const char *Identifier) {
+  StringRef Buffer(Data, Len);
+  MemoryBufferRef MemBuffer(Buffer, Identifier);
+  unwrap(Context)->enableDebugTypeODRUniquing();
+  Expected<std::unique_ptr<Module>> SrcOrError =
+      parseBitcodeFile(MemBuffer, *unwrap(Context));
+  if (!SrcOrError) {
+    LLVMRustSetLastError(toString(SrcOrError.takeError()).c_str());
+    return nullptr;
+  }
+  return wrap(std::move(*SrcOrError).release());
+}
+
+// Promotes the local symbols which are referenced from other modules to
+// globals with unique names.
+extern "C" bool LLVMRustPrepareThinLTORename(const LLVMRustThinLTOData *Data,
+                                             LLVMModuleRef M) {
+  Module &Mod = *unwrap(M);
+  if (renameModuleForThinLTO(Mod, Data->Index)) {
+    LLVMRustSetLastError("renameModuleForThinLTO failed");
+    return false;
+  }
+  return true;
+}
+
+// Applies the linkage chosen for linkonce/weak symbols by the analysis.
+extern "C" bool
+LLVMRustPrepareThinLTOResolveWeak(const LLVMRustThinLTOData *Data,
+                                  LLVMModuleRef M) {
+  Module &Mod = *unwrap(M);
+  const auto &DefinedGlobals =
+      Data->ModuleToDefinedGVSummaries.lookup(Mod.getModuleIdentifier());
+  thinLTOResolveWeakForLinkerModule(Mod, DefinedGlobals);
+  return true;
+}
+
+extern "C" bool
+LLVMRustPrepareThinLTOInternalize(const LLVMRustThinLTOData *Data,
+                                  LLVMModuleRef M) {
+  Module &Mod = *unwrap(M);
+  const auto &DefinedGlobals =
+      Data->ModuleToDefinedGVSummaries.lookup(Mod.getModuleIdentifier());
+  thinLTOInternalizeModule(Mod, DefinedGlobals);
+  return true;
+}
+
+// Pulls in the definitions this module imports from the other modules.
+extern "C" bool LLVMRustPrepareThinLTOImport(const LLVMRustThinLTOData *Data,
+                                             LLVMModuleRef M) {
+  Module &Mod = *unwrap(M);
+  const auto &ImportList = Data->ImportLists.lookup(Mod.getModuleIdentifier());
+  auto Loader = [&](StringRef Identifier) {
+    const auto &Memory = Data->ModuleMap.lookup(Identifier);
+    auto &Context = Mod.getContext();
+    return getLazyBitcodeModule(Memory, Context, true, true);
+  };
+  FunctionImporter Importer(Data->Index, Loader);
+  Expected<bool> Result = Importer.importFunctions(Mod, ImportList);
+  if (!Result) {
+    LLVMRustSetLastError(toString(Result.takeError()).c_str());
+    return false;
+  }
+  return true;
+}
+
+extern "C" void
+LLVMRustPassManagerBuilderPopulateThinLTOPassManager(LLVMPassManagerBuilderRef PMBR,
+                                                     LLVMPassManagerRef PMR) {
+  unwrap(PMBR)->populateThinLTOPassManager(*unwrap(PMR));
+}
+
+#else
+
+struct LLVMRustThinLTOBuffer {
+};
+
+struct LLVMRustThinLTOModule {
+};
+
+struct LLVMRustThinLTOData {
+};
+
+extern "C" bool LLVMRustThinLTOAvailable() { return false; }
+
+extern "C" LLVMRustThinLTOBuffer *LLVMRustThinLTOBufferCreate(LLVMModuleRef M) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" void LLVMRustThinLTOBufferFree(LLVMRustThinLTOBuffer *Buffer) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" const char *
+LLVMRustThinLTOBufferPtr(const LLVMRustThinLTOBuffer *Buffer) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" size_t
+LLVMRustThinLTOBufferLen(const LLVMRustThinLTOBuffer *Buffer) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" LLVMRustThinLTOData *
+LLVMRustCreateThinLTOData(LLVMRustThinLTOModule *Modules, unsigned NumModules,
+                          const char **PreservedSymbols,
+                          unsigned NumPreservedSymbols) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" void LLVMRustFreeThinLTOData(LLVMRustThinLTOData *Data) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" LLVMModuleRef LLVMRustParseBitcodeForThinLTO(LLVMContextRef Context,
+                                                       const char *Data,
+                                                       size_t Len,
+                                                       const char *Identifier) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" bool LLVMRustPrepareThinLTORename(const LLVMRustThinLTOData *Data,
+                                             LLVMModuleRef M) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" bool
+LLVMRustPrepareThinLTOResolveWeak(const LLVMRustThinLTOData *Data,
+                                  LLVMModuleRef M) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" bool
+LLVMRustPrepareThinLTOInternalize(const LLVMRustThinLTOData *Data,
+                                  LLVMModuleRef M) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" bool LLVMRustPrepareThinLTOImport(const LLVMRustThinLTOData *Data,
+                                             LLVMModuleRef M) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" void
+LLVMRustPassManagerBuilderPopulateThinLTOPassManager(LLVMPassManagerBuilderRef PMBR,
+                                                     LLVMPassManagerRef PMR) {
+  report_fatal_error("ThinLTO not available");
+}
+
+#endif // LLVM_VERSION_GE(4, 0)
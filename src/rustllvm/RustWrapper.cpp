@@ -874,6 +874,18 @@ LLVMRustUnpackInlineAsmDiagnostic(LLVMDiagnosticInfoRef DI, unsigned *CookieOut,
   *InstructionOut = wrap(IA->getInstruction());
 }
 
+extern "C" void
+LLVMRustUnpackStackSizeDiagnostic(LLVMDiagnosticInfoRef DI,
+                                  LLVMValueRef *FunctionOut,
+                                  uint64_t *StackSizeOut) {
+  // Undefined to call this not on a stack size diagnostic!
+  llvm::DiagnosticInfoStackSize *SS =
+      static_cast<llvm::DiagnosticInfoStackSize *>(unwrap(DI));
+
+  *FunctionOut = wrap(&SS->getFunction());
+  *StackSizeOut = SS->getStackSize();
+}
+
 extern "C" void LLVMRustWriteDiagnosticInfoToString(LLVMDiagnosticInfoRef DI,
                                                     RustStringRef Str) {
   RawRustStringOstream OS(Str);
@@ -0,0 +1,44 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "lib"]
+
+//! ```
+//! broken::record_exe();
+//! ```
+//!
+//! ```
+//! broken::record_exe();
+//! let x: u32 = "not a number";
+//! ```
+//!
+//! ```
+//! broken::record_exe();
+//! assert_eq!(1 + 1, 2);
+//! ```
+//!
+//! ```
+//! broken::record_exe();
+//! ```
+
+/// Appends the path of the running executable to the file named by
+/// `DOCTEST_EXES`, so the Makefile can tell which tests shared one.
+#[doc(hidden)]
+pub fn record_exe() {
+    use std::env;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    if let Some(log) = env::var_os("DOCTEST_EXES") {
+        let exe = env::current_exe().unwrap();
+        let mut f = OpenOptions::new().append(true).create(true).open(log).unwrap();
+        writeln!(f, "{}", exe.display()).unwrap();
+    }
+}
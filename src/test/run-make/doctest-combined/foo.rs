@@ -0,0 +1,78 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+#![crate_type = "lib"]
+
+//! ```
+//! foo::record_exe();
+//! assert_eq!(foo::answer(), 42);
+//! ```
+//!
+//! ```
+//! use foo::answer;
+//! foo::record_exe();
+//! fn twice(x: u32) -> u32 { x * 2 }
+//! assert_eq!(twice(answer()), 84);
+//! ```
+//!
+//! ```should_panic
+//! assert_eq!(foo::answer(), 0);
+//! ```
+//!
+//! ```no_run
+//! loop {}
+//! ```
+//!
+//! ```compile_fail
+//! let x: u32 = "not a number";
+//! ```
+//!
+//! ```
+//! #![allow(unused_variables)]
+//! let unused = foo::answer();
+//! ```
+//!
+//! ```
+//! extern crate foo;
+//!
+//! fn main() {
+//!     foo::record_exe();
+//!     assert_eq!(foo::answer(), 42);
+//! }
+//! ```
+//!
+//! ```ignore
+//! this isn't rust
+//! ```
+
+/// The answer.
+///
+/// ```
+/// // Items in one test don't clash with those in another.
+/// fn twice(x: u32) -> u32 { x + x }
+/// assert_eq!(twice(foo::answer()), 84);
+/// ```
+pub fn answer() -> u32 { 42 }
+
+/// Appends the path of the running executable to the file named by
+/// `DOCTEST_EXES`, so the Makefile can tell which tests shared one.
+#[doc(hidden)]
+pub fn record_exe() {
+    use std::env;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    if let Some(log) = env::var_os("DOCTEST_EXES") {
+        let exe = env::current_exe().unwrap();
+        let mut f = OpenOptions::new().append(true).create(true).open(log).unwrap();
+        writeln!(f, "{}", exe.display()).unwrap();
+    }
+}
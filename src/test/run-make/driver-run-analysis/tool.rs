@@ -0,0 +1,70 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// A tool which type checks a crate that only exists in memory and reports the
+// types of its functions.
+
+#![feature(rustc_private)]
+
+extern crate rustc;
+extern crate rustc_driver;
+
+use rustc::hir;
+use rustc_driver::analysis::{self, InMemoryFileLoader};
+use std::env;
+use std::path::PathBuf;
+
+const SOURCE: &'static str = "
+pub fn answer() -> u32 { 42 }
+pub fn double(x: u64) -> u64 { x * 2 }
+";
+
+fn main() {
+    // `$(RUSTC)` starts with the rpath environment, then the path to rustc.
+    // Remove two segments from that path to get the sysroot.
+    let mut sysroot = PathBuf::from(env::args().nth(2).unwrap());
+    sysroot.pop();
+    sysroot.pop();
+
+    let mut loader = InMemoryFileLoader::new();
+    loader.add_file("virtual.rs", SOURCE.to_string());
+
+    let args: Vec<String> = vec!["tool".to_string(),
+                                 "--sysroot".to_string(),
+                                 sysroot.to_str().unwrap().to_string(),
+                                 "--crate-type".to_string(),
+                                 "lib".to_string(),
+                                 "--out-dir".to_string(),
+                                 env::var("TMPDIR").unwrap(),
+                                 "virtual.rs".to_string()];
+
+    let (result, fns) = analysis::run_analysis(&args, Some(Box::new(loader)), |tcx, _| {
+        let mut fns = vec![];
+        for item in tcx.hir.krate().items.values() {
+            if let hir::ItemFn(..) = item.node {
+                let def_id = tcx.hir.local_def_id(item.id);
+                fns.push(format!("{}: {}", item.name, tcx.item_type(def_id)));
+            }
+        }
+        fns.sort();
+        fns
+    });
+    assert!(result.is_ok());
+    for f in fns.unwrap() {
+        println!("{}", f);
+    }
+
+    let mut loader = InMemoryFileLoader::new();
+    loader.add_file("virtual.rs", "pub fn broken() -> u32 { \"\" }".to_string());
+    let (result, output) = analysis::run_analysis(&args, Some(Box::new(loader)), |_, _| ());
+    assert!(result.is_err());
+    assert!(output.is_none());
+}
@@ -0,0 +1,25 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Changes the compiler version recorded in the metadata file it's given from
+// `rustc ...` to `rusty ...`, as if another compiler had written it.
+
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+
+fn main() {
+    let path = env::args().nth(1).unwrap();
+    let mut bytes = vec![];
+    File::open(&path).unwrap().read_to_end(&mut bytes).unwrap();
+    let pos = bytes.windows(6).position(|w| w == b"rustc ").unwrap();
+    bytes[pos + 4] = b'y';
+    File::create(&path).unwrap().write_all(&bytes).unwrap();
+}
@@ -0,0 +1,27 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "rlib"]
+
+pub struct Counter {
+    pub count: u32,
+}
+
+impl Counter {
+    pub fn bump(&mut self) -> u32 {
+        self.count += 1;
+        self.count
+    }
+}
+
+#[macro_export]
+macro_rules! counter {
+    () => ($crate::Counter { count: 0 })
+}
@@ -0,0 +1,26 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "rlib"]
+
+#[inline(never)]
+pub fn big_frame(n: usize) -> u8 {
+    let mut buf = [0u8; 4096];
+    for i in 0..buf.len() {
+        buf[i] = (i * n) as u8;
+    }
+    black_box(&mut buf);
+    buf[n % buf.len()]
+}
+
+#[inline(never)]
+fn black_box(buf: &mut [u8]) {
+    unsafe { ::std::ptr::write_volatile(&mut buf[0], 1) }
+}
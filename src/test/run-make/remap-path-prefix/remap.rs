@@ -0,0 +1,24 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "rlib"]
+
+// Both the panic location and `file!()` end up in the output.
+pub fn check(value: u32) -> &'static str {
+    if value == 0 {
+        panic!("zero");
+    }
+    file!()
+}
+
+// Instantiated downstream, so it is serialized in the crate's metadata.
+pub fn generic<T: Clone>(value: &T) -> T {
+    value.clone()
+}
@@ -0,0 +1,38 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+#![crate_type = "lib"]
+
+/// ```rust,ignroe
+/// this_is_not_run();
+/// ```
+pub fn misspelled() {}
+
+/// Returns a Vec<T>.
+pub fn html() {}
+
+/// Unbalanced </div>.
+pub fn end_tag() {}
+
+/// Never shown.
+#[doc(hidden)]
+pub fn hidden() {}
+
+/// Fine: `Vec<T>`, <b>bold</b> and
+///
+/// ```
+/// let x = 1;
+/// ```
+pub fn fine() {}
+
+/// ```
+/// let x = 1;
+pub fn unclosed() {}
@@ -0,0 +1,19 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Links to [`Missing`], [some text](missing::path) and [`Exists`].
+//!
+//! Code isn't looked at: `[NotALink]` and `[text](in_span::Nope)`.
+//!
+//! ```
+//! // [text](in_block::Nope)
+//! ```
+
+pub struct Exists;
@@ -0,0 +1,28 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+#![crate_name = "foo"]
+
+/// A documented struct.
+pub struct Wrapper<T: Clone> {
+    pub inner: T,
+}
+
+impl<T> Wrapper<T> where T: Clone {
+    /// Makes a new `Wrapper`.
+    #[deprecated(since = "1.0.0", note = "use something else")]
+    pub fn new(inner: T) -> Wrapper<T> {
+        Wrapper { inner: inner }
+    }
+}
+
+#[doc(hidden)]
+pub struct Hidden;
@@ -0,0 +1,86 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// A tool which runs save-analysis on a crate and prints the records it is
+// handed, without any save-analysis files being written.
+
+#![feature(rustc_private)]
+
+extern crate getopts;
+extern crate rustc;
+extern crate rustc_driver;
+extern crate rustc_resolve;
+extern crate rustc_save_analysis;
+
+use rustc::session::Session;
+use rustc_driver::{run_compiler, Compilation, CompilerCalls};
+use rustc_driver::driver::CompileController;
+use rustc_resolve::MakeGlobMap;
+use rustc_save_analysis::process_crate_with_callback;
+use rustc_save_analysis::records::{Record, DefKind};
+use std::env;
+use std::path::PathBuf;
+
+struct Calls;
+
+impl<'a> CompilerCalls<'a> for Calls {
+    fn build_controller(&mut self, _: &Session, _: &getopts::Matches) -> CompileController<'a> {
+        let mut control = CompileController::basic();
+        control.make_glob_map = MakeGlobMap::Yes;
+        control.after_analysis.stop = Compilation::Stop;
+        control.after_analysis.callback = Box::new(|state| {
+            let mut records = vec![];
+            process_crate_with_callback(state.tcx.unwrap(),
+                                        state.expanded_crate.unwrap(),
+                                        state.analysis.unwrap(),
+                                        state.crate_name.unwrap(),
+                                        &mut |record| records.push(record));
+            let mut bump_id = None;
+            for record in &records {
+                match *record {
+                    Record::Prelude(ref prelude) => println!("crate: {}", prelude.crate_name),
+                    Record::Def(ref def) => {
+                        println!("def: {:?} {}", def.kind, def.qualname);
+                        if let DefKind::Method = def.kind {
+                            bump_id = Some(def.id);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            // Both calls of `bump` in `twice`.
+            let bump_id = bump_id.unwrap();
+            let calls = records.iter().filter(|record| match **record {
+                Record::Ref(ref r) => r.ref_id.krate == bump_id.krate &&
+                                      r.ref_id.index == bump_id.index,
+                _ => false,
+            }).count();
+            println!("calls of bump: {}", calls);
+        });
+        control
+    }
+}
+
+fn main() {
+    // `$(RUSTC)` starts with the rpath environment, then the path to rustc.
+    // Remove two segments from that path to get the sysroot.
+    let mut sysroot = PathBuf::from(env::args().nth(2).unwrap());
+    sysroot.pop();
+    sysroot.pop();
+
+    let args: Vec<String> = vec!["tool".to_string(),
+                                 "--sysroot".to_string(),
+                                 sysroot.to_str().unwrap().to_string(),
+                                 "--out-dir".to_string(),
+                                 env::var("TMPDIR").unwrap(),
+                                 "foo.rs".to_string()];
+    let (result, _) = run_compiler(&args, &mut Calls, None, None);
+    assert!(result.is_ok());
+}
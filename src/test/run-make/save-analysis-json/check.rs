@@ -0,0 +1,56 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Checks that both calls to `area` in foo.rs refer to the method of the impl
+// rather than to the trait's declaration of it.
+
+#![feature(rustc_private)]
+
+extern crate serialize;
+
+use serialize::json::{self, Json};
+use std::env;
+use std::fs::File;
+use std::io::Read;
+
+fn main() {
+    let path = env::args().nth(1).unwrap();
+    let mut contents = String::new();
+    File::open(&path).unwrap().read_to_string(&mut contents).unwrap();
+    let analysis = json::from_str(&contents).unwrap();
+
+    let defs = analysis.find("defs").unwrap().as_array().unwrap();
+    let areas = defs.iter().filter(|def| {
+        def.find("kind").unwrap().as_string() == Some("Method") &&
+        def.find("name").unwrap().as_string() == Some("area")
+    }).collect::<Vec<_>>();
+    assert_eq!(areas.len(), 2);
+    // The qualname of the impl's method is `<Square as Shape>::area`.
+    let (impl_method, trait_method) = if is_impl_method(areas[0]) {
+        (areas[0], areas[1])
+    } else {
+        (areas[1], areas[0])
+    };
+    assert!(is_impl_method(impl_method));
+    assert!(!is_impl_method(trait_method));
+
+    let refs = analysis.find("refs").unwrap().as_array().unwrap();
+    let refs_to = |def: &Json| {
+        let id = def.find("id").unwrap();
+        refs.iter().filter(|r| r.find("ref_id") == Some(id)).count()
+    };
+    // `s.area()` and `Shape::area(&s)`.
+    assert_eq!(refs_to(impl_method), 2);
+    assert_eq!(refs_to(trait_method), 0);
+}
+
+fn is_impl_method(def: &Json) -> bool {
+    def.find("qualname").unwrap().as_string().unwrap().starts_with("<Square as ")
+}
@@ -0,0 +1,39 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "lib"]
+
+pub trait Named {
+    fn name(&self) -> &'static str;
+}
+
+pub trait Shape: Named {
+    fn area(&self) -> u32;
+}
+
+pub struct Square(pub u32);
+
+impl Named for Square {
+    fn name(&self) -> &'static str { "square" }
+}
+
+impl Shape for Square {
+    fn area(&self) -> u32 { self.0 * self.0 }
+}
+
+pub fn total() -> u32 {
+    let s = Square(3);
+    let v = vec![1u32, 2];
+    s.area() + v.len() as u32 + Shape::area(&s) + helper()
+}
+
+fn helper() -> u32 {
+    1
+}
@@ -0,0 +1,27 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate lib;
+
+mod a {
+    pub fn values() -> Vec<u32> {
+        (1..5).collect()
+    }
+}
+
+mod b {
+    pub fn check(total: u32) {
+        assert_eq!(total, 10);
+    }
+}
+
+fn main() {
+    b::check(lib::sum(&a::values()));
+}
@@ -0,0 +1,70 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+
+#![crate_name = "foo"]
+
+use std::fmt;
+
+// @count foo/trait.Describe.html '//*[@id="implementors-list"]//code' 1
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+impl<T: fmt::Display> Describe for T {
+    fn describe(&self) -> String {
+        self.to_string()
+    }
+}
+
+// @has foo/struct.Plain.html
+// @has - '//*[@id="synthetic-implementations-list"]//code' 'impl Send for Plain'
+// @has - '//*[@id="synthetic-implementations-list"]//code' 'impl Sync for Plain'
+// @!has - '//*[@id="blanket-implementations-list"]//code' 'Describe for T'
+// @!has - '//*[@id="blanket-implementations-list"]//code' 'ToString for T'
+pub struct Plain;
+
+// @has foo/struct.Shown.html
+// @has - '//*[@id="implementations-list"]//code' 'impl Display for Shown'
+// @has - '//*[@id="blanket-implementations-list"]//code' 'impl<T: Display> Describe for T'
+// @has - '//*[@id="blanket-implementations-list"]//code' 'ToString for T'
+pub struct Shown;
+
+impl fmt::Display for Shown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("shown")
+    }
+}
+
+// @has foo/struct.NotSend.html
+// @has - '//*[@id="synthetic-implementations-list"]//code' 'impl !Send for NotSend'
+// @has - '//*[@id="synthetic-implementations-list"]//code' 'impl !Sync for NotSend'
+pub struct NotSend(*const u8);
+
+// @has foo/struct.Manual.html
+// @has - '//*[@id="implementations-list"]//code' 'impl Send for Manual'
+// @!has - '//*[@id="synthetic-implementations-list"]//code' 'Send for Manual'
+// @has - '//*[@id="synthetic-implementations-list"]//code' 'impl !Sync for Manual'
+pub struct Manual(*const u8);
+
+unsafe impl Send for Manual {}
+
+// @has foo/struct.Wrapper.html
+// @has - '//*[@id="synthetic-implementations-list"]//code' 'impl<T: Send> Send for Wrapper<T>'
+// @has - '//*[@id="synthetic-implementations-list"]//code' 'impl<T: Sync> Sync for Wrapper<T>'
+pub struct Wrapper<T>(T);
+
+// @has foo/struct.Pair.html
+// @has - '//*[@id="synthetic-implementations-list"]//code' 'impl<A: Send, B> Send for Pair<A, B>'
+pub struct Pair<A, B> {
+    a: A,
+    f: fn(B),
+}
@@ -0,0 +1,50 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// compile-flags: --document-private-items
+
+#![crate_name = "foo"]
+
+// @has foo/index.html
+// @has - '//a[@href="fn.public.html"]' 'public'
+// @has - '//a[@href="fn.private.html"]' 'private'
+// @has - '//a[@href="struct.Hidden.html"]' 'Hidden'
+// @!has - '//a[@href="struct.ReallyHidden.html"]' 'ReallyHidden'
+// @!has - 'use std::fmt'
+// @count - '//*[@class="private"]' 3
+
+// @has foo/fn.public.html
+// @!has - '//h1//*[@class="private"]' ''
+/// Public function.
+pub fn public() {}
+
+// @has foo/fn.private.html
+// @has - '//h1//*[@class="private"]' '🔒'
+// @has - '//*[@class="docblock"]' 'Private function with unindented docs.'
+///   Private function
+///   with unindented docs.
+fn private() {}
+
+// @has foo/struct.Hidden.html
+struct Hidden;
+
+// @!has foo/struct.ReallyHidden.html
+#[doc(hidden)]
+pub struct ReallyHidden;
+
+// @has foo/inner/index.html
+// @has foo/inner/fn.in_private_mod.html
+mod inner {
+    pub fn in_private_mod() {}
+}
+
+#[allow(unused_imports)]
+use std::fmt;
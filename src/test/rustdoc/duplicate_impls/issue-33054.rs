@@ -11,7 +11,10 @@
 // @has issue_33054/impls/struct.Foo.html
 // @has - '//code' 'impl Foo'
 // @has - '//code' 'impl Bar for Foo'
-// @count - '//*[@class="impl"]' 2
+// @count - '//*[@id="method.new"]' 1
+// @count - '//*[@id="implementations-list"]/*[@class="impl"]' 1
+// @has - '//*[@id="synthetic-implementations-list"]//code' 'impl Send for Foo'
+// @count - '//*[@id="synthetic-implementations-list"]/*[@class="impl"]' 2
 // @has issue_33054/impls/bar/trait.Bar.html
 // @has - '//code' 'impl Bar for Foo'
 // @count - '//*[@class="struct"]' 1
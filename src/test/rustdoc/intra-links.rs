@@ -0,0 +1,65 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// @has intra_links/index.html
+// @has - '//a/@href' '../intra_links/struct.ThisType.html'
+// @has - '//a/@href' '../intra_links/struct.ThisType.html#method.this_method'
+// @has - '//a/@href' '../intra_links/enum.ThisEnum.html#variant.ThisVariant'
+// @has - '//a/@href' '../intra_links/trait.ThisTrait.html#tymethod.this_method'
+// @has - '//a/@href' '../intra_links/fn.this_function.html'
+// @has - '//a/@href' '../intra_links/submodule/index.html'
+// @has - '//a/@href' '../intra_links/macro.this_macro.html'
+// @has - '//code' '[text](ThisType)'
+//! Links to [`ThisType`], [a method](ThisType::this_method), [`ThisEnum::ThisVariant`],
+//! [`ThisTrait::this_method`], [`this_function()`], [`submodule`] and [`this_macro!`].
+//!
+//! [Some text](http://example.com), `[NotALink]` and `[text](ThisType)` are left alone.
+
+#[macro_export]
+macro_rules! this_macro {
+    () => {};
+}
+
+pub struct ThisType;
+
+impl ThisType {
+    pub fn this_method() {}
+}
+
+pub enum ThisEnum { ThisVariant }
+
+pub trait ThisTrait {
+    fn this_method();
+}
+
+pub fn this_function() {}
+
+pub mod submodule {
+    // @has intra_links/submodule/struct.Inner.html '//a/@href' \
+    //      '../../intra_links/struct.ThisType.html#method.this_method'
+    // @has - '//a/@href' '../../intra_links/submodule/fn.sibling.html'
+    /// Refers to [`super::ThisType::this_method`] and [`sibling`].
+    pub struct Inner;
+
+    use super::ThisEnum as Renamed;
+
+    // @has intra_links/submodule/fn.sibling.html '//a/@href' \
+    //      '../../intra_links/enum.ThisEnum.html'
+    /// Goes through an import to [`Renamed`].
+    pub fn sibling() {}
+}
+
+// @has intra_links/struct.ThisType.html '//a/@href' \
+//      '../intra_links/struct.ThisType.html#method.this_method'
+/// See [`Self::this_method`].
+impl ThisType {
+    pub fn other() {}
+}
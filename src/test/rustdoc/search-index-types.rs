@@ -0,0 +1,27 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+
+#![crate_name = "foo"]
+
+pub struct Bag<T>(Vec<T>);
+
+impl<T> Bag<T> {
+    // `Self` is indexed as the type of the impl.
+    // @has 'search-index.js' '"inputs":[{"generics":[{"generic":true,"name":"t"}],"name":"bag"}],"output":{"name":"usize"}'
+    pub fn len(&self) -> usize { self.0.len() }
+}
+
+// @has - '"inputs":[{"name":"str"}],"output":{"generics":[{"name":"char"}],"name":"option"}'
+pub fn first_char(s: &str) -> Option<char> { s.chars().next() }
+
+// @has - '"inputs":[{"generics":[{"name":"u8"}],"name":"slice"},{"generics":[{"name":"u32"},{"name":"bool"}],"name":"tuple"}]'
+pub fn slices(_: &[u8], _: (u32, bool)) {}
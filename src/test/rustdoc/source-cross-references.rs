@@ -0,0 +1,45 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_name = "foo"]
+
+pub struct Point {
+    pub x: i32,
+}
+
+pub fn origin() -> Point {
+    Point { x: 0 }
+}
+
+pub fn norm(p: &Point) -> i32 {
+    let x = p.x;
+    x.abs()
+}
+
+pub fn points() -> Vec<Point> {
+    let mut points = Vec::new();
+    points.push(origin());
+    points
+}
+
+// @has src/foo/source-cross-references.rs.html
+// @has - '//a[@href="source-cross-references.rs.html#13"]' 'Point'
+// @has - '//a[@href="source-cross-references.rs.html#14"]' 'x'
+// @has - '//a[@href="source-cross-references.rs.html#17"]' 'origin'
+// @has - '//a[@href="source-cross-references.rs.html#22"]' 'x'
+// @has - '//a/@href' 'references.html#ref.'
+// @has - '//a/@href' 'struct.Vec.html'
+
+// @has src/foo/references.html
+// @has - '//h3/code' 'Point'
+// @has - '//h3/a[@href="source-cross-references.rs.html#17"]' 'source-cross-references.rs:17'
+// @has - '//ul[@class="references"]/li/a[@href="source-cross-references.rs.html#28"]' \
+//        'source-cross-references.rs:28'
+// @has - '//ul[@class="references"]/li/code' 'points.push(origin());'
@@ -185,6 +185,10 @@ pub struct Config {
     // Print one character per test instead of one line
     pub quiet: bool,
 
+    // Whether the profiler runtime (`profiler_builtins`) was built for the
+    // target, so tests using `-C profile-generate` can run
+    pub profiler_support: bool,
+
     // Configuration for various run-make tests frobbing things like C compilers
     // or querying about various LLVM component information.
     pub cc: String,